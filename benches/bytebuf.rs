@@ -0,0 +1,203 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use voidpoc::ByteBuf;
+use voidpoc::frame::{FrameDecoder, FrameEncoder};
+
+// Chunk Data packets are a few kilobytes for a flat world and up to a few
+// hundred kilobytes for a full column of random blocks
+const PAYLOAD_SIZES: [usize; 3] = [16 * 1024, 64 * 1024, 256 * 1024];
+const FRAMES_PER_READ: usize = 8;
+
+/// The previous `Vec` based buffer, kept to compare against.
+mod legacy {
+    pub struct ByteBuf {
+        pub data: Vec<u8>,
+        pub read_offset: usize,
+    }
+
+    impl ByteBuf {
+        pub fn new() -> ByteBuf {
+            ByteBuf {
+                data: Vec::new(),
+                read_offset: 0,
+            }
+        }
+
+        pub fn write_u8(&mut self, val: u8) {
+            self.data.push(val);
+        }
+
+        pub fn write_varint(&mut self, mut val: i32) {
+            loop {
+                if (val & !0x7F) == 0 {
+                    self.write_u8(val as u8);
+                    return;
+                }
+                self.write_u8(((val & 0x7F) | 0x80) as u8);
+                val = ((val as u32) >> 7) as i32;
+            }
+        }
+
+        pub fn write_buf(&mut self, buf: &[u8]) {
+            self.data.extend_from_slice(buf);
+        }
+
+        pub fn read_all(&mut self) -> Vec<u8> {
+            let buf = self.data.clone();
+            self.data.clear();
+            self.read_offset = 0;
+            buf
+        }
+    }
+
+    /// Packet buffer, then frame body, then frame, as `send_packet` used to do.
+    pub fn encode(sections: &[u8]) -> Vec<u8> {
+        let mut content_buf = ByteBuf::new();
+        content_buf.write_varint(0x27);
+        content_buf.write_varint(sections.len() as i32);
+        content_buf.write_buf(sections);
+
+        let mut body = ByteBuf::new();
+        body.write_buf(&content_buf.data);
+
+        let mut buf = ByteBuf::new();
+        buf.write_varint(body.data.len() as i32);
+        buf.write_buf(&body.data);
+        buf.read_all()
+    }
+
+    pub struct FrameDecoder {
+        buf: ByteBuf,
+    }
+
+    impl FrameDecoder {
+        pub fn new() -> FrameDecoder {
+            FrameDecoder { buf: ByteBuf::new() }
+        }
+
+        pub fn feed(&mut self, data: &[u8]) {
+            if self.buf.read_offset > 0 {
+                self.buf.data.drain(..self.buf.read_offset);
+                self.buf.read_offset = 0;
+            }
+            self.buf.write_buf(data);
+        }
+
+        pub fn next_frame(&mut self) -> Option<ByteBuf> {
+            let pending = &self.buf.data[self.buf.read_offset..];
+            let (len, header_len) = peek_varint(pending)?;
+            if pending.len() < header_len + len {
+                return None;
+            }
+            let mut frame = ByteBuf::new();
+            frame.write_buf(&pending[header_len..header_len + len]);
+            self.buf.read_offset += header_len + len;
+            Some(frame)
+        }
+    }
+
+    fn peek_varint(data: &[u8]) -> Option<(usize, usize)> {
+        let mut value = 0;
+        for (i, byte) in data.iter().take(5).enumerate() {
+            value |= (*byte as usize & 0x7F) << (7 * i);
+            if (*byte & 0x80) == 0 {
+                return Some((value, i + 1));
+            }
+        }
+        None
+    }
+}
+
+fn encode(encoder: &FrameEncoder, sections: &[u8]) -> bytes::BytesMut {
+    let mut buf = encoder.begin_with_capacity(sections.len() + 10);
+    buf.write_varint(0x27);
+    buf.write_byte_array(sections);
+    encoder.finish(buf)
+}
+
+/// Serialized chunk sections, written as a single byte array like Chunk Data does.
+fn sections(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i * 31) as u8).collect()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    let encoder = FrameEncoder::new();
+    for size in PAYLOAD_SIZES {
+        let sections = sections(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("legacy", size), &sections, |b, sections| {
+            b.iter(|| black_box(legacy::encode(sections)))
+        });
+        group.bench_with_input(BenchmarkId::new("bytebuf", size), &sections, |b, sections| {
+            b.iter(|| black_box(encode(&encoder, sections)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for size in PAYLOAD_SIZES {
+        // Several frames arriving in a single read
+        let encoder = FrameEncoder::new();
+        let sections = sections(size);
+        let mut stream = Vec::new();
+        for _ in 0..FRAMES_PER_READ {
+            stream.extend_from_slice(&encode(&encoder, &sections));
+        }
+
+        group.throughput(Throughput::Bytes((size * FRAMES_PER_READ) as u64));
+        group.bench_with_input(BenchmarkId::new("legacy", size), &stream, |b, stream| {
+            b.iter(|| {
+                let mut decoder = legacy::FrameDecoder::new();
+                decoder.feed(stream);
+                while let Some(frame) = decoder.next_frame() {
+                    black_box(&frame.data[frame.read_offset..]);
+                }
+                // Next read, drops the frames handed out
+                decoder.feed(&[]);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("bytebuf", size), &stream, |b, stream| {
+            b.iter(|| {
+                let mut decoder = FrameDecoder::new();
+                decoder.feed(stream);
+                while let Some(frame) = decoder.next_frame().unwrap() {
+                    black_box(frame.as_slice());
+                }
+                decoder.feed(&[]);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_pop_front(c: &mut Criterion) {
+    // `pop_front_i8` used to reallocate the remaining bytes on every call
+    let mut group = c.benchmark_group("pop_front");
+    let data: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            let mut data = data.clone();
+            while !data.is_empty() {
+                black_box(data[0] as i8);
+                data = data[1..].to_vec();
+            }
+        })
+    });
+    group.bench_function("bytebuf", |b| {
+        b.iter(|| {
+            let mut buf = ByteBuf::with_capacity(data.len());
+            buf.write_buf(&data);
+            while let Ok(val) = buf.pop_front_i8() {
+                black_box(val);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode, bench_pop_front);
+criterion_main!(benches);
@@ -0,0 +1,109 @@
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::Path;
+use std::{env, fs};
+
+// Packet ids are generated from the `packets.json` report of the vanilla data
+// generator, one per supported version, oldest first:
+// java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports
+const VERSIONS: [&str; 3] = ["1.21.1", "1.21.3", "1.21.4"];
+
+const STATES: [(&str, &str); 5] = [
+    ("handshake", "Handshake"),
+    ("status", "Status"),
+    ("login", "Login"),
+    ("configuration", "Configuration"),
+    ("play", "Play"),
+];
+const BOUNDS: [(&str, &str); 2] = [("clientbound", "Client"), ("serverbound", "Server")];
+
+fn main() {
+    let mut out = String::from("// Generated by build.rs from data/<version>/packets.json\n");
+    for version in VERSIONS {
+        let path = format!("data/{}/packets.json", version);
+        println!("cargo:rerun-if-changed={}", path);
+
+        let report = fs::read_to_string(&path).expect("Failed to read the packets report");
+        let report: Value = serde_json::from_str(&report).expect("Failed to parse the packets report");
+        write_version(&mut out, version, &report);
+    }
+
+    // The latest version is also reachable without its module, as `ids::play::...`
+    let latest = VERSIONS[VERSIONS.len() - 1];
+    writeln!(out, "\npub use {}::*;", module_name(latest)).unwrap();
+
+    writeln!(out, "\nfn names(version: ProtocolVersion, state: PacketStatus, bound: PacketBound) -> &'static [&'static str] {{").unwrap();
+    writeln!(out, "    match (version, state, bound) {{").unwrap();
+    for version in VERSIONS {
+        for (state, state_variant) in STATES {
+            for (bound, bound_variant) in BOUNDS {
+                writeln!(
+                    out,
+                    "        (ProtocolVersion::{}, PacketStatus::{}, PacketBound::{}) => {}::{}::{}::NAMES,",
+                    variant_name(version),
+                    state_variant,
+                    bound_variant,
+                    module_name(version),
+                    state,
+                    bound
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(out, "    }}\n}}").unwrap();
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("packet_ids.rs");
+    fs::write(dest, out).expect("Failed to write the packet ids");
+}
+
+fn write_version(out: &mut String, version: &str, report: &Value) {
+    writeln!(out, "\npub mod {} {{", module_name(version)).unwrap();
+    for (state, _) in STATES {
+        writeln!(out, "    pub mod {} {{", state).unwrap();
+        for (bound, _) in BOUNDS {
+            let names = packet_names(&report[state][bound], version, state, bound);
+            writeln!(out, "        pub mod {} {{", bound).unwrap();
+            for (id, name) in names.iter().enumerate() {
+                writeln!(out, "            pub const {}: crate::PacketType = 0x{:02X};", const_name(name), id).unwrap();
+            }
+            writeln!(out, "            pub(crate) const NAMES: &[&str] = &{:?};", names).unwrap();
+            writeln!(out, "        }}").unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+/// Resource names of a state and direction, indexed by packet id.
+fn packet_names(packets: &Value, version: &str, state: &str, bound: &str) -> Vec<String> {
+    let Some(packets) = packets.as_object() else {
+        return Vec::new();
+    };
+
+    let mut names = vec![None; packets.len()];
+    for (name, packet) in packets {
+        let id = packet["protocol_id"].as_u64().expect("protocol_id must be a number") as usize;
+        match names.get_mut(id) {
+            Some(slot @ None) => *slot = Some(name.clone()),
+            _ => panic!("{} {} {} packet ids are not contiguous at {} ({})", version, state, bound, id, name),
+        }
+    }
+    names.into_iter().map(|name| name.unwrap()).collect()
+}
+
+/// `1.21.4` becomes `v1_21_4`.
+fn module_name(version: &str) -> String {
+    format!("v{}", version.replace('.', "_"))
+}
+
+/// `1.21.4` becomes `V1_21_4`, its `ProtocolVersion` variant.
+fn variant_name(version: &str) -> String {
+    format!("V{}", version.replace('.', "_"))
+}
+
+/// `minecraft:keep_alive` becomes `KEEP_ALIVE`.
+fn const_name(name: &str) -> String {
+    let path = name.strip_prefix("minecraft:").unwrap_or(name);
+    path.replace(['/', '.', '-', ':'], "_").to_uppercase()
+}
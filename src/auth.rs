@@ -0,0 +1,175 @@
+use crate::Property;
+use md5::{Digest, Md5};
+use serde::Deserialize;
+use std::fmt;
+use uuid::Uuid;
+
+// AUTHENTICATION //
+
+#[derive(Debug)]
+pub enum AuthError {
+    Http(reqwest::Error),
+    NotAuthenticated,
+    InvalidProfile(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Http(err) => write!(f, "session server request failed: {}", err),
+            AuthError::NotAuthenticated => write!(f, "player has not joined through the session server"),
+            AuthError::InvalidProfile(err) => write!(f, "invalid profile from session server: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+#[derive(Deserialize, Debug)]
+struct ProfileProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Profile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    properties: Vec<ProfileProperty>,
+}
+
+/// Profile of an authenticated player, as returned by the session server.
+#[derive(Debug)]
+pub struct GameProfile {
+    pub uuid: Uuid,
+    pub username: String,
+    pub properties: Vec<Property>,
+}
+
+/// UUID given to a player in offline mode, a name-based (v3) UUID of
+/// `OfflinePlayer:<name>` computed like Java's `UUID.nameUUIDFromBytes`.
+pub fn offline_uuid(username: &str) -> Uuid {
+    let digest: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", username).as_bytes()).into();
+    uuid::Builder::from_md5_bytes(digest).into_uuid()
+}
+
+/// Client of a Mojang compatible session server.
+pub struct SessionService {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SessionService {
+    pub fn new(url: &str) -> SessionService {
+        SessionService {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Checks that `username` joined the server identified by `server_hash`.
+    pub async fn has_joined(&self, username: &str, server_hash: &str) -> Result<GameProfile, AuthError> {
+        let response = self
+            .client
+            .get(format!("{}/session/minecraft/hasJoined", self.url))
+            .query(&[("username", username), ("serverId", server_hash)])
+            .send()
+            .await
+            .map_err(AuthError::Http)?;
+
+        // The session server answers 204 No Content when the player is unknown
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Err(AuthError::NotAuthenticated);
+        }
+        let profile: Profile = response
+            .error_for_status()
+            .map_err(AuthError::Http)?
+            .json()
+            .await
+            .map_err(AuthError::Http)?;
+
+        let uuid = Uuid::parse_str(&profile.id).map_err(|e| AuthError::InvalidProfile(e.to_string()))?;
+        Ok(GameProfile {
+            uuid,
+            username: profile.name,
+            properties: profile
+                .properties
+                .into_iter()
+                .map(|p| Property {
+                    name: p.name,
+                    value: p.value,
+                    signature: p.signature,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::{AuthError, SessionService, offline_uuid};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves a single HTTP request, returning the request line.
+    async fn mock_session_server(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap().lines().next().unwrap().to_string()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    pub fn test_auth_offline_uuid() {
+        // Same values as a vanilla offline-mode server
+        assert_eq!(offline_uuid("Notch").to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+        assert_eq!(offline_uuid("jeb_").to_string(), "a762f560-4fce-3236-812a-b80efff0b62b");
+        assert_eq!(offline_uuid("Notch"), offline_uuid("Notch"));
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+    }
+
+    #[tokio::test]
+    pub async fn test_auth_has_joined() {
+        let (url, server) = mock_session_server(
+            "200 OK",
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"dGV4","signature":"c2ln"}]}"#,
+        ).await;
+
+        let profile = SessionService::new(&url).has_joined("Notch", "-7c9d5b").await.unwrap();
+        assert_eq!(profile.uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(profile.username, "Notch");
+        assert_eq!(profile.properties.len(), 1);
+        assert_eq!(profile.properties[0].name, "textures");
+        assert_eq!(profile.properties[0].signature, Some("c2ln".to_string()));
+
+        let request_line = server.await.unwrap();
+        assert_eq!(request_line, "GET /session/minecraft/hasJoined?username=Notch&serverId=-7c9d5b HTTP/1.1");
+    }
+
+    #[tokio::test]
+    pub async fn test_auth_not_joined() {
+        let (url, _server) = mock_session_server("204 No Content", "").await;
+
+        let result = SessionService::new(&url).has_joined("Notch", "abc").await;
+        assert!(matches!(result, Err(AuthError::NotAuthenticated)));
+    }
+}
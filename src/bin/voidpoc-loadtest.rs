@@ -0,0 +1,287 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, VecDeque};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tokio::time::{MissedTickBehavior, interval, sleep, timeout};
+use voidpoc::client::{Client, ClientError, ClientOptions};
+use voidpoc::packet::PacketStatus;
+use voidpoc::play::{
+    ClientBoundPlayPongResponsePacket, ClientBoundPlaySetTimePacket, ServerBoundPlayClientTickEndPacket,
+    ServerBoundPlayMovePlayerPosPacket, ServerBoundPlayPingRequestPacket,
+};
+use voidpoc::protocol::ProtocolVersion;
+
+// LOAD TEST //
+
+const USAGE: &str = "Usage: voidpoc-loadtest [address] [--players N] [--duration SECONDS] [--ramp-up MILLIS] [--protocol VERSION]";
+
+const TICK: Duration = Duration::from_millis(50);
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+// Sprinting speed, in blocks per tick
+const WALK_SPEED: f64 = 0.28;
+// Ticks before a bot picks a new direction
+const WALK_TURN_TICKS: u32 = 40;
+
+struct LoadTestOptions {
+    address: String,
+    players: usize,
+    duration: Duration,
+    ramp_up: Duration,
+    version: ProtocolVersion,
+}
+
+impl LoadTestOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<LoadTestOptions, String> {
+        let mut options = LoadTestOptions {
+            address: "127.0.0.1:25565".to_string(),
+            players: 100,
+            duration: Duration::from_secs(30),
+            ramp_up: Duration::from_millis(10),
+            version: ProtocolVersion::LATEST,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+            match arg.as_str() {
+                "--players" => options.players = parse_number(&value(&arg)?)?,
+                "--duration" => options.duration = Duration::from_secs(parse_number(&value(&arg)?)?),
+                "--ramp-up" => options.ramp_up = Duration::from_millis(parse_number(&value(&arg)?)?),
+                "--protocol" => {
+                    let protocol = parse_number(&value(&arg)?)?;
+                    options.version = ProtocolVersion::from_protocol(protocol)
+                        .ok_or(format!("unsupported protocol version {}", protocol))?;
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => options.address = arg,
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(val: &str) -> Result<T, String> {
+    val.parse().map_err(|_| format!("invalid number '{}'", val))
+}
+
+/// What a single simulated player measured.
+#[derive(Default)]
+struct BotReport {
+    login: Option<Duration>,
+    error: Option<String>,
+    /// Time between the ping ending a tick and the server answering it
+    ping_latencies: Vec<Duration>,
+    ticks_sent: u64,
+    play_time: Duration,
+    /// Game time of the first and last time updates, and when they arrived
+    first_time: Option<(i64, Instant)>,
+    last_time: Option<(i64, Instant)>,
+}
+
+impl BotReport {
+    /// Server ticks over the wall-clock time between the first and last time
+    /// updates, as ticks and seconds.
+    fn server_ticks(&self) -> Option<(i64, f64)> {
+        let ((first, first_at), (last, last_at)) = (self.first_time?, self.last_time?);
+        let elapsed = (last_at - first_at).as_secs_f64();
+        (elapsed > 0.0).then_some((last - first, elapsed))
+    }
+}
+
+/// Logs in, then walks around and ends a tick with a ping every 50 ms until the deadline.
+async fn run_bot(options: ClientOptions, deadline: Instant) -> BotReport {
+    let mut report = BotReport::default();
+    let start = Instant::now();
+    let mut client = match timeout(LOGIN_TIMEOUT, login(options)).await {
+        Ok(Ok(client)) => client,
+        Ok(Err(err)) => {
+            report.error = Some(err.to_string());
+            return report;
+        }
+        Err(_) => {
+            report.error = Some("login timed out".to_string());
+            return report;
+        }
+    };
+    report.login = Some(start.elapsed());
+
+    let play_start = Instant::now();
+    if let Err(err) = play(&mut client, deadline, &mut report).await {
+        report.error = Some(err.to_string());
+    }
+    report.play_time = play_start.elapsed();
+    report
+}
+
+async fn login(options: ClientOptions) -> Result<Client, ClientError> {
+    let mut client = Client::connect_with(options).await?;
+    client.wait_for_state(PacketStatus::Play).await?;
+    Ok(client)
+}
+
+async fn play(client: &mut Client, deadline: Instant, report: &mut BotReport) -> Result<(), ClientError> {
+    // Client Tick End only exists since 1.21.2
+    let ends_ticks = client.version() > ProtocolVersion::V1_21_1;
+    let mut rng = StdRng::from_entropy();
+    let (mut x, mut z) = (0.0, 0.0);
+    let mut direction: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+    let mut pending_ticks = VecDeque::new();
+
+    let mut ticks = interval(TICK);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {
+                if Instant::now() >= deadline {
+                    return Ok(());
+                }
+                if report.ticks_sent.is_multiple_of(WALK_TURN_TICKS as u64) {
+                    direction = rng.gen_range(0.0..std::f64::consts::TAU);
+                }
+                x += direction.cos() * WALK_SPEED;
+                z += direction.sin() * WALK_SPEED;
+                client
+                    .send(ServerBoundPlayMovePlayerPosPacket {
+                        x,
+                        feet_y: 0.0,
+                        z,
+                        flags: 0x01,
+                    })
+                    .await?;
+                if ends_ticks {
+                    client.send(ServerBoundPlayClientTickEndPacket::default()).await?;
+                }
+                client
+                    .send(ServerBoundPlayPingRequestPacket {
+                        id: report.ticks_sent as i64,
+                    })
+                    .await?;
+                pending_ticks.push_back((report.ticks_sent as i64, Instant::now()));
+                report.ticks_sent += 1;
+            }
+            packet = client.next_packet() => {
+                let Some(packet) = packet else {
+                    return Err(client.closed().await.err().unwrap_or(ClientError::Closed));
+                };
+                // Pongs come back in order, answering every ping up to theirs
+                if let Some(pong) = packet.downcast_ref::<ClientBoundPlayPongResponsePacket>() {
+                    while let Some((id, sent)) = pending_ticks.front().copied()
+                        && id <= pong.id
+                    {
+                        pending_ticks.pop_front();
+                        if id == pong.id {
+                            report.ping_latencies.push(sent.elapsed());
+                        }
+                    }
+                }
+                // Sent every second with the server game time
+                if let Some(time) = packet.downcast_ref::<ClientBoundPlaySetTimePacket>() {
+                    let update = (time.world_age, Instant::now());
+                    report.first_time.get_or_insert(update);
+                    report.last_time = Some(update);
+                }
+            }
+        }
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+fn print_latencies(title: &str, mut latencies: Vec<Duration>) {
+    latencies.sort();
+    if latencies.is_empty() {
+        println!("{:<14} no samples", title);
+        return;
+    }
+    println!(
+        "{:<14} p50 {:>8.2?}  p90 {:>8.2?}  p99 {:>8.2?}  max {:>8.2?}  ({} samples)",
+        title,
+        percentile(&latencies, 0.5),
+        percentile(&latencies, 0.9),
+        percentile(&latencies, 0.99),
+        latencies[latencies.len() - 1],
+        latencies.len()
+    );
+}
+
+fn print_report(options: &LoadTestOptions, reports: Vec<BotReport>) -> bool {
+    let connected = reports.iter().filter(|report| report.login.is_some()).count();
+    let mut failures: BTreeMap<&str, usize> = BTreeMap::new();
+    for report in &reports {
+        if let Some(error) = &report.error {
+            *failures.entry(error.as_str()).or_default() += 1;
+        }
+    }
+
+    println!();
+    println!("{:<14} {} connected, {} failed to log in", "Players", connected, options.players - connected);
+    print_latencies("Login", reports.iter().filter_map(|report| report.login).collect());
+    print_latencies("Ping RTT", reports.iter().flat_map(|report| report.ping_latencies.iter().copied()).collect());
+
+    let server_ticks: Vec<(i64, f64)> = reports.iter().filter_map(BotReport::server_ticks).collect();
+    if server_ticks.is_empty() {
+        println!("{:<14} no time updates", "Server TPS");
+    } else {
+        let ticks: i64 = server_ticks.iter().map(|(ticks, _)| ticks).sum();
+        let seconds: f64 = server_ticks.iter().map(|(_, seconds)| seconds).sum();
+        let lowest = server_ticks.iter().map(|(ticks, seconds)| *ticks as f64 / seconds).fold(f64::INFINITY, f64::min);
+        println!(
+            "{:<14} {:.2} average, {:.2} lowest ({} bots)",
+            "Server TPS",
+            ticks as f64 / seconds,
+            lowest,
+            server_ticks.len()
+        );
+    }
+
+    if !failures.is_empty() {
+        println!("Failures");
+        for (error, count) in &failures {
+            println!("  {:>5}  {}", count, error);
+        }
+    }
+    failures.is_empty()
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match LoadTestOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "Connecting {} players to {} with {} for {:?}",
+        options.players, options.address, options.version, options.duration
+    );
+
+    let deadline = Instant::now() + options.ramp_up * options.players as u32 + options.duration;
+    let mut bots = JoinSet::new();
+    for i in 0..options.players {
+        let mut client_options = ClientOptions::new(&options.address, &format!("bot{}", i));
+        client_options.version = options.version;
+        bots.spawn(run_bot(client_options, deadline));
+        sleep(options.ramp_up).await;
+    }
+
+    let mut reports = Vec::with_capacity(options.players);
+    while let Some(report) = bots.join_next().await {
+        reports.push(report.unwrap_or_else(|err| BotReport {
+            error: Some(format!("bot panicked: {}", err)),
+            ..BotReport::default()
+        }));
+    }
+
+    if print_report(&options, reports) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use voidpoc::packet::PacketBound;
+use voidpoc::proxy::{CheckedPacket, ConformanceChecker, Verdict};
+
+// PROXY //
+
+const USAGE: &str = "Usage: voidpoc-proxy <upstream> [--listen ADDRESS] [--verbose]";
+
+// Bytes shown around the first difference of a mismatch
+const CONTEXT_BEFORE: usize = 8;
+const CONTEXT_AFTER: usize = 24;
+
+struct ProxyOptions {
+    upstream: String,
+    listen: String,
+    verbose: bool,
+}
+
+impl ProxyOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<ProxyOptions, String> {
+        let mut upstream = None;
+        let mut options = ProxyOptions {
+            upstream: String::new(),
+            listen: "0.0.0.0:25566".to_string(),
+            verbose: false,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--listen" => options.listen = args.next().ok_or("missing value for --listen")?,
+                "--verbose" => options.verbose = true,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => upstream = Some(arg),
+            }
+        }
+        options.upstream = upstream.ok_or("missing upstream server address")?;
+        Ok(options)
+    }
+}
+
+/// Verdicts of a packet type over a connection.
+#[derive(Default)]
+struct PacketSummary {
+    matched: usize,
+    failed: usize,
+    not_implemented: usize,
+}
+
+fn describe(packet: &CheckedPacket) -> String {
+    let direction = match packet.bound {
+        PacketBound::Server => "C->S",
+        PacketBound::Client => "S->C",
+    };
+    format!(
+        "{:<13} {} 0x{:02X} {}",
+        format!("{:?}", packet.state),
+        direction,
+        packet.id,
+        packet.name.unwrap_or("<unknown>")
+    )
+}
+
+fn hex_window(data: &[u8], offset: usize) -> String {
+    let start = offset.saturating_sub(CONTEXT_BEFORE);
+    let end = (offset + CONTEXT_AFTER).min(data.len());
+    let hex: Vec<String> = data[start.min(end)..end].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("@{:<6} {}", start, hex.join(" "))
+}
+
+fn report(connection: u64, packet: &CheckedPacket, verbose: bool) {
+    if !packet.verdict.is_failure() {
+        if verbose {
+            println!("#{} {}: {}", connection, describe(packet), packet.verdict);
+        }
+        return;
+    }
+    println!("#{} {}: {}", connection, describe(packet), packet.verdict);
+    if let Verdict::Mismatch {
+        offset,
+        original,
+        encoded,
+    } = &packet.verdict
+    {
+        println!("    vanilla {}", hex_window(original, *offset));
+        println!("    voidpoc {}", hex_window(encoded, *offset));
+    }
+}
+
+fn print_summary(connection: u64, summary: &BTreeMap<String, PacketSummary>) {
+    println!("#{} closed, packets relayed:", connection);
+    let mut not_implemented = Vec::new();
+    for (packet, counts) in summary {
+        if counts.matched + counts.failed == 0 {
+            not_implemented.push(packet.as_str());
+            continue;
+        }
+        println!("    {:<60} {:>6} ok {:>6} failed", packet, counts.matched, counts.failed);
+    }
+    if !not_implemented.is_empty() {
+        println!("    Not implemented: {}", not_implemented.len());
+        for packet in not_implemented {
+            println!("        {}", packet);
+        }
+    }
+}
+
+/// Relays a client to the upstream server, checking everything relayed.
+async fn relay(connection: u64, mut client: TcpStream, options: Arc<ProxyOptions>) -> std::io::Result<()> {
+    let mut server = TcpStream::connect(&options.upstream).await?;
+    let (mut client_read, mut client_write) = client.split();
+    let (mut server_read, mut server_write) = server.split();
+    let mut checker = ConformanceChecker::new();
+    let mut summary: BTreeMap<String, PacketSummary> = BTreeMap::new();
+    let mut client_buf = vec![0; 65536];
+    let mut server_buf = vec![0; 65536];
+
+    loop {
+        // Bytes are relayed untouched, checking only looks at a copy
+        let (bound, data) = tokio::select! {
+            len = client_read.read(&mut client_buf) => {
+                let len = len?;
+                if len == 0 {
+                    break;
+                }
+                server_write.write_all(&client_buf[..len]).await?;
+                (PacketBound::Server, &client_buf[..len])
+            }
+            len = server_read.read(&mut server_buf) => {
+                let len = len?;
+                if len == 0 {
+                    break;
+                }
+                client_write.write_all(&server_buf[..len]).await?;
+                (PacketBound::Client, &server_buf[..len])
+            }
+        };
+
+        let was_stopped = checker.stopped().is_some();
+        for packet in checker.feed(bound, data) {
+            report(connection, &packet, options.verbose);
+            let counts = summary.entry(describe(&packet)).or_default();
+            match packet.verdict {
+                Verdict::Match => counts.matched += 1,
+                Verdict::NotImplemented => counts.not_implemented += 1,
+                _ => counts.failed += 1,
+            }
+        }
+        if let (false, Some(reason)) = (was_stopped, checker.stopped()) {
+            println!("#{} not checked anymore: {}", connection, reason);
+        }
+    }
+    print_summary(connection, &summary);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match ProxyOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => Arc::new(options),
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let listener = match TcpListener::bind(&options.listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Failed to listen on {}: {}", options.listen, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Proxying {} to {}", options.listen, options.upstream);
+
+    let next_connection_id = AtomicU64::new(0);
+    loop {
+        let (client, address) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                eprintln!("Failed to accept connection: {}", err);
+                continue;
+            }
+        };
+        let connection = next_connection_id.fetch_add(1, Ordering::Relaxed);
+        println!("#{} connected from {}", connection, address);
+        let options = options.clone();
+        tokio::spawn(async move {
+            if let Err(err) = relay(connection, client, options).await {
+                println!("#{} connection error: {}", connection, err);
+            }
+        });
+    }
+}
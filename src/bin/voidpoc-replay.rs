@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use voidpoc::ByteBuf;
+use voidpoc::capture::{CaptureReader, CaptureRecord};
+use voidpoc::frame::{FrameDecoder, FrameEncoder};
+use voidpoc::packet::{PacketBound, PacketStatus};
+
+// REPLAY //
+
+const USAGE: &str = "Usage: voidpoc-replay <capture> [--connection ID] [--hex] [--replay ADDRESS]";
+
+// How long to wait for the server to send what it sent in the capture
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct ReplayOptions {
+    path: String,
+    connection: Option<u64>,
+    hex: bool,
+    replay: Option<String>,
+}
+
+impl ReplayOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<ReplayOptions, String> {
+        let mut path = None;
+        let mut options = ReplayOptions {
+            path: String::new(),
+            connection: None,
+            hex: false,
+            replay: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--connection" => {
+                    let val = args.next().ok_or("missing value for --connection")?;
+                    options.connection = Some(val.parse().map_err(|_| format!("invalid connection id '{}'", val))?);
+                }
+                "--hex" => options.hex = true,
+                "--replay" => options.replay = Some(args.next().ok_or("missing value for --replay")?),
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => path = Some(arg),
+            }
+        }
+        options.path = path.ok_or("missing capture file")?;
+        Ok(options)
+    }
+}
+
+fn describe(record: &CaptureRecord) -> String {
+    let direction = match record.bound {
+        PacketBound::Server => "C->S",
+        PacketBound::Client => "S->C",
+    };
+    format!(
+        "#{:<4} {:<13} {} 0x{:02X} {}",
+        record.connection,
+        format!("{:?}", record.state),
+        direction,
+        record.id,
+        record.name().unwrap_or("<unknown>")
+    )
+}
+
+fn hex_dump(data: &[u8]) {
+    for line in data.chunks(16) {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("        {}", hex.join(" "));
+    }
+}
+
+fn print_records(records: &[CaptureRecord], hex: bool) {
+    let Some(first) = records.first() else {
+        println!("No records");
+        return;
+    };
+    for record in records {
+        let elapsed = record.timestamp.saturating_sub(first.timestamp) as f64 / 1_000_000.0;
+        println!("+{:>10.3}s {}  ({} bytes)", elapsed, describe(record), record.data.len());
+        match record.decode() {
+            Ok(packet) => println!("        {:?}", packet),
+            Err(err) => println!("        not decoded: {:?}", err),
+        }
+        if hex {
+            hex_dump(&record.data);
+        }
+    }
+}
+
+/// Encryption cannot be replayed, the server's key pair is different every run.
+fn is_encryption(record: &CaptureRecord) -> bool {
+    // Login Start is also named `hello`, serverbound
+    let name = match record.bound {
+        PacketBound::Client => "minecraft:hello",
+        PacketBound::Server => "minecraft:key",
+    };
+    record.state == PacketStatus::Login && record.name() == Some(name)
+}
+
+/// Packet received during the replay, named as in the matching captured packet.
+struct Received {
+    name: Option<&'static str>,
+    id: i32,
+    data: Vec<u8>,
+}
+
+/// Sends the serverbound side of a connection again, waiting before every
+/// packet for the server to send what it sent before that packet in the
+/// capture, then compares what the server sent. The server must have
+/// encryption disabled.
+async fn replay(records: &[CaptureRecord], address: &str) -> Result<bool, String> {
+    let records: Vec<&CaptureRecord> = records.iter().filter(|record| !is_encryption(record)).collect();
+    let expected: Vec<&CaptureRecord> = records.iter().copied().filter(|record| record.bound == PacketBound::Client).collect();
+
+    let mut stream = TcpStream::connect(address).await.map_err(|err| err.to_string())?;
+    let mut encoder = FrameEncoder::new();
+    let mut decoder = FrameDecoder::new();
+    let mut received: Vec<Received> = Vec::new();
+    let mut expected_before = 0;
+
+    for record in records.iter().copied() {
+        if record.bound == PacketBound::Client {
+            expected_before += 1;
+            continue;
+        }
+        if !receive(&mut stream, &mut decoder, &mut encoder, &expected, &mut received, expected_before).await? {
+            println!("Timed out waiting for the server, stopping before {}", describe(record));
+            break;
+        }
+        let payload = record.payload();
+        stream
+            .write_all(&encoder.encode(payload.as_slice()))
+            .await
+            .map_err(|err| err.to_string())?;
+    }
+    // Everything the server sent after the last serverbound packet
+    receive(&mut stream, &mut decoder, &mut encoder, &expected, &mut received, expected.len()).await?;
+
+    Ok(compare(&expected, &received))
+}
+
+/// Reads until `count` packets were received, `false` on timeout or end of stream.
+async fn receive(
+    stream: &mut TcpStream,
+    decoder: &mut FrameDecoder,
+    encoder: &mut FrameEncoder,
+    expected: &[&CaptureRecord],
+    received: &mut Vec<Received>,
+    count: usize,
+) -> Result<bool, String> {
+    let mut buf = [0; 4096];
+    while received.len() < count {
+        if let Some(mut frame) = decoder.next_frame().map_err(|err| err.to_string())? {
+            let id = frame.read_varint().map_err(|err| format!("{:?}", err))?;
+            // Named like the packet expected at this point, or the last one
+            let context = expected.get(received.len()).or(expected.last());
+            let name = context.and_then(|record| voidpoc::ids::name(record.version, record.state, PacketBound::Client, id));
+            if let (Some(context), Some("minecraft:login_compression")) = (context, name)
+                && context.state == PacketStatus::Login
+            {
+                let threshold = ByteBuf::from_bytes(frame.as_slice().into())
+                    .read_varint()
+                    .map_err(|err| format!("{:?}", err))?;
+                let threshold = (threshold >= 0).then_some(threshold);
+                decoder.set_compression(threshold);
+                encoder.set_compression(threshold);
+            }
+            received.push(Received {
+                name,
+                id,
+                data: frame.as_slice().to_vec(),
+            });
+            continue;
+        }
+
+        match timeout(RESPONSE_TIMEOUT, stream.read(&mut buf)).await {
+            Ok(Ok(0)) | Err(_) => return Ok(false),
+            Ok(Ok(len)) => decoder.feed(&buf[..len]),
+            Ok(Err(err)) => return Err(err.to_string()),
+        }
+    }
+    Ok(true)
+}
+
+fn compare(expected: &[&CaptureRecord], received: &[Received]) -> bool {
+    let mut matched = 0;
+    let mut mismatches = 0;
+    let mut changed = BTreeSet::new();
+    for i in 0..expected.len().max(received.len()) {
+        match (expected.get(i), received.get(i)) {
+            (Some(expected), Some(received)) if received.id == expected.id => {
+                matched += 1;
+                if received.data != expected.data {
+                    changed.insert(expected.name().unwrap_or("<unknown>"));
+                }
+            }
+            (Some(expected), Some(received)) => {
+                mismatches += 1;
+                println!(
+                    "  {:>5}: expected {}, got 0x{:02X} {}",
+                    i,
+                    describe(expected),
+                    received.id,
+                    received.name.unwrap_or("<unknown>")
+                );
+            }
+            (Some(expected), None) => {
+                mismatches += 1;
+                println!("  {:>5}: expected {}, got nothing", i, describe(expected));
+            }
+            (None, Some(received)) => {
+                mismatches += 1;
+                println!("  {:>5}: unexpected 0x{:02X} {}", i, received.id, received.name.unwrap_or("<unknown>"));
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    println!(
+        "{} of {} clientbound packets matched, {} received",
+        matched,
+        expected.len(),
+        received.len()
+    );
+    // Keep-alive ids, UUIDs and the like differ between runs
+    if !changed.is_empty() {
+        println!("Same packets with different content: {}", changed.into_iter().collect::<Vec<_>>().join(", "));
+    }
+    mismatches == 0
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match ReplayOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let records = match CaptureReader::open(&options.path).and_then(|reader| reader.collect::<Result<Vec<_>, _>>()) {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Some(address) = &options.replay else {
+        let records: Vec<CaptureRecord> = records
+            .into_iter()
+            .filter(|record| options.connection.is_none_or(|connection| record.connection == connection))
+            .collect();
+        print_records(&records, options.hex);
+        return ExitCode::SUCCESS;
+    };
+
+    // A replay is a single connection, the first one by default
+    let Some(connection) = options.connection.or(records.first().map(|record| record.connection)) else {
+        eprintln!("No records to replay");
+        return ExitCode::FAILURE;
+    };
+    let records: Vec<CaptureRecord> = records.into_iter().filter(|record| record.connection == connection).collect();
+    println!("Replaying connection #{} ({} records) against {}", connection, records.len(), address);
+    match replay(&records, address).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("Replay failed: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
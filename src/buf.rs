@@ -0,0 +1,1304 @@
+use crate::Property;
+use crate::item::{MAX_SLOT_DEPTH, SlotComponent};
+use crate::protocol::ProtocolVersion;
+use crate::text::TextComponent;
+use crate::types::{Angle, Identifier, Position, Quaternion, Slot, Vec3};
+use bit_set::BitSet;
+use bytes::{Buf, Bytes, BytesMut};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use simdnbt::owned::{Nbt, NbtCompound, NbtTag};
+use uuid::Uuid;
+
+// BUFFER //
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    Underflow { needed: usize, remaining: usize },
+    VarIntTooLong,
+    VarLongTooLong,
+    InvalidLength(i32),
+    StringTooLong { len: usize, max: usize },
+    InvalidUtf8,
+    InvalidData(String),
+    UnknownPacketId(i32),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Underflow { needed, remaining } => {
+                write!(f, "needed {} more bytes but only {} remain", needed, remaining)
+            }
+            DecodeError::VarIntTooLong => write!(f, "varint is too long"),
+            DecodeError::VarLongTooLong => write!(f, "varlong is too long"),
+            DecodeError::InvalidLength(len) => write!(f, "invalid length {}", len),
+            DecodeError::StringTooLong { len, max } => {
+                write!(f, "string of {} bytes is over the maximum of {}", len, max)
+            }
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::InvalidData(err) => write!(f, "invalid data: {}", err),
+            DecodeError::UnknownPacketId(id) => write!(f, "unknown packet id 0x{:02X}", id),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Growable protocol buffer.
+///
+/// Writes append at the end and reads move a cursor forward, so a buffer can be
+/// read while it is being filled without ever moving its content.
+/// Splitting off a frame or handing the written bytes to the socket shares the
+/// underlying allocation instead of copying it.
+pub struct ByteBuf {
+    data: BytesMut,
+    read_offset: usize,
+}
+
+impl ByteBuf {
+    const SEGMENT_BITS: i32 = 0x7F;
+    const CONTINUE_BIT: i32 = 0x80;
+    // Strings are limited to 32767 characters, which is at most 3 bytes each
+    pub const MAX_STRING_LEN: usize = 32767 * 3;
+
+    pub fn new() -> ByteBuf {
+        ByteBuf::from_bytes(BytesMut::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> ByteBuf {
+        ByteBuf::from_bytes(BytesMut::with_capacity(capacity))
+    }
+
+    pub fn from_bytes(data: BytesMut) -> ByteBuf {
+        ByteBuf { data, read_offset: 0 }
+    }
+
+    pub fn dump(&self) {
+        for byte in self.as_slice() {
+            print!("{:02X} ", byte);
+        }
+        println!();
+    }
+
+    /// Fails if less than `len` bytes are left to read.
+    #[inline]
+    pub(crate) fn ensure_remaining(&self, len: usize) -> Result<(), DecodeError> {
+        if self.remaining_len() < len {
+            return Err(DecodeError::Underflow {
+                needed: len,
+                remaining: self.remaining_len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads the next `N` bytes.
+    #[inline]
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        self.ensure_remaining(N)?;
+        let bytes = self.data[self.read_offset..self.read_offset + N].try_into().unwrap();
+        self.read_offset += N;
+        Ok(bytes)
+    }
+
+    /// Drops the bytes already read from the underlying buffer, so that it
+    /// can be split or handed out.
+    fn compact(&mut self) {
+        self.data.advance(self.read_offset);
+        self.read_offset = 0;
+    }
+
+    /// Reads a varint length prefix, rejecting negative values.
+    pub(crate) fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_varint()?;
+        if len < 0 {
+            return Err(DecodeError::InvalidLength(len));
+        }
+        Ok(len as usize)
+    }
+
+    pub fn pop_front_i8(&mut self) -> Result<i8, DecodeError> {
+        self.read_i8()
+    }
+
+    pub fn reserve(&mut self, additional: usize) -> &Self {
+        self.data.reserve(additional);
+        self
+    }
+
+    #[inline]
+    pub fn write_u8(&mut self, val: u8) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(u8::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_u16(&mut self, val: u16) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_u32(&mut self, val: u32) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_u64(&mut self, val: u64) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_i8(&mut self, val: i8) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_i8(&mut self) -> Result<i8, DecodeError> {
+        Ok(i8::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_i16(&mut self, val: i16) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(i16::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_i32(&mut self, val: i32) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_i64(&mut self, val: i64) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_i64(&mut self) -> Result<i64, DecodeError> {
+        Ok(i64::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_f32(&mut self, val: f32) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_f64(&mut self, val: f64) -> &Self {
+        self.data.extend_from_slice(&val.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn read_f64(&mut self) -> Result<f64, DecodeError> {
+        Ok(f64::from_be_bytes(self.take()?))
+    }
+
+    #[inline]
+    pub fn write_varint(&mut self, mut val: i32) -> &Self {
+        loop {
+            if (val & !ByteBuf::SEGMENT_BITS) == 0 {
+                self.write_u8(val as u8);
+                return self;
+            }
+            self.write_u8(((val & ByteBuf::SEGMENT_BITS) | ByteBuf::CONTINUE_BIT) as u8);
+            val = ((val as u32) >> 7) as i32;
+        }
+    }
+
+    pub fn write_string(&mut self, val: &str) -> &Self {
+        self.write_varint(val.len() as i32);
+        self.write_buf(val.as_bytes());
+        self
+    }
+
+    pub fn read_string(&mut self) -> Result<String, DecodeError> {
+        self.read_string_max(ByteBuf::MAX_STRING_LEN)
+    }
+
+    /// Reads a string of at most `max` bytes.
+    pub fn read_string_max(&mut self, max: usize) -> Result<String, DecodeError> {
+        let len = self.read_len()?;
+        if len > max {
+            return Err(DecodeError::StringTooLong { len, max });
+        }
+        self.ensure_remaining(len)?;
+        let str = std::str::from_utf8(&self.as_slice()[..len])
+            .map_err(|_| DecodeError::InvalidUtf8)?
+            .to_string();
+        self.read_offset += len;
+        Ok(str)
+    }
+
+    #[inline]
+    pub fn read_varint(&mut self) -> Result<i32, DecodeError> {
+        let mut value = 0;
+        let mut position = 0;
+        let mut current_byte;
+
+        loop {
+            current_byte = self.read_i8()?;
+            value |= (current_byte as i32 & ByteBuf::SEGMENT_BITS) << position;
+
+            if (current_byte as i32 & ByteBuf::CONTINUE_BIT) == 0 {
+                break;
+            }
+            position += 7;
+            if position >= 32 {
+                return Err(DecodeError::VarIntTooLong);
+            }
+        }
+        Ok(value)
+    }
+
+    pub fn write_varlong(&mut self, mut val: i64) -> &Self {
+        loop {
+            if (val & !(ByteBuf::SEGMENT_BITS as i64)) == 0 {
+                self.write_u8(val as u8);
+                return self;
+            }
+            self.write_u8(((val & ByteBuf::SEGMENT_BITS as i64) | ByteBuf::CONTINUE_BIT as i64) as u8);
+            val = ((val as u64) >> 7) as i64;
+        }
+    }
+
+    pub fn read_varlong(&mut self) -> Result<i64, DecodeError> {
+        let mut value = 0;
+        let mut position = 0;
+
+        loop {
+            let current_byte = self.read_u8()?;
+            value |= (current_byte as i64 & ByteBuf::SEGMENT_BITS as i64) << position;
+
+            if (current_byte as i32 & ByteBuf::CONTINUE_BIT) == 0 {
+                break;
+            }
+            position += 7;
+            if position >= 64 {
+                return Err(DecodeError::VarLongTooLong);
+            }
+        }
+        Ok(value)
+    }
+
+    #[inline]
+    pub fn write_buf(&mut self, buf: &[u8]) -> &Self {
+        self.data.extend_from_slice(buf);
+        self
+    }
+
+    pub fn write_byte_array(&mut self, buf: &[u8]) -> &Self {
+        self.write_varint(buf.len() as i32);
+        self.write_buf(buf);
+        self
+    }
+
+    pub fn read_byte_array(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let len = self.read_len()?;
+        self.ensure_remaining(len)?;
+        let bytes = self.as_slice()[..len].to_vec();
+        self.read_offset += len;
+        Ok(bytes)
+    }
+
+    /// Reads everything left in the buffer.
+    #[inline]
+    pub fn read_buf(&mut self) -> Vec<u8> {
+        self.read_all().to_vec()
+    }
+
+    pub fn write_uuid(&mut self, uuid: &Uuid) -> &Self {
+        // Encode the most significant 64 bits first, then the least significant 64 bits
+        self.write_i64((uuid.as_u128() >> 64) as i64);
+        self.write_i64(uuid.as_u128() as i64);
+        self
+    }
+
+    pub fn read_uuid(&mut self) -> Result<Uuid, DecodeError> {
+        let most_significant = self.read_i64()? as u64;
+        let least_significant = self.read_i64()? as u64;
+
+        Ok(Uuid::from_u128(((most_significant as u128) << 64) | (least_significant as u128)))
+    }
+
+    #[inline]
+    pub fn write_bool(&mut self, val: bool) -> &Self {
+        self.write_u8(if val { 1 } else { 0 });
+        self
+    }
+
+    #[inline]
+    pub fn read_bool(&mut self) -> Result<bool, DecodeError> {
+        Ok(self.read_u8()? == 1)
+    }
+
+    pub fn write_property(&mut self, prop: &Property) -> &Self {
+        self.write_string(&prop.name);
+        self.write_string(&prop.value);
+        self.write_bool(prop.signature.is_some());
+        if let Some(signature) = &prop.signature {
+            self.write_string(signature);
+        }
+        self
+    }
+
+    pub fn read_property(&mut self) -> Result<Property, DecodeError> {
+        let name = self.read_string()?;
+        let value = self.read_string()?;
+        let signature = if self.read_bool()? {
+            Some(self.read_string()?)
+        } else {
+            None
+        };
+        Ok(Property {
+            name,
+            value,
+            signature,
+        })
+    }
+
+    pub fn write_properties(&mut self, props: &[Property]) -> &Self {
+        self.write_varint(props.len() as i32);
+        for prop in props {
+            self.write_property(prop);
+        }
+        self
+    }
+
+    pub fn read_properties(&mut self) -> Result<Vec<Property>, DecodeError> {
+        let len = self.read_len()?;
+        let mut props = Vec::new();
+        for _ in 0..len {
+            props.push(self.read_property()?);
+        }
+        Ok(props)
+    }
+
+    pub fn write_nbt(&mut self, nbt: &Nbt) -> &Self {
+        let mut buf = Vec::new();
+        nbt.write(&mut buf);
+        self.write_buf(&buf);
+        self
+    }
+
+    pub fn read_nbt(&mut self) -> Result<Nbt, DecodeError> {
+        let mut cursor = std::io::Cursor::new(self.as_slice());
+        let nbt = simdnbt::owned::read(&mut cursor).map_err(|e| DecodeError::InvalidData(e.to_string()))?;
+        self.read_offset += cursor.position() as usize;
+        Ok(nbt)
+    }
+
+    pub fn write_nbt_compound(&mut self, nbt: &NbtCompound) -> &Self {
+        let mut buf = Vec::new();
+        nbt.write(&mut buf);
+        self.write_buf(&buf);
+        self
+    }
+
+    pub fn read_nbt_compound(&mut self) -> Result<NbtCompound, DecodeError> {
+        let mut cursor = std::io::Cursor::new(self.as_slice());
+        let nbt = simdnbt::owned::read_compound(&mut cursor).map_err(|e| DecodeError::InvalidData(format!("{:?}", e)))?;
+        self.read_offset += cursor.position() as usize;
+        Ok(nbt)
+    }
+
+    /// Writes a single unnamed tag, the network format of NBT since 1.20.2.
+    pub fn write_nbt_tag(&mut self, tag: &NbtTag) -> &Self {
+        let mut buf = Vec::new();
+        tag.write(&mut buf);
+        self.write_buf(&buf);
+        self
+    }
+
+    pub fn read_nbt_tag(&mut self) -> Result<NbtTag, DecodeError> {
+        let mut cursor = std::io::Cursor::new(self.as_slice());
+        let tag = simdnbt::owned::read_tag(&mut cursor).map_err(|e| DecodeError::InvalidData(format!("{:?}", e)))?;
+        self.read_offset += cursor.position() as usize;
+        Ok(tag)
+    }
+
+    /// Writes a text component as network NBT.
+    pub fn write_component(&mut self, component: &TextComponent) -> &Self {
+        self.write_nbt_tag(&component.to_nbt());
+        self
+    }
+
+    pub fn read_component(&mut self) -> Result<TextComponent, DecodeError> {
+        let tag = self.read_nbt_tag()?;
+        TextComponent::from_nbt(&tag)
+    }
+
+    /// Writes a plain text component, sent as an NBT string tag.
+    pub fn write_text_component(&mut self, text: &str) -> &Self {
+        self.write_component(&TextComponent::text(text))
+    }
+
+    /// Reads the text of a component, without its formatting.
+    pub fn read_text_component(&mut self) -> Result<String, DecodeError> {
+        Ok(self.read_component()?.to_plain())
+    }
+
+    /// Writes a value serialized as a JSON string.
+    pub fn write_json<T: Serialize>(&mut self, val: &T) -> &Self {
+        self.write_string(&serde_json::to_string(val).expect("Failed to serialize JSON"));
+        self
+    }
+
+    pub fn read_json<T: DeserializeOwned>(&mut self) -> Result<T, DecodeError> {
+        let json = self.read_string()?;
+        serde_json::from_str(&json).map_err(|e| DecodeError::InvalidData(e.to_string()))
+    }
+
+    pub fn write_position(&mut self, pos: &Position) -> &Self {
+        self.write_i64(pos.pack());
+        self
+    }
+
+    pub fn read_position(&mut self) -> Result<Position, DecodeError> {
+        Ok(Position::unpack(self.read_i64()?))
+    }
+
+    pub fn write_angle(&mut self, angle: Angle) -> &Self {
+        self.write_u8(angle.0);
+        self
+    }
+
+    pub fn read_angle(&mut self) -> Result<Angle, DecodeError> {
+        Ok(Angle(self.read_u8()?))
+    }
+
+    pub fn write_identifier(&mut self, id: &Identifier) -> &Self {
+        self.write_string(&id.to_string());
+        self
+    }
+
+    pub fn read_identifier(&mut self) -> Result<Identifier, DecodeError> {
+        Identifier::parse(&self.read_string_max(Identifier::MAX_LEN)?)
+    }
+
+    /// Writes a bit set as a length-prefixed array of longs.
+    pub fn write_bitset(&mut self, bits: &BitSet) -> &Self {
+        let mut longs = vec![0u64; bits.iter().last().map_or(0, |last| last / 64 + 1)];
+        for bit in bits.iter() {
+            longs[bit / 64] |= 1 << (bit % 64);
+        }
+        self.write_varint(longs.len() as i32);
+        for long in longs {
+            self.write_u64(long);
+        }
+        self
+    }
+
+    pub fn read_bitset(&mut self) -> Result<BitSet, DecodeError> {
+        let len = self.read_len()?;
+        self.ensure_remaining(len * 8)?;
+        let mut bits = BitSet::new();
+        for i in 0..len {
+            let long = self.read_u64()?;
+            for bit in 0..64 {
+                if long & (1 << bit) != 0 {
+                    bits.insert(i * 64 + bit);
+                }
+            }
+        }
+        Ok(bits)
+    }
+
+    /// Writes the first `len` bits of a bit set as `ceil(len / 8)` bytes,
+    /// without a length prefix.
+    pub fn write_fixed_bitset(&mut self, bits: &BitSet, len: usize) -> &Self {
+        let mut bytes = vec![0u8; len.div_ceil(8)];
+        for bit in bits.iter().take_while(|bit| *bit < len) {
+            bytes[bit / 8] |= 1 << (bit % 8);
+        }
+        self.write_buf(&bytes);
+        self
+    }
+
+    pub fn read_fixed_bitset(&mut self, len: usize) -> Result<BitSet, DecodeError> {
+        let byte_len = len.div_ceil(8);
+        self.ensure_remaining(byte_len)?;
+        let mut bits = BitSet::with_capacity(len);
+        for i in 0..byte_len {
+            let byte = self.read_u8()?;
+            for bit in 0..8 {
+                if byte & (1 << bit) != 0 && i * 8 + bit < len {
+                    bits.insert(i * 8 + bit);
+                }
+            }
+        }
+        Ok(bits)
+    }
+
+    /// Writes a boolean telling whether the value is present, then the value.
+    pub fn write_prefixed_option<T>(&mut self, val: Option<&T>, write: impl FnOnce(&mut ByteBuf, &T)) -> &Self {
+        self.write_bool(val.is_some());
+        if let Some(val) = val {
+            write(self, val);
+        }
+        self
+    }
+
+    pub fn read_prefixed_option<T>(
+        &mut self,
+        read: impl FnOnce(&mut ByteBuf) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        if self.read_bool()? {
+            Ok(Some(read(self)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Writes a varint length, then every element.
+    pub fn write_prefixed_array<T>(&mut self, vals: &[T], mut write: impl FnMut(&mut ByteBuf, &T)) -> &Self {
+        self.write_varint(vals.len() as i32);
+        for val in vals {
+            write(self, val);
+        }
+        self
+    }
+
+    pub fn read_prefixed_array<T>(
+        &mut self,
+        mut read: impl FnMut(&mut ByteBuf) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let len = self.read_len()?;
+        // Every element takes at least a byte, don't trust the length any further
+        let mut vals = Vec::with_capacity(len.min(self.remaining_len()));
+        for _ in 0..len {
+            vals.push(read(self)?);
+        }
+        Ok(vals)
+    }
+
+    pub fn write_slot(&mut self, slot: &Slot) -> &Self {
+        self.write_slot_for(slot, ProtocolVersion::LATEST)
+    }
+
+    /// Writes a slot with the component ids and layouts of `version`.
+    /// Components that don't exist in `version` are left out.
+    pub fn write_slot_for(&mut self, slot: &Slot, version: ProtocolVersion) -> &Self {
+        if slot.is_empty() {
+            self.write_varint(0);
+            return self;
+        }
+        let components: Vec<_> = slot
+            .components_to_add
+            .iter()
+            .filter(|component| component.id(version).is_some())
+            .collect();
+        self.write_varint(slot.item_count);
+        self.write_varint(slot.item_id);
+        self.write_varint(components.len() as i32);
+        self.write_varint(slot.components_to_remove.len() as i32);
+        for component in components {
+            component.write_to(self, version);
+        }
+        for id in &slot.components_to_remove {
+            self.write_varint(*id);
+        }
+        self
+    }
+
+    pub fn read_slot(&mut self) -> Result<Slot, DecodeError> {
+        self.read_slot_for(ProtocolVersion::LATEST)
+    }
+
+    /// Reads a slot with the component ids and layouts of `version`.
+    pub fn read_slot_for(&mut self, version: ProtocolVersion) -> Result<Slot, DecodeError> {
+        self.read_slot_nested(version, 0)
+    }
+
+    /// Reads a slot held by components of `depth` other slots.
+    pub(crate) fn read_slot_nested(&mut self, version: ProtocolVersion, depth: usize) -> Result<Slot, DecodeError> {
+        if depth > MAX_SLOT_DEPTH {
+            return Err(DecodeError::InvalidData("slots nested too deep".to_string()));
+        }
+        let item_count = self.read_varint()?;
+        if item_count <= 0 {
+            return Ok(Slot::empty());
+        }
+        let item_id = self.read_varint()?;
+        let add_len = self.read_len()?;
+        let remove_len = self.read_len()?;
+        self.ensure_remaining(add_len + remove_len)?;
+        let mut components_to_add = Vec::with_capacity(add_len);
+        for _ in 0..add_len {
+            components_to_add.push(SlotComponent::read_nested(self, version, depth)?);
+        }
+        let mut components_to_remove = Vec::with_capacity(remove_len);
+        for _ in 0..remove_len {
+            components_to_remove.push(self.read_varint()?);
+        }
+        Ok(Slot {
+            item_count,
+            item_id,
+            components_to_add,
+            components_to_remove,
+        })
+    }
+
+    pub fn write_vec3(&mut self, vec: &Vec3) -> &Self {
+        self.write_f32(vec.x);
+        self.write_f32(vec.y);
+        self.write_f32(vec.z);
+        self
+    }
+
+    pub fn read_vec3(&mut self) -> Result<Vec3, DecodeError> {
+        Ok(Vec3 {
+            x: self.read_f32()?,
+            y: self.read_f32()?,
+            z: self.read_f32()?,
+        })
+    }
+
+    pub fn write_quaternion(&mut self, quat: &Quaternion) -> &Self {
+        self.write_f32(quat.x);
+        self.write_f32(quat.y);
+        self.write_f32(quat.z);
+        self.write_f32(quat.w);
+        self
+    }
+
+    pub fn read_quaternion(&mut self) -> Result<Quaternion, DecodeError> {
+        Ok(Quaternion {
+            x: self.read_f32()?,
+            y: self.read_f32()?,
+            z: self.read_f32()?,
+            w: self.read_f32()?,
+        })
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.data.clear();
+        self.read_offset = 0;
+        self
+    }
+
+    /// Number of bytes written and not read yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len() - self.read_offset
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Same as `len`, reads consume the buffer.
+    #[inline]
+    pub fn remaining_len(&self) -> usize {
+        self.len()
+    }
+
+    /// Unread bytes, without consuming them.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[self.read_offset..]
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data[self.read_offset..]
+    }
+
+    /// Discards the next `len` unread bytes.
+    pub fn skip(&mut self, len: usize) -> Result<(), DecodeError> {
+        self.ensure_remaining(len)?;
+        self.read_offset += len;
+        Ok(())
+    }
+
+    /// Splits off the next `len` unread bytes, sharing the same allocation.
+    pub fn split_to(&mut self, len: usize) -> Result<ByteBuf, DecodeError> {
+        self.ensure_remaining(len)?;
+        self.compact();
+        Ok(ByteBuf::from_bytes(self.data.split_to(len)))
+    }
+
+    /// Takes every unread byte, leaving the buffer empty but keeping its spare capacity.
+    pub fn read_all(&mut self) -> BytesMut {
+        self.compact();
+        self.data.split()
+    }
+
+    pub fn into_inner(mut self) -> BytesMut {
+        self.compact();
+        self.data
+    }
+
+    pub fn freeze(self) -> Bytes {
+        self.into_inner().freeze()
+    }
+}
+
+impl Default for ByteBuf {
+    fn default() -> Self {
+        ByteBuf::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buf::{ByteBuf, DecodeError};
+    use crate::item::{Enchantments, SlotComponent};
+    use crate::protocol::ProtocolVersion;
+    use crate::text::TextComponent;
+    use crate::types::{Angle, Identifier, Position, Quaternion, Slot, Vec3};
+    use crate::Property;
+    use bit_set::BitSet;
+
+    #[test]
+    pub fn test_buf_write_varint() {
+        let mut buf = ByteBuf::new();
+        buf.write_varint(156);
+
+        assert_eq!(buf.read_varint().unwrap(), 156);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_varint_multiple() {
+        let mut buf = ByteBuf::new();
+        buf.write_varint(2611);
+        buf.write_varint(2611);
+
+        assert_eq!(buf.read_varint().unwrap(), 2611);
+        assert_eq!(buf.read_varint().unwrap(), 2611);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_read_underflow() {
+        let mut buf = ByteBuf::new();
+        buf.write_u8(0x01);
+
+        assert_eq!(buf.read_i64(), Err(DecodeError::Underflow { needed: 8, remaining: 1 }));
+        assert_eq!(buf.read_u8(), Ok(0x01));
+        assert_eq!(buf.read_u8(), Err(DecodeError::Underflow { needed: 1, remaining: 0 }));
+    }
+
+    #[test]
+    pub fn test_buf_read_varint_too_long() {
+        let mut buf = ByteBuf::new();
+        buf.write_buf(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+
+        assert_eq!(buf.read_varint(), Err(DecodeError::VarIntTooLong));
+    }
+
+    #[test]
+    pub fn test_buf_read_string_errors() {
+        let mut buf = ByteBuf::new();
+        buf.write_string("abcde");
+        assert_eq!(buf.read_string_max(4), Err(DecodeError::StringTooLong { len: 5, max: 4 }));
+
+        let mut buf = ByteBuf::new();
+        buf.write_byte_array(&[0xC3, 0x28]);
+        assert_eq!(buf.read_string(), Err(DecodeError::InvalidUtf8));
+
+        // Length prefix larger than the data actually sent
+        let mut buf = ByteBuf::new();
+        buf.write_varint(10);
+        buf.write_buf(b"abc");
+        assert_eq!(buf.read_string(), Err(DecodeError::Underflow { needed: 10, remaining: 3 }));
+    }
+
+    #[test]
+    pub fn test_buf_read_negative_length() {
+        let mut buf = ByteBuf::new();
+        buf.write_varint(-1);
+
+        assert_eq!(buf.read_properties(), Err(DecodeError::InvalidLength(-1)));
+    }
+
+    #[test]
+    pub fn test_buf_write_string() {
+        let mut buf = ByteBuf::new();
+        buf.write_string("abcde");
+
+        let str = String::from("abcde");
+        let buf_str = buf.read_string().unwrap();
+
+        assert_eq!(str, buf_str);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_clear() {
+        let mut buf = ByteBuf::new();
+
+        buf.write_u8(0u8);
+        assert_eq!(buf.len(), 1);
+        buf.clear();
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_uuid() {
+        use uuid::Uuid;
+
+        let mut buf = ByteBuf::new();
+        let uuid = Uuid::new_v4();
+        buf.write_uuid(&uuid);
+
+        let read_uuid = buf.read_uuid().unwrap();
+        assert_eq!(uuid, read_uuid);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_uuid_byte_order() {
+        use uuid::Uuid;
+
+        let mut buf = ByteBuf::new();
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        buf.write_uuid(&uuid);
+
+        // Sent as the raw 16 bytes, most significant first
+        assert_eq!(buf.as_slice(), uuid.as_bytes());
+    }
+
+    #[test]
+    pub fn test_buf_write_property_no_signature() {
+        let mut buf = ByteBuf::new();
+        let property = Property {
+            name: "name".to_string(),
+            value: "value".to_string(),
+            signature: None,
+        };
+
+        buf.write_property(&property);
+
+        let read_property = buf.read_property().unwrap();
+        assert_eq!(property, read_property);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_property_with_signature() {
+        let mut buf = ByteBuf::new();
+        let property = Property {
+            name: "name".to_string(),
+            value: "value".to_string(),
+            signature: Some("signature".to_string()),
+        };
+
+        buf.write_property(&property);
+
+        let read_property = buf.read_property().unwrap();
+        assert_eq!(property, read_property);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_properties_no_signature() {
+        let mut buf = ByteBuf::new();
+        let properties = vec![
+            Property {
+                name: "name".to_string(),
+                value: "value".to_string(),
+                signature: None,
+            },
+            Property {
+                name: "name2".to_string(),
+                value: "value2".to_string(),
+                signature: None,
+            },
+        ];
+
+        buf.write_properties(&properties);
+
+        let read_properties = buf.read_properties().unwrap();
+        assert_eq!(properties, read_properties);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_properties_with_signature() {
+        let mut buf = ByteBuf::new();
+        let properties = vec![
+            Property {
+                name: "name".to_string(),
+                value: "value".to_string(),
+                signature: Some("signature".to_string()),
+            },
+            Property {
+                name: "name2".to_string(),
+                value: "value2".to_string(),
+                signature: Some("signature2".to_string()),
+            },
+        ];
+
+        buf.write_properties(&properties);
+
+        let read_properties = buf.read_properties().unwrap();
+        assert_eq!(properties, read_properties);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_properties_mixed() {
+        let mut buf = ByteBuf::new();
+        let properties = vec![
+            Property {
+                name: "name".to_string(),
+                value: "value".to_string(),
+                signature: None,
+            },
+            Property {
+                name: "name2".to_string(),
+                value: "value2".to_string(),
+                signature: Some("signature2".to_string()),
+            },
+        ];
+
+        buf.write_properties(&properties);
+
+        let read_properties = buf.read_properties().unwrap();
+        assert_eq!(properties, read_properties);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_byte_array() {
+        let mut buf = ByteBuf::new();
+        buf.write_byte_array(&[0x01, 0x02, 0x03]);
+        buf.write_byte_array(&[]);
+
+        assert_eq!(buf.read_byte_array().unwrap(), vec![0x01, 0x02, 0x03]);
+        assert_eq!(buf.read_byte_array().unwrap(), Vec::<u8>::new());
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_numbers() {
+        let mut buf = ByteBuf::new();
+        buf.write_u16(0xBEEF);
+        buf.write_u32(0xDEADBEEF);
+        buf.write_u64(0x0123456789ABCDEF);
+        buf.write_i64(-2);
+        buf.write_f64(-1.5);
+
+        assert_eq!(buf.read_u16().unwrap(), 0xBEEF);
+        assert_eq!(buf.read_u32().unwrap(), 0xDEADBEEF);
+        assert_eq!(buf.read_u64().unwrap(), 0x0123456789ABCDEF);
+        assert_eq!(buf.read_i64().unwrap(), -2);
+        assert_eq!(buf.read_f64().unwrap(), -1.5);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_split_to() {
+        let mut buf = ByteBuf::new();
+        buf.write_buf(&[0x01, 0x02, 0x03, 0x04]);
+
+        let mut front = buf.split_to(3).unwrap();
+        assert_eq!(front.read_u8().unwrap(), 0x01);
+        assert_eq!(front.remaining_len(), 2);
+        assert_eq!(buf.as_slice(), &[0x04]);
+        assert!(buf.split_to(2).is_err());
+    }
+
+    #[test]
+    pub fn test_buf_interleaved_read_write() {
+        let mut buf = ByteBuf::with_capacity(16);
+        buf.write_varint(300);
+        assert_eq!(buf.read_varint().unwrap(), 300);
+
+        // Writing after reading appends after the unread bytes
+        buf.write_u8(0x01);
+        buf.write_u8(0x02);
+        assert_eq!(buf.read_u8().unwrap(), 0x01);
+        buf.write_u8(0x03);
+        assert_eq!(buf.as_slice(), &[0x02, 0x03]);
+    }
+
+    #[test]
+    pub fn test_buf_write_f32() {
+        let mut buf = ByteBuf::new();
+        buf.write_f32(3.14);
+
+        assert_eq!(buf.read_f32().unwrap(), 3.14);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_nbt_compound() {
+        use simdnbt::owned::{BaseNbt, NbtCompound, NbtTag};
+
+        let mut buf = ByteBuf::new();
+        let nbt = NbtCompound::from_values(vec![
+            ("key".into(), NbtTag::String("value".into())),
+        ]);
+        buf.write_nbt_compound(&nbt);
+
+        let read_nbt = buf.read_nbt_compound().unwrap();
+        assert_eq!(nbt, read_nbt);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_pop_front_i8() {
+        let mut buf = ByteBuf::new();
+        buf.write_u8(0x01);
+        buf.write_u8(0x02);
+        buf.write_u8(0x03);
+
+        assert_eq!(buf.pop_front_i8().unwrap(), 0x01);
+        assert_eq!(buf.pop_front_i8().unwrap(), 0x02);
+        assert_eq!(buf.pop_front_i8().unwrap(), 0x03);
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_i16_i32() {
+        let mut buf = ByteBuf::new();
+        buf.write_i16(-2);
+        buf.write_i32(-65536);
+        assert_eq!(buf.as_slice(), &[0xFF, 0xFE, 0xFF, 0xFF, 0x00, 0x00]);
+
+        assert_eq!(buf.read_i16().unwrap(), -2);
+        assert_eq!(buf.read_i32().unwrap(), -65536);
+        assert!(matches!(buf.read_i16(), Err(DecodeError::Underflow { needed: 2, remaining: 0 })));
+    }
+
+    #[test]
+    pub fn test_buf_write_varlong() {
+        // Sample values from the protocol documentation
+        let samples: [(i64, &[u8]); 6] = [
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (2147483647, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+            (i64::MIN, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+        ];
+        for (val, bytes) in samples {
+            let mut buf = ByteBuf::new();
+            buf.write_varlong(val);
+            assert_eq!(buf.as_slice(), bytes);
+            assert_eq!(buf.read_varlong().unwrap(), val);
+            assert_eq!(buf.remaining_len(), 0);
+        }
+
+        let mut buf = ByteBuf::new();
+        buf.write_buf(&[0xFF; 11]);
+        assert_eq!(buf.read_varlong(), Err(DecodeError::VarLongTooLong));
+    }
+
+    #[test]
+    pub fn test_buf_write_position() {
+        let mut buf = ByteBuf::new();
+        let positions = [
+            Position::new(0, 0, 0),
+            Position::new(-1, -64, -1),
+            Position::new(33554431, 2047, -33554432),
+        ];
+        for pos in &positions {
+            buf.write_position(pos);
+        }
+        for pos in &positions {
+            assert_eq!(&buf.read_position().unwrap(), pos);
+        }
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_angle() {
+        let mut buf = ByteBuf::new();
+        buf.write_angle(Angle::from_degrees(180.0));
+        assert_eq!(buf.as_slice(), &[128]);
+        assert_eq!(buf.read_angle().unwrap(), Angle(128));
+    }
+
+    #[test]
+    pub fn test_buf_write_identifier() {
+        let mut buf = ByteBuf::new();
+        buf.write_identifier(&Identifier::minecraft("overworld").unwrap());
+        assert_eq!(buf.read_identifier().unwrap().to_string(), "minecraft:overworld");
+
+        // Identifiers without a namespace are in the minecraft namespace
+        buf.write_string("stone");
+        assert_eq!(buf.read_identifier().unwrap(), Identifier::minecraft("stone").unwrap());
+
+        buf.write_string("Not An Identifier");
+        assert!(matches!(buf.read_identifier(), Err(DecodeError::InvalidData(_))));
+    }
+
+    #[test]
+    pub fn test_buf_write_bitset() {
+        let mut buf = ByteBuf::new();
+        let bits: BitSet = [0, 3, 64, 130].into_iter().collect();
+        buf.write_bitset(&bits);
+
+        assert_eq!(buf.read_varint().unwrap(), 3);
+        assert_eq!(buf.read_u64().unwrap(), 0b1001);
+        assert_eq!(buf.read_u64().unwrap(), 1);
+        assert_eq!(buf.read_u64().unwrap(), 1 << 2);
+
+        buf.write_bitset(&bits);
+        assert_eq!(buf.read_bitset().unwrap(), bits);
+
+        // An empty set is an empty array
+        buf.write_bitset(&BitSet::new());
+        assert_eq!(buf.as_slice(), &[0x00]);
+        assert!(buf.read_bitset().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_buf_write_fixed_bitset() {
+        let mut buf = ByteBuf::new();
+        let bits: BitSet = [0, 9, 20].into_iter().collect();
+        buf.write_fixed_bitset(&bits, 12);
+
+        // Bits past the fixed length are dropped
+        assert_eq!(buf.as_slice(), &[0x01, 0x02]);
+        let expected: BitSet = [0, 9].into_iter().collect();
+        assert_eq!(buf.read_fixed_bitset(12).unwrap(), expected);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_prefixed_option() {
+        let mut buf = ByteBuf::new();
+        buf.write_prefixed_option(Some(&42), |buf, val| {
+            buf.write_varint(*val);
+        });
+        buf.write_prefixed_option::<i32>(None, |buf, val| {
+            buf.write_varint(*val);
+        });
+        assert_eq!(buf.as_slice(), &[0x01, 42, 0x00]);
+
+        assert_eq!(buf.read_prefixed_option(|buf| buf.read_varint()).unwrap(), Some(42));
+        assert_eq!(buf.read_prefixed_option(|buf| buf.read_varint()).unwrap(), None);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_prefixed_array() {
+        let mut buf = ByteBuf::new();
+        let names = vec!["minecraft:overworld".to_string(), "minecraft:the_nether".to_string()];
+        buf.write_prefixed_array(&names, |buf, name| {
+            buf.write_string(name);
+        });
+
+        assert_eq!(buf.read_prefixed_array(|buf| buf.read_string()).unwrap(), names);
+        assert_eq!(buf.remaining_len(), 0);
+
+        // A length larger than the data runs out of bytes instead of allocating it
+        buf.write_varint(i32::MAX);
+        assert!(matches!(buf.read_prefixed_array(|buf| buf.read_u8()), Err(DecodeError::Underflow { .. })));
+    }
+
+    #[test]
+    pub fn test_buf_write_slot() {
+        let mut buf = ByteBuf::new();
+        buf.write_slot(&Slot::empty());
+        assert_eq!(buf.as_slice(), &[0x00]);
+        assert!(buf.read_slot().unwrap().is_empty());
+
+        let mut slot = Slot::new(1, 64);
+        slot.components_to_remove.push(5);
+        buf.write_slot(&slot);
+        assert_eq!(buf.as_slice(), &[64, 1, 0, 1, 5]);
+        assert_eq!(buf.read_slot().unwrap(), slot);
+
+        // Components are decoded with the layout of their type
+        slot.components_to_add.push(SlotComponent::Damage(16));
+        buf.write_slot(&slot);
+        assert_eq!(buf.as_slice(), &[64, 1, 1, 1, 3, 16, 5]);
+        assert_eq!(buf.read_slot().unwrap(), slot);
+
+        slot.components_to_add.push(SlotComponent::CustomName(TextComponent::text("Sword")));
+        slot.components_to_add.push(SlotComponent::Enchantments(Enchantments {
+            levels: vec![(13, 5)],
+            show_in_tooltip: true,
+        }));
+        slot.components_to_add.push(SlotComponent::Glider);
+        buf.write_slot(&slot);
+        assert_eq!(buf.read_slot().unwrap(), slot);
+        assert_eq!(buf.remaining_len(), 0);
+
+        // Components missing from older versions are left out
+        buf.write_slot_for(&slot, ProtocolVersion::V1_21_1);
+        let old = buf.read_slot_for(ProtocolVersion::V1_21_1).unwrap();
+        assert_eq!(old.components_to_add, slot.components_to_add[..3]);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_vec3_quaternion() {
+        let mut buf = ByteBuf::new();
+        let vec = Vec3 { x: 1.0, y: -2.5, z: 0.125 };
+        let quat = Quaternion { x: 0.0, y: 0.707, z: 0.0, w: 0.707 };
+        buf.write_vec3(&vec);
+        buf.write_quaternion(&quat);
+        assert_eq!(buf.len(), 12 + 16);
+
+        assert_eq!(buf.read_vec3().unwrap(), vec);
+        assert_eq!(buf.read_quaternion().unwrap(), quat);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_buf_write_text_component() {
+        let mut buf = ByteBuf::new();
+        buf.write_text_component("Hello");
+        // String tag, then its length and content
+        assert_eq!(buf.as_slice(), &[0x08, 0x00, 0x05, b'H', b'e', b'l', b'l', b'o']);
+        assert_eq!(buf.read_text_component().unwrap(), "Hello");
+
+        // Compound with a text field
+        buf.write_buf(&[0x0A, 0x08, 0x00, 0x04, b't', b'e', b'x', b't', 0x00, 0x02, b'h', b'i', 0x00]);
+        assert_eq!(buf.read_text_component().unwrap(), "hi");
+        assert_eq!(buf.remaining_len(), 0);
+
+        buf.write_buf(&[0x01, 0x05]);
+        assert!(matches!(buf.read_text_component(), Err(DecodeError::InvalidData(_))));
+    }
+}
@@ -0,0 +1,381 @@
+use crate::packet::{Packet, PacketBound, PacketManager, PacketStatus};
+use crate::protocol::ProtocolVersion;
+use crate::{ByteBuf, DecodeError, PacketType, ids};
+use bytes::{Bytes, BytesMut};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, oneshot};
+
+// CAPTURE //
+
+/// Start of a capture file, the last byte is the format version.
+pub const MAGIC: &[u8; 8] = b"VOIDCAP\x01";
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(io::Error),
+    InvalidFormat(String),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Io(err) => write!(f, "failed to read capture: {}", err),
+            CaptureError::InvalidFormat(err) => write!(f, "invalid capture: {}", err),
+            CaptureError::Decode(err) => write!(f, "invalid capture record: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(err: io::Error) -> Self {
+        CaptureError::Io(err)
+    }
+}
+
+impl From<DecodeError> for CaptureError {
+    fn from(err: DecodeError) -> Self {
+        CaptureError::Decode(err)
+    }
+}
+
+/// A single frame, after decryption and decompression.
+///
+/// On disk, every record is a u32 length followed by the timestamp (u64),
+/// connection id (u64), protocol version (i32), state (u8), bound (u8),
+/// packet id (varint) and the packet content.
+#[derive(Debug, Clone)]
+pub struct CaptureRecord {
+    /// Microseconds since the Unix epoch
+    pub timestamp: u64,
+    pub connection: u64,
+    pub version: ProtocolVersion,
+    pub state: PacketStatus,
+    pub bound: PacketBound,
+    /// Id in `version`
+    pub id: PacketType,
+    /// Packet content after the id
+    pub data: Bytes,
+}
+
+impl CaptureRecord {
+    /// Record of a packet payload, its id followed by its content.
+    pub fn from_payload(
+        connection: u64,
+        version: ProtocolVersion,
+        state: PacketStatus,
+        bound: PacketBound,
+        payload: &[u8],
+    ) -> Result<CaptureRecord, DecodeError> {
+        let mut buf = ByteBuf::with_capacity(payload.len());
+        buf.write_buf(payload);
+        let id = buf.read_varint()?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+        Ok(CaptureRecord {
+            timestamp,
+            connection,
+            version,
+            state,
+            bound,
+            id,
+            data: buf.freeze(),
+        })
+    }
+
+    /// Resource name of the packet, `None` for ids unknown to its version.
+    pub fn name(&self) -> Option<&'static str> {
+        ids::name(self.version, self.state, self.bound, self.id)
+    }
+
+    /// Decodes the packet with the existing packet types.
+    pub fn decode(&self) -> Result<Box<dyn Packet>, DecodeError> {
+        let packets = PacketManager::new(self.version);
+        let id = packets
+            .latest_id(self.state, self.bound, self.id)
+            .ok_or(DecodeError::UnknownPacketId(self.id))?;
+        let mut buf = ByteBuf::from_bytes(BytesMut::from(&self.data[..]));
+        packets.decode_dyn(self.state, self.bound, id, &mut buf)
+    }
+
+    /// Packet id followed by its content, as found in a frame.
+    pub fn payload(&self) -> ByteBuf {
+        let mut buf = ByteBuf::with_capacity(self.data.len() + 5);
+        buf.write_varint(self.id);
+        buf.write_buf(&self.data);
+        buf
+    }
+
+    fn write_to(&self, buf: &mut ByteBuf) {
+        let mut record = ByteBuf::with_capacity(self.data.len() + 32);
+        record.write_u64(self.timestamp);
+        record.write_u64(self.connection);
+        record.write_i32(self.version.protocol());
+        record.write_u8(self.state as u8);
+        record.write_u8(match self.bound {
+            PacketBound::Client => 0,
+            PacketBound::Server => 1,
+        });
+        record.write_varint(self.id);
+        record.write_buf(&self.data);
+
+        buf.write_u32(record.len() as u32);
+        buf.write_buf(record.as_slice());
+    }
+
+    fn read_from(mut buf: ByteBuf) -> Result<CaptureRecord, CaptureError> {
+        let timestamp = buf.read_u64()?;
+        let connection = buf.read_u64()?;
+        let protocol = buf.read_i32()?;
+        let version = ProtocolVersion::from_protocol(protocol)
+            .ok_or(CaptureError::InvalidFormat(format!("unknown protocol version {}", protocol)))?;
+        let state = buf.read_u8()?;
+        let state = PacketStatus::from_id(state as i32)
+            .ok_or(CaptureError::InvalidFormat(format!("unknown state {}", state)))?;
+        let bound = match buf.read_u8()? {
+            0 => PacketBound::Client,
+            1 => PacketBound::Server,
+            bound => return Err(CaptureError::InvalidFormat(format!("unknown bound {}", bound))),
+        };
+        let id = buf.read_varint()?;
+        Ok(CaptureRecord {
+            timestamp,
+            connection,
+            version,
+            state,
+            bound,
+            id,
+            data: buf.freeze(),
+        })
+    }
+}
+
+/// Message to the writer task of a capture.
+enum CaptureMessage {
+    Record(CaptureRecord),
+    /// Answered once everything recorded before is on disk
+    Flush(oneshot::Sender<io::Result<()>>),
+}
+
+/// Opt-in recorder of every frame going through the server.
+///
+/// Records are written to the file by a background task, so recording never
+/// waits on the disk. Clones record to the same file.
+#[derive(Clone)]
+pub struct Capture {
+    records: mpsc::UnboundedSender<CaptureMessage>,
+}
+
+impl Capture {
+    /// Creates (or truncates) a capture file. Must be called within a Tokio runtime.
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<Capture> {
+        let mut file = BufWriter::new(tokio::fs::File::create(path).await?);
+        file.write_all(MAGIC).await?;
+        file.flush().await?;
+
+        let (records, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Capture::write_records(file, receiver));
+        Ok(Capture { records })
+    }
+
+    async fn write_records(mut file: BufWriter<tokio::fs::File>, mut records: mpsc::UnboundedReceiver<CaptureMessage>) {
+        let mut buf = ByteBuf::new();
+        while let Some(message) = records.recv().await {
+            let record = match message {
+                CaptureMessage::Record(record) => record,
+                CaptureMessage::Flush(done) => {
+                    let _ = done.send(file.flush().await);
+                    continue;
+                }
+            };
+            record.write_to(&mut buf);
+            let result = match file.write_all(&buf.read_all()).await {
+                // Flush once caught up, so the file is usable while the server runs
+                Ok(()) if records.is_empty() => file.flush().await,
+                result => result,
+            };
+            if let Err(err) = result {
+                eprintln!("Failed to write capture, recording stopped: {}", err);
+                return;
+            }
+        }
+    }
+
+    /// Records a packet payload, its id followed by its content.
+    pub fn record(&self, connection: u64, version: ProtocolVersion, state: PacketStatus, bound: PacketBound, payload: &[u8]) {
+        match CaptureRecord::from_payload(connection, version, state, bound, payload) {
+            // Only fails once the writer stopped, which already logged why
+            Ok(record) => {
+                let _ = self.records.send(CaptureMessage::Record(record));
+            }
+            Err(err) => eprintln!("Packet without id not recorded: {:?}", err),
+        }
+    }
+
+    /// Waits until everything recorded so far is written to the file.
+    pub async fn flush(&self) -> io::Result<()> {
+        let (done, flushed) = oneshot::channel();
+        let stopped = || io::Error::other("capture writer stopped");
+        self.records.send(CaptureMessage::Flush(done)).map_err(|_| stopped())?;
+        flushed.await.map_err(|_| stopped())?
+    }
+}
+
+/// Reads the records of a capture file in order.
+pub struct CaptureReader<R: Read> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<CaptureReader<BufReader<File>>, CaptureError> {
+        CaptureReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut reader: R) -> Result<CaptureReader<R>, CaptureError> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(CaptureError::InvalidFormat("not a voidpoc capture".to_string()));
+        }
+        Ok(CaptureReader { reader })
+    }
+
+    fn read_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        let mut len = [0; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let mut data = BytesMut::zeroed(u32::from_be_bytes(len) as usize);
+        self.reader.read_exact(&mut data)?;
+        CaptureRecord::read_from(ByteBuf::from_bytes(data)).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ByteBuf;
+    use crate::capture::{Capture, CaptureReader, CaptureRecord, MAGIC};
+    use crate::handshake::ServerBoundHandshakePacket;
+    use crate::packet::{Packet, PacketBound, PacketManager, PacketStatus};
+    use crate::protocol::ProtocolVersion;
+    use std::io::Cursor;
+
+    fn handshake_payload() -> ByteBuf {
+        let mut buf = ByteBuf::new();
+        PacketManager::default().encode(
+            &ServerBoundHandshakePacket {
+                protocol_version: 769,
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: 2,
+            },
+            &mut buf,
+        );
+        buf
+    }
+
+    #[test]
+    pub fn test_capture_record_round_trip() {
+        let payload = handshake_payload();
+        let record = CaptureRecord::from_payload(
+            3,
+            ProtocolVersion::V1_21_3,
+            PacketStatus::Handshake,
+            PacketBound::Server,
+            payload.as_slice(),
+        )
+        .unwrap();
+        assert_eq!(record.payload().as_slice(), payload.as_slice());
+
+        let mut file = ByteBuf::new();
+        file.write_buf(MAGIC);
+        record.write_to(&mut file);
+        record.write_to(&mut file);
+
+        let records: Vec<CaptureRecord> = CaptureReader::new(Cursor::new(file.as_slice().to_vec()))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, record.timestamp);
+        assert_eq!(records[0].connection, 3);
+        assert_eq!(records[0].version, ProtocolVersion::V1_21_3);
+        assert_eq!(records[0].bound, PacketBound::Server);
+        assert_eq!(records[0].name(), Some("minecraft:intention"));
+
+        let packet = records[0].decode().unwrap();
+        let handshake = (packet.as_ref() as &dyn std::any::Any)
+            .downcast_ref::<ServerBoundHandshakePacket>()
+            .unwrap();
+        assert_eq!(handshake.server_address, "localhost");
+        assert_eq!(handshake.name(), "minecraft:intention");
+    }
+
+    #[test]
+    pub fn test_capture_reader_invalid() {
+        assert!(CaptureReader::new(Cursor::new(b"NOTACAPTURE".to_vec())).is_err());
+
+        // Truncated record
+        let mut file = ByteBuf::new();
+        file.write_buf(MAGIC);
+        file.write_u32(10);
+        file.write_u64(0);
+        let mut reader = CaptureReader::new(Cursor::new(file.as_slice().to_vec())).unwrap();
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_capture_file() {
+        let path = std::env::temp_dir().join(format!("voidpoc-capture-{}.bin", std::process::id()));
+        let capture = Capture::create(&path).await.unwrap();
+        let payload = handshake_payload();
+        for connection in 0..3 {
+            capture.record(connection, ProtocolVersion::LATEST, PacketStatus::Handshake, PacketBound::Server, payload.as_slice());
+        }
+        drop(capture);
+
+        // The writer task flushes once it caught up
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            records = CaptureReader::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+            if records.len() == 3 {
+                break;
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.iter().map(|record| record.connection).collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[tokio::test]
+    pub async fn test_capture_flush() {
+        let path = std::env::temp_dir().join(format!("voidpoc-capture-flush-{}.bin", std::process::id()));
+        let capture = Capture::create(&path).await.unwrap();
+        let payload = handshake_payload();
+        capture.record(7, ProtocolVersion::LATEST, PacketStatus::Handshake, PacketBound::Server, payload.as_slice());
+        capture.flush().await.unwrap();
+
+        let records = CaptureReader::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].connection, 7);
+    }
+}
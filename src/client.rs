@@ -0,0 +1,598 @@
+use crate::configuration::{
+    ClientBoundConfigurationClientBoundKnownPacks, ClientBoundConfigurationKeepAlivePacket, Pack,
+    ServerBoundConfigurationAcknowledgeFinishConfiguration, ServerBoundConfigurationKeepAlivePacket,
+    ServerBoundConfigurationKnownPacks,
+};
+use crate::crypto::{self, StreamDecryptor, StreamEncryptor};
+use crate::frame::{FrameDecoder, FrameEncoder, FrameError};
+use crate::handshake::ServerBoundHandshakePacket;
+use crate::login::{
+    ClientBoundLoginEncryptionRequestPacket, ClientBoundLoginLoginSuccessPacket, ClientBoundLoginSetCompressionPacket,
+    ServerBoundLoginEncryptionResponsePacket, ServerBoundLoginLoginAcknowledgedPacket, ServerBoundLoginStartPacket,
+};
+use crate::packet::{ClientBoundPlayKeepAlivePacket, Packet, PacketBound, PacketManager, PacketStatus};
+use crate::play::{ClientBoundPlayPlayerPosition, ServerBoundPlayConfirmTeleportationPacket, ServerBoundPlayKeepAlivePacket};
+use crate::protocol::ProtocolVersion;
+use crate::status::{ClientBoundStatusResponsePacket, ServerBoundStatusRequestPacket, ServerStatusResponse};
+use crate::text::TextComponent;
+use crate::{ByteBuf, DecodeError, PacketType, auth, ids};
+use bytes::Bytes;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, io};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+
+// CLIENT //
+
+// Received packets waiting to be consumed before the client stops reading the socket
+const PACKET_QUEUE_LEN: usize = 256;
+const DEFAULT_PORT: u16 = 25565;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    Frame(FrameError),
+    Decode(DecodeError),
+    /// Server closed the connection, with the text of its reason.
+    Disconnected(String),
+    /// Server is in online mode, which needs a Mojang account.
+    OnlineMode,
+    Encryption(String),
+    /// Packet does not exist in the client's protocol version.
+    UnsupportedPacket(&'static str),
+    Closed,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(err) => write!(f, "connection error: {}", err),
+            ClientError::Frame(err) => write!(f, "invalid frame: {}", err),
+            ClientError::Decode(err) => write!(f, "invalid packet: {:?}", err),
+            ClientError::Disconnected(reason) => write!(f, "disconnected by the server: {}", reason),
+            ClientError::OnlineMode => write!(f, "server is in online mode"),
+            ClientError::Encryption(err) => write!(f, "failed to enable encryption: {}", err),
+            ClientError::UnsupportedPacket(name) => write!(f, "packet {} does not exist in this protocol version", name),
+            ClientError::Closed => write!(f, "connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        ClientError::Io(err)
+    }
+}
+
+impl From<FrameError> for ClientError {
+    fn from(err: FrameError) -> Self {
+        ClientError::Frame(err)
+    }
+}
+
+impl From<DecodeError> for ClientError {
+    fn from(err: DecodeError) -> Self {
+        ClientError::Decode(err)
+    }
+}
+
+/// Connection settings of a `Client`.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// `host:port` of the server, the port defaults to 25565
+    pub address: String,
+    pub username: String,
+    pub version: ProtocolVersion,
+}
+
+impl ClientOptions {
+    pub fn new(address: &str, username: &str) -> ClientOptions {
+        ClientOptions {
+            address: address.to_string(),
+            username: username.to_string(),
+            version: ProtocolVersion::LATEST,
+        }
+    }
+
+    fn host_port(&self) -> (String, u16) {
+        match self.address.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => (host.to_string(), port.parse().unwrap()),
+            _ => (self.address.clone(), DEFAULT_PORT),
+        }
+    }
+}
+
+/// Clientbound packet received by a `Client`.
+pub struct ReceivedPacket {
+    pub state: PacketStatus,
+    /// Id in the latest protocol version, `None` for packets removed since
+    pub id: Option<PacketType>,
+    /// Resource name in the client's protocol version
+    pub name: Option<&'static str>,
+    /// Decoded packet, for the packets this crate implements
+    pub packet: Option<Box<dyn Packet>>,
+    /// Content after the packet id, as received
+    pub data: Bytes,
+}
+
+impl ReceivedPacket {
+    pub fn is<T: Packet>(&self) -> bool {
+        self.downcast_ref::<T>().is_some()
+    }
+
+    pub fn downcast_ref<T: Packet>(&self) -> Option<&T> {
+        let packet: &dyn std::any::Any = self.packet.as_deref()?;
+        packet.downcast_ref::<T>()
+    }
+}
+
+/// Sending half, shared between the `Client` and its reader task.
+struct ClientWriter {
+    stream: OwnedWriteHalf,
+    encoder: FrameEncoder,
+    encryptor: Option<StreamEncryptor>,
+    packets: PacketManager,
+}
+
+impl ClientWriter {
+    async fn send(&mut self, packet: &(dyn Packet + Sync)) -> Result<(), ClientError> {
+        let mut buf = self.encoder.begin();
+        if !self.packets.encode(packet, &mut buf) {
+            return Err(ClientError::UnsupportedPacket(packet.name()));
+        }
+        let mut data = self.encoder.finish(buf);
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(&mut data);
+        }
+        self.stream.write_all(&data).await?;
+        Ok(())
+    }
+}
+
+/// Receiving half, owned by the reader task which answers the packets a
+/// vanilla client must answer to stay connected.
+struct ClientReader {
+    stream: OwnedReadHalf,
+    decoder: FrameDecoder,
+    decryptor: Option<StreamDecryptor>,
+    packets: PacketManager,
+    writer: Arc<Mutex<ClientWriter>>,
+    state: watch::Sender<PacketStatus>,
+    received: mpsc::Sender<ReceivedPacket>,
+}
+
+impl ClientReader {
+    async fn run(mut self) -> Result<(), ClientError> {
+        let mut buf = [0; 4096];
+        loop {
+            let len = self.stream.read(&mut buf).await?;
+            if len == 0 {
+                return Err(ClientError::Closed);
+            }
+            let data = &mut buf[..len];
+            if let Some(decryptor) = &mut self.decryptor {
+                decryptor.decrypt(data);
+            }
+            self.decoder.feed(data);
+
+            while let Some(mut frame) = self.decoder.next_frame()? {
+                let wire_id = frame.read_varint()?;
+                let data = Bytes::copy_from_slice(frame.as_slice());
+                let received = self.handle(wire_id, frame, data).await?;
+                if self.received.send(received).await.is_err() {
+                    // Client was dropped
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle(&mut self, wire_id: PacketType, mut frame: ByteBuf, data: Bytes) -> Result<ReceivedPacket, ClientError> {
+        let state = *self.state.borrow();
+        let version = self.packets.version;
+        let id = self.packets.latest_id(state, PacketBound::Client, wire_id);
+        let name = ids::name(version, state, PacketBound::Client, wire_id);
+        let packet = match id {
+            Some(id) => match self.packets.decode_dyn(state, PacketBound::Client, id, &mut frame) {
+                Ok(packet) => Some(packet),
+                Err(DecodeError::UnknownPacketId(_)) => None,
+                Err(err) => return Err(err.into()),
+            },
+            None => None,
+        };
+        let received = ReceivedPacket {
+            state,
+            id,
+            name,
+            packet,
+            data,
+        };
+
+        match (state, name) {
+            (PacketStatus::Login, Some("minecraft:login_disconnect")) => {
+                let mut buf = ByteBuf::from_bytes(received.data[..].into());
+                let reason: TextComponent = buf.read_json()?;
+                return Err(ClientError::Disconnected(reason.to_plain()));
+            }
+            (PacketStatus::Configuration | PacketStatus::Play, Some("minecraft:disconnect")) => {
+                let mut buf = ByteBuf::from_bytes(received.data[..].into());
+                return Err(ClientError::Disconnected(buf.read_text_component()?));
+            }
+            _ => {}
+        }
+
+        if let Some(request) = received.downcast_ref::<ClientBoundLoginEncryptionRequestPacket>() {
+            self.enable_encryption(request).await?;
+        } else if let Some(compression) = received.downcast_ref::<ClientBoundLoginSetCompressionPacket>() {
+            let threshold = (compression.threshold >= 0).then_some(compression.threshold);
+            self.decoder.set_compression(threshold);
+            self.writer.lock().await.encoder.set_compression(threshold);
+        } else if received.is::<ClientBoundLoginLoginSuccessPacket>() {
+            self.send(&ServerBoundLoginLoginAcknowledgedPacket::default()).await?;
+            self.state.send_replace(PacketStatus::Configuration);
+        } else if let Some(packs) = received.downcast_ref::<ClientBoundConfigurationClientBoundKnownPacks>() {
+            // Claim to know every pack the server knows, so it does not send their registries
+            let known_packs = packs
+                .known_packs
+                .iter()
+                .map(|pack| Pack {
+                    namespace: pack.namespace.clone(),
+                    id: pack.id.clone(),
+                    version: pack.version.clone(),
+                })
+                .collect();
+            self.send(&ServerBoundConfigurationKnownPacks { known_packs }).await?;
+        } else if let Some(keep_alive) = received.downcast_ref::<ClientBoundConfigurationKeepAlivePacket>() {
+            self.send(&ServerBoundConfigurationKeepAlivePacket { id: keep_alive.id }).await?;
+        } else if received.name == Some("minecraft:finish_configuration") {
+            self.send(&ServerBoundConfigurationAcknowledgeFinishConfiguration::default()).await?;
+            self.state.send_replace(PacketStatus::Play);
+        } else if let Some(keep_alive) = received.downcast_ref::<ClientBoundPlayKeepAlivePacket>() {
+            self.send(&ServerBoundPlayKeepAlivePacket { id: keep_alive.id }).await?;
+        } else if let Some(position) = received.downcast_ref::<ClientBoundPlayPlayerPosition>() {
+            self.send(&ServerBoundPlayConfirmTeleportationPacket {
+                teleport_id: position.teleport_id,
+            })
+            .await?;
+        }
+        Ok(received)
+    }
+
+    async fn send(&self, packet: &(dyn Packet + Sync)) -> Result<(), ClientError> {
+        self.writer.lock().await.send(packet).await
+    }
+
+    async fn enable_encryption(&mut self, request: &ClientBoundLoginEncryptionRequestPacket) -> Result<(), ClientError> {
+        if request.should_authenticate {
+            return Err(ClientError::OnlineMode);
+        }
+        let encryption_error = |err: rsa::Error| ClientError::Encryption(err.to_string());
+        let shared_secret = crypto::generate_shared_secret();
+        let response = ServerBoundLoginEncryptionResponsePacket {
+            shared_secret: crypto::encrypt_with_public_key(&request.public_key, &shared_secret).map_err(encryption_error)?,
+            verify_token: crypto::encrypt_with_public_key(&request.public_key, &request.verify_token)
+                .map_err(encryption_error)?,
+        };
+        let (encryptor, decryptor) =
+            crypto::stream_ciphers(&shared_secret).map_err(|err| ClientError::Encryption(err.to_string()))?;
+
+        // The response itself is the last packet sent in clear
+        let mut writer = self.writer.lock().await;
+        writer.send(&response).await?;
+        writer.encryptor = Some(encryptor);
+        self.decryptor = Some(decryptor);
+        Ok(())
+    }
+}
+
+/// Bot client, logging in like a vanilla client in offline mode.
+///
+/// Keep-alives, known packs, teleport confirmations and the state switches are
+/// answered in the background. Every received packet, including those, is then
+/// available through `next_packet` or as a `Stream`.
+pub struct Client {
+    version: ProtocolVersion,
+    writer: Arc<Mutex<ClientWriter>>,
+    state: watch::Receiver<PacketStatus>,
+    received: mpsc::Receiver<ReceivedPacket>,
+    task: JoinHandle<Result<(), ClientError>>,
+}
+
+impl Client {
+    /// Connects with the latest protocol version and logs in.
+    pub async fn connect(address: &str, username: &str) -> Result<Client, ClientError> {
+        Client::connect_with(ClientOptions::new(address, username)).await
+    }
+
+    /// Connects and logs in, returning once the server accepted the login.
+    pub async fn connect_with(options: ClientOptions) -> Result<Client, ClientError> {
+        let (host, port) = options.host_port();
+        let stream = TcpStream::connect((host.as_str(), port)).await?;
+        stream.set_nodelay(true)?;
+        let (read_half, write_half) = stream.into_split();
+
+        let packets = PacketManager::new(options.version);
+        let mut writer = ClientWriter {
+            stream: write_half,
+            encoder: FrameEncoder::new(),
+            encryptor: None,
+            packets,
+        };
+        writer
+            .send(&ServerBoundHandshakePacket {
+                protocol_version: options.version.protocol(),
+                server_address: host,
+                server_port: port,
+                next_state: PacketStatus::Login as i32,
+            })
+            .await?;
+        writer
+            .send(&ServerBoundLoginStartPacket {
+                name: options.username.clone(),
+                uuid: auth::offline_uuid(&options.username),
+            })
+            .await?;
+
+        let writer = Arc::new(Mutex::new(writer));
+        let (state_sender, mut state) = watch::channel(PacketStatus::Login);
+        let (received_sender, received) = mpsc::channel(PACKET_QUEUE_LEN);
+        let reader = ClientReader {
+            stream: read_half,
+            decoder: FrameDecoder::new(),
+            decryptor: None,
+            packets,
+            writer: writer.clone(),
+            state: state_sender,
+            received: received_sender,
+        };
+        let task = tokio::spawn(reader.run());
+
+        if state.wait_for(|state| *state != PacketStatus::Login).await.is_err() {
+            // Reader stopped before the login succeeded
+            return match task.await {
+                Ok(Err(err)) => Err(err),
+                _ => Err(ClientError::Closed),
+            };
+        }
+
+        Ok(Client {
+            version: options.version,
+            writer,
+            state,
+            received,
+            task,
+        })
+    }
+
+    /// Queries the status of a server, as the multiplayer server list does.
+    pub async fn status(address: &str) -> Result<ServerStatusResponse, ClientError> {
+        let options = ClientOptions::new(address, "");
+        let (host, port) = options.host_port();
+        let mut stream = TcpStream::connect((host.as_str(), port)).await?;
+
+        let packets = PacketManager::default();
+        let encoder = FrameEncoder::new();
+        for packet in [
+            &ServerBoundHandshakePacket {
+                protocol_version: options.version.protocol(),
+                server_address: host,
+                server_port: port,
+                next_state: PacketStatus::Status as i32,
+            } as &dyn Packet,
+            &ServerBoundStatusRequestPacket::default(),
+        ] {
+            let mut buf = encoder.begin();
+            packets.encode(packet, &mut buf);
+            stream.write_all(&encoder.finish(buf)).await?;
+        }
+
+        let mut decoder = FrameDecoder::new();
+        let mut buf = [0; 4096];
+        loop {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 {
+                return Err(ClientError::Closed);
+            }
+            decoder.feed(&buf[..len]);
+            if let Some(mut frame) = decoder.next_frame()? {
+                let id = frame.read_varint()?;
+                if id != ClientBoundStatusResponsePacket::ID {
+                    return Err(DecodeError::UnknownPacketId(id).into());
+                }
+                let mut packet = ClientBoundStatusResponsePacket::default();
+                packet.decode(&mut frame)?;
+                return Ok(packet.response);
+            }
+        }
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Current connection state, `Configuration` or `Play` once connected.
+    pub fn state(&self) -> PacketStatus {
+        *self.state.borrow()
+    }
+
+    /// Waits until the connection reaches a state, such as `Play`.
+    pub async fn wait_for_state(&mut self, state: PacketStatus) -> Result<(), ClientError> {
+        self.state
+            .wait_for(|current| *current == state)
+            .await
+            .map(|_| ())
+            .map_err(|_| ClientError::Closed)
+    }
+
+    /// Sends a serverbound packet, with the layout of the client's protocol version.
+    pub async fn send<P: Packet + Sync>(&self, packet: P) -> Result<(), ClientError> {
+        self.writer.lock().await.send(&packet).await
+    }
+
+    /// Next received packet, `None` once the connection is closed.
+    pub async fn next_packet(&mut self) -> Option<ReceivedPacket> {
+        self.received.recv().await
+    }
+
+    /// Waits for the connection to end, with the error that ended it.
+    pub async fn closed(&mut self) -> Result<(), ClientError> {
+        match (&mut self.task).await {
+            Ok(result) => result,
+            Err(_) => Err(ClientError::Closed),
+        }
+    }
+}
+
+impl Stream for Client {
+    type Item = ReceivedPacket;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ReceivedPacket>> {
+        self.received.poll_recv(cx)
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ByteBuf;
+    use crate::client::{Client, ClientError, ClientOptions};
+    use crate::configuration::{ClientBoundConfigurationFinishConfigurationPacket, ClientBoundConfigurationKeepAlivePacket};
+    use crate::frame::{FrameDecoder, FrameEncoder};
+    use crate::login::{ClientBoundLoginDisconnectPacket, ClientBoundLoginLoginSuccessPacket};
+    use crate::packet::{Packet, PacketBound, PacketManager, PacketStatus};
+    use crate::play::{ClientBoundPlayGameEventPacket, ServerBoundPlayConfirmTeleportationPacket};
+    use crate::{auth, ids};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_stream::StreamExt;
+
+    /// Server side of the test connection, without compression nor encryption.
+    struct TestServer {
+        stream: TcpStream,
+        decoder: FrameDecoder,
+        packets: PacketManager,
+    }
+
+    impl TestServer {
+        async fn send(&mut self, packet: &(dyn Packet + Sync)) {
+            let encoder = FrameEncoder::new();
+            let mut buf = encoder.begin();
+            assert!(self.packets.encode(packet, &mut buf));
+            self.stream.write_all(&encoder.finish(buf)).await.unwrap();
+        }
+
+        /// Name of the next serverbound packet, and its content.
+        async fn receive(&mut self, state: PacketStatus) -> (&'static str, ByteBuf) {
+            loop {
+                if let Some(mut frame) = self.decoder.next_frame().unwrap() {
+                    let id = frame.read_varint().unwrap();
+                    let name = ids::name(self.packets.version, state, PacketBound::Server, id).unwrap();
+                    return (name, frame);
+                }
+                let mut buf = [0; 1024];
+                let len = self.stream.read(&mut buf).await.unwrap();
+                assert!(len > 0);
+                self.decoder.feed(&buf[..len]);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_client_options_address() {
+        assert_eq!(ClientOptions::new("localhost", "bot").host_port(), ("localhost".to_string(), 25565));
+        assert_eq!(ClientOptions::new("127.0.0.1:25566", "bot").host_port(), ("127.0.0.1".to_string(), 25566));
+    }
+
+    #[tokio::test]
+    pub async fn test_client_login_to_play() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = TestServer {
+                stream,
+                decoder: FrameDecoder::new(),
+                packets: PacketManager::default(),
+            };
+            assert_eq!(server.receive(PacketStatus::Handshake).await.0, "minecraft:intention");
+            assert_eq!(server.receive(PacketStatus::Login).await.0, "minecraft:hello");
+            server
+                .send(&ClientBoundLoginLoginSuccessPacket {
+                    uuid: auth::offline_uuid("bot"),
+                    username: "bot".to_string(),
+                    ..Default::default()
+                })
+                .await;
+            assert_eq!(server.receive(PacketStatus::Login).await.0, "minecraft:login_acknowledged");
+
+            server.send(&ClientBoundConfigurationKeepAlivePacket { id: 42 }).await;
+            let (name, mut buf) = server.receive(PacketStatus::Configuration).await;
+            assert_eq!(name, "minecraft:keep_alive");
+            assert_eq!(buf.read_i64().unwrap(), 42);
+            server.send(&ClientBoundConfigurationFinishConfigurationPacket::default()).await;
+            assert_eq!(server.receive(PacketStatus::Configuration).await.0, "minecraft:finish_configuration");
+
+            server
+                .send(&ClientBoundPlayGameEventPacket {
+                    event_id: 13,
+                    value: 0.0,
+                })
+                .await;
+            // Keep the socket open until the client is done
+            server.receive(PacketStatus::Play).await;
+        });
+
+        let mut client = Client::connect(&address, "bot").await.unwrap();
+        client.wait_for_state(PacketStatus::Play).await.unwrap();
+        let event = loop {
+            let received = client.next().await.unwrap();
+            if let Some(event) = received.downcast_ref::<ClientBoundPlayGameEventPacket>() {
+                break event.event_id;
+            }
+        };
+        assert_eq!(event, 13);
+
+        client
+            .send(ServerBoundPlayConfirmTeleportationPacket { teleport_id: 1 })
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn test_client_login_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = TestServer {
+                stream,
+                decoder: FrameDecoder::new(),
+                packets: PacketManager::default(),
+            };
+            server.receive(PacketStatus::Handshake).await;
+            server.receive(PacketStatus::Login).await;
+            server.send(&ClientBoundLoginDisconnectPacket::new("Server is full")).await;
+        });
+
+        match Client::connect(&address, "bot").await {
+            Err(ClientError::Disconnected(reason)) => assert_eq!(reason, "Server is full"),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("connected"),
+        }
+        server.await.unwrap();
+    }
+}
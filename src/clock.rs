@@ -0,0 +1,70 @@
+use crate::shutdown::Shutdown;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use tokio::time::{MissedTickBehavior, interval};
+
+// GAME CLOCK //
+
+pub const TICK: Duration = Duration::from_millis(50);
+// How often players in Play are sent the game time
+pub const TIME_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Server tick counter, shared by every connection. Ticks missed by an
+/// overloaded server are skipped instead of caught up, so its TPS drops.
+#[derive(Clone, Default)]
+pub struct GameClock {
+    ticks: Arc<AtomicI64>,
+}
+
+impl GameClock {
+    pub fn new() -> GameClock {
+        GameClock::default()
+    }
+
+    /// Ticks since the server started, sent to players as the world age.
+    pub fn game_time(&self) -> i64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    pub fn tick(&self) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Ticks every `TICK` until the shutdown.
+    pub async fn run(self, shutdown: Shutdown) {
+        let mut ticks = interval(TICK);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // The first tick completes immediately
+        ticks.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => self.tick(),
+                _ = shutdown.triggered() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{GameClock, TICK};
+    use crate::shutdown::Shutdown;
+
+    #[tokio::test]
+    pub async fn test_clock_run() {
+        let clock = GameClock::new();
+        let shutdown = Shutdown::new();
+        let task = tokio::spawn(clock.clone().run(shutdown.clone()));
+
+        tokio::time::sleep(TICK * 4 + TICK / 2).await;
+        shutdown.trigger("stop");
+        task.await.unwrap();
+        let ticks = clock.game_time();
+        assert!((3..=5).contains(&ticks), "{} ticks", ticks);
+
+        // Stopped with the server
+        tokio::time::sleep(TICK * 2).await;
+        assert_eq!(clock.game_time(), ticks);
+    }
+}
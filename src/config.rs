@@ -0,0 +1,94 @@
+use crate::players::StatusSample;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+// CONFIG //
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub address: String,
+    /// Packets at least this big are zlib compressed, a negative value disables compression
+    pub compression_threshold: i32,
+    /// Run the encryption handshake during login, encrypting everything sent afterwards
+    pub encryption: bool,
+    /// Authenticate players against the session server, implies encryption
+    pub online_mode: bool,
+    pub session_server: String,
+    /// Record every frame to this capture file, read it back with `voidpoc-replay`
+    pub capture: Option<String>,
+    /// Let in players transferred from another server, refused with a disconnect otherwise
+    pub accepts_transfers: bool,
+    /// Shown in the server list, with `§` codes for colors and decorations
+    pub motd: String,
+    /// Shown in the server list, a 64x64 PNG reloaded when it changes
+    pub icon: String,
+    pub max_players: i32,
+    /// Names of the players allowed to join, everyone when unset
+    pub whitelist: Option<Vec<String>>,
+    /// Name of the world players spawn in
+    pub world: String,
+    /// Settings per hostname players connect with, overriding the ones above
+    pub virtual_hosts: HashMap<String, VirtualHostConfig>,
+    /// Players listed in the server list: `"players"`, `"hidden"` or `{"custom": [lines]}`
+    pub status_sample: StatusSample,
+    /// Shown to connected players when the server stops, with `§` codes
+    pub shutdown_message: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "0.0.0.0:25565".to_string(),
+            compression_threshold: 256,
+            encryption: true,
+            online_mode: false,
+            session_server: "https://sessionserver.mojang.com".to_string(),
+            capture: None,
+            accepts_transfers: false,
+            motd: "HANNNNNNNNNN".to_string(),
+            icon: "server-icon.png".to_string(),
+            max_players: 100,
+            whitelist: None,
+            world: "minecraft:overworld".to_string(),
+            virtual_hosts: HashMap::new(),
+            status_sample: StatusSample::Players,
+            shutdown_message: "Server restarting".to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Loads the config from a JSON file, missing keys (or a missing file) use the defaults.
+    pub fn load(path: &str) -> ServerConfig {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).expect("Failed to parse server config"),
+            Err(_) => ServerConfig::default(),
+        }
+    }
+
+    pub fn compression(&self) -> Option<i32> {
+        if self.compression_threshold >= 0 {
+            Some(self.compression_threshold)
+        } else {
+            None
+        }
+    }
+
+    pub fn encryption_enabled(&self) -> bool {
+        self.encryption || self.online_mode
+    }
+}
+
+/// Settings of a virtual host, the ones left unset are those of the server.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct VirtualHostConfig {
+    pub motd: Option<String>,
+    pub icon: Option<String>,
+    pub max_players: Option<i32>,
+    /// Replaces the whitelist of the server, an empty one lets nobody in
+    pub whitelist: Option<Vec<String>>,
+    pub world: Option<String>,
+}
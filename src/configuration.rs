@@ -1,32 +1,75 @@
-use crate::packet::{Packet, PacketFactory};
-use crate::{ByteBuf, PacketType};
+use crate::field::PacketField;
+use crate::ids;
+use crate::packet::{Packet, PacketBound, PacketFactory, PacketStatus};
+use crate::protocol::ProtocolVersion;
+use crate::text::TextComponent;
+use crate::{ByteBuf, DecodeError, PacketType};
 use serde::{Deserialize, Serialize};
-use std::any::Any;
-use simdnbt::owned::{BaseNbt, Nbt, NbtCompound, NbtTag};
+use simdnbt::owned::{BaseNbt, Nbt, NbtCompound};
 // STATUS //
 
 #[derive(Eq, PartialEq)]
 pub enum EServerConfigurationPacket {
-    ServerBoundConfigurationAcknowledgeFinishConfiguration = 0x03,
+    ServerBoundConfigurationAcknowledgeFinishConfiguration,
+    ServerBoundConfigurationKnownPacks,
+    ServerBoundConfigurationKeepAlivePacket,
+    ServerBoundConfigurationClientInformationPacket,
 }
 
 pub struct ServerConfigurationPacketFactory {}
 
 impl PacketFactory<EServerConfigurationPacket> for ServerConfigurationPacketFactory {
-    fn decode<T: Packet + 'static>(&self, id: i32, buf: &mut ByteBuf) -> Box<T> {
-        let packet: Box<dyn Any> = match id {
-            0x03 => Box::new(ServerBoundConfigurationAcknowledgeFinishConfiguration::default()) as Box<dyn Any>,
-            _ => panic!("Unknown packet id"),
-        };
-        let mut packet: Box<T> = packet.downcast::<T>().expect("Failed to downcast Packet");
-
-        packet.decode(buf);
-        packet
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ServerBoundConfigurationAcknowledgeFinishConfiguration::ID => Some(Box::new(ServerBoundConfigurationAcknowledgeFinishConfiguration::default())),
+            ServerBoundConfigurationKnownPacks::ID => Some(Box::new(ServerBoundConfigurationKnownPacks::default())),
+            ServerBoundConfigurationKeepAlivePacket::ID => Some(Box::new(ServerBoundConfigurationKeepAlivePacket::default())),
+            ServerBoundConfigurationClientInformationPacket::ID => Some(Box::new(ServerBoundConfigurationClientInformationPacket::default())),
+            _ => None,
+        }
     }
 
     fn from_id(&self, id: i32) -> Option<EServerConfigurationPacket> {
         match id {
-            0x03 => Some(EServerConfigurationPacket::ServerBoundConfigurationAcknowledgeFinishConfiguration),
+            ServerBoundConfigurationAcknowledgeFinishConfiguration::ID => Some(EServerConfigurationPacket::ServerBoundConfigurationAcknowledgeFinishConfiguration),
+            ServerBoundConfigurationKnownPacks::ID => Some(EServerConfigurationPacket::ServerBoundConfigurationKnownPacks),
+            ServerBoundConfigurationKeepAlivePacket::ID => Some(EServerConfigurationPacket::ServerBoundConfigurationKeepAlivePacket),
+            ServerBoundConfigurationClientInformationPacket::ID => Some(EServerConfigurationPacket::ServerBoundConfigurationClientInformationPacket),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq)]
+pub enum EClientConfigurationPacket {
+    ClientBoundConfigurationClientBoundKnownPacks,
+    ClientBoundConfigurationRegistryDataPacket,
+    ClientBoundConfigurationFinishConfigurationPacket,
+    ClientBoundConfigurationKeepAlivePacket,
+    ClientBoundConfigurationDisconnectPacket,
+}
+
+pub struct ClientConfigurationPacketFactory {}
+
+impl PacketFactory<EClientConfigurationPacket> for ClientConfigurationPacketFactory {
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ClientBoundConfigurationClientBoundKnownPacks::ID => Some(Box::new(ClientBoundConfigurationClientBoundKnownPacks::default())),
+            ClientBoundConfigurationRegistryDataPacket::ID => Some(Box::new(ClientBoundConfigurationRegistryDataPacket::default())),
+            ClientBoundConfigurationFinishConfigurationPacket::ID => Some(Box::new(ClientBoundConfigurationFinishConfigurationPacket::default())),
+            ClientBoundConfigurationKeepAlivePacket::ID => Some(Box::new(ClientBoundConfigurationKeepAlivePacket::default())),
+            ClientBoundConfigurationDisconnectPacket::ID => Some(Box::new(ClientBoundConfigurationDisconnectPacket::default())),
+            _ => None,
+        }
+    }
+
+    fn from_id(&self, id: i32) -> Option<EClientConfigurationPacket> {
+        match id {
+            ClientBoundConfigurationClientBoundKnownPacks::ID => Some(EClientConfigurationPacket::ClientBoundConfigurationClientBoundKnownPacks),
+            ClientBoundConfigurationRegistryDataPacket::ID => Some(EClientConfigurationPacket::ClientBoundConfigurationRegistryDataPacket),
+            ClientBoundConfigurationFinishConfigurationPacket::ID => Some(EClientConfigurationPacket::ClientBoundConfigurationFinishConfigurationPacket),
+            ClientBoundConfigurationKeepAlivePacket::ID => Some(EClientConfigurationPacket::ClientBoundConfigurationKeepAlivePacket),
+            ClientBoundConfigurationDisconnectPacket::ID => Some(EClientConfigurationPacket::ClientBoundConfigurationDisconnectPacket),
             _ => None,
         }
     }
@@ -38,6 +81,8 @@ impl PacketFactory<EServerConfigurationPacket> for ServerConfigurationPacketFact
 
 // Acknowledge Finish Configuration 0x03
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:finish_configuration", state = Configuration, bound = Server)]
 pub struct ServerBoundConfigurationAcknowledgeFinishConfiguration {}
 
 impl Default for ServerBoundConfigurationAcknowledgeFinishConfiguration {
@@ -46,87 +91,115 @@ impl Default for ServerBoundConfigurationAcknowledgeFinishConfiguration {
     }
 }
 
-impl Packet for ServerBoundConfigurationAcknowledgeFinishConfiguration {
-    fn id(&self) -> PacketType {
-        0x03
-    }
+// Serverbound Known Packs
 
-    fn encode(&self, buf: &mut ByteBuf) {
-    }
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:select_known_packs", state = Configuration, bound = Server)]
+pub struct ServerBoundConfigurationKnownPacks {
+    #[packet(length_prefixed)]
+    pub known_packs: Vec<Pack>,
+}
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-    }
+// Serverbound Keep Alive
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:keep_alive", state = Configuration, bound = Server)]
+pub struct ServerBoundConfigurationKeepAlivePacket {
+    pub id: i64,
+}
+
+// Client Information
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:client_information", state = Configuration, bound = Server)]
+pub struct ServerBoundConfigurationClientInformationPacket {
+    #[packet(max_len = 16)]
+    pub locale: String,
+    pub view_distance: i8,
+    #[packet(varint)]
+    pub chat_mode: i32,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    #[packet(varint)]
+    pub main_hand: i32,
+    pub enable_text_filtering: bool,
+    pub allow_server_listings: bool,
+    #[packet(varint, since = V1_21_3)]
+    pub particle_status: i32,
 }
 
 // CLIENTBOUND //
 
 // Clientbound Known Packs
 
-struct Pack {
+#[derive(PacketField, Debug)]
+pub struct Pack {
     pub namespace: String,
     pub id: String,
     pub version: String,
 }
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:select_known_packs", state = Configuration, bound = Client)]
 pub struct ClientBoundConfigurationClientBoundKnownPacks {
+    #[packet(length_prefixed)]
     pub known_packs: Vec<Pack>,
 }
 
-impl Default for ClientBoundConfigurationClientBoundKnownPacks {
-    fn default() -> Self {
+impl ClientBoundConfigurationClientBoundKnownPacks {
+    /// The vanilla `minecraft:core` pack of a version.
+    pub fn core(version: ProtocolVersion) -> Self {
         ClientBoundConfigurationClientBoundKnownPacks {
             known_packs: vec![
                 Pack {
                     namespace: "minecraft".to_string(),
                     id: "core".to_string(),
-                    version: "1.21.4".to_string(),
+                    version: version.name().to_string(),
                 }
             ]
         }
     }
 }
 
-impl Packet for ClientBoundConfigurationClientBoundKnownPacks {
-    fn id(&self) -> PacketType {
-        0x0E
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_varint(self.known_packs.len() as i32);
-        for pack in &self.known_packs {
-            buf.write_string(&pack.namespace);
-            buf.write_string(&pack.id);
-            buf.write_string(&pack.version);
-        }
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        let count = buf.read_varint();
-        for _ in 0..count {
-            self.known_packs.push(Pack {
-                namespace: buf.read_string(),
-                id: buf.read_string(),
-                version: buf.read_string(),
-            });
-        }
+impl Default for ClientBoundConfigurationClientBoundKnownPacks {
+    fn default() -> Self {
+        ClientBoundConfigurationClientBoundKnownPacks::core(ProtocolVersion::LATEST)
     }
 }
 
 // Registry Data 0x07
 
+#[derive(Debug, Clone)]
 pub struct RegistryEntry {
     pub identifier: String,
     pub data: Option<Nbt>,
 }
 
+#[derive(Debug, Default)]
 pub struct ClientBoundConfigurationRegistryDataPacket {
     pub identifier: String,
     pub entries: Vec<RegistryEntry>,
 }
 
+impl ClientBoundConfigurationRegistryDataPacket {
+    pub const ID: PacketType = ids::configuration::clientbound::REGISTRY_DATA;
+}
+
 impl Packet for ClientBoundConfigurationRegistryDataPacket {
     fn id(&self) -> PacketType {
-        0x07
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "minecraft:registry_data"
+    }
+
+    fn state(&self) -> PacketStatus {
+        PacketStatus::Configuration
+    }
+
+    fn bound(&self) -> PacketBound {
+        PacketBound::Client
     }
 
     fn encode(&self, buf: &mut ByteBuf) {
@@ -147,13 +220,22 @@ impl Packet for ClientBoundConfigurationRegistryDataPacket {
         }
     }
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
+    fn decode(&mut self, buf: &mut ByteBuf) -> Result<(), DecodeError> {
+        self.identifier = buf.read_string()?;
+        self.entries = buf.read_prefixed_array(|buf| {
+            Ok(RegistryEntry {
+                identifier: buf.read_string()?,
+                data: buf.read_prefixed_option(Nbt::read_from)?,
+            })
+        })?;
+        Ok(())
     }
 }
 
 // Finish Configuration 0x03
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:finish_configuration", state = Configuration, bound = Client)]
 pub struct ClientBoundConfigurationFinishConfigurationPacket {}
 
 impl Default for ClientBoundConfigurationFinishConfigurationPacket {
@@ -162,14 +244,24 @@ impl Default for ClientBoundConfigurationFinishConfigurationPacket {
     }
 }
 
-impl Packet for ClientBoundConfigurationFinishConfigurationPacket {
-    fn id(&self) -> PacketType {
-        0x03
-    }
+// Clientbound Keep Alive
 
-    fn encode(&self, buf: &mut ByteBuf) {
-    }
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:keep_alive", state = Configuration, bound = Client)]
+pub struct ClientBoundConfigurationKeepAlivePacket {
+    pub id: i64,
+}
+
+// Disconnect (configuration)
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:disconnect", state = Configuration, bound = Client)]
+pub struct ClientBoundConfigurationDisconnectPacket {
+    pub reason: TextComponent,
+}
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
+impl ClientBoundConfigurationDisconnectPacket {
+    pub fn new(reason: impl Into<TextComponent>) -> ClientBoundConfigurationDisconnectPacket {
+        ClientBoundConfigurationDisconnectPacket { reason: reason.into() }
     }
 }
@@ -0,0 +1,325 @@
+use crate::ByteBuf;
+use crate::capture::Capture;
+use crate::crypto::StreamEncryptor;
+use crate::frame::FrameEncoder;
+use crate::packet::{Packet, PacketBound, PacketManager};
+use crate::protocol::ProtocolVersion;
+use bytes::BytesMut;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, watch};
+
+// CONNECTION //
+
+// Packets waiting to be written before senders have to wait, or the player
+// is disconnected by `try_send`
+pub const OUTBOUND_QUEUE_LEN: usize = 1024;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SendError {
+    /// Connection is closed, or closing
+    Closed,
+    /// Queue was full, the player has been disconnected
+    Overflow,
+    /// Packet does not exist in the player's protocol version
+    UnsupportedPacket(&'static str),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Closed => write!(f, "connection is closed"),
+            SendError::Overflow => write!(f, "outbound queue is full"),
+            SendError::UnsupportedPacket(name) => write!(f, "packet {} does not exist in this protocol version", name),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/// Message to the writer task, handled in queue order.
+enum Outbound {
+    /// Packet id and content, in a buffer from `FrameEncoder::begin`
+    Packet(ByteBuf),
+    /// Bytes written as is, outside of any frame
+    Raw(BytesMut),
+    Compression(Option<i32>),
+    Encryption(Box<StreamEncryptor>),
+    /// Ends the connection, after the packets queued before
+    Close,
+}
+
+/// Cloneable sender of packets to a connected player.
+///
+/// Packets are encoded for the player's protocol version right away, then
+/// framed, compressed, encrypted and written by the connection's writer task.
+#[derive(Clone)]
+pub struct PlayerHandle {
+    id: u64,
+    packets: PacketManager,
+    queue: mpsc::Sender<Outbound>,
+    closed: Arc<watch::Sender<bool>>,
+    // Shut down, packets queued before are still written
+    closing: Arc<AtomicBool>,
+    capture: Option<Capture>,
+    // Milliseconds, as shown in the tab list
+    latency: Arc<AtomicU32>,
+}
+
+impl PlayerHandle {
+    /// Spawns the writer task of a connection. Must be called within a Tokio runtime.
+    pub fn spawn<W>(id: u64, stream: W, capture: Option<Capture>) -> PlayerHandle
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (queue, receiver) = mpsc::channel(OUTBOUND_QUEUE_LEN);
+        let closed = Arc::new(watch::channel(false).0);
+        tokio::spawn(write_queue(stream, receiver, closed.subscribe()));
+        PlayerHandle {
+            id,
+            packets: PacketManager::default(),
+            queue,
+            closed,
+            closing: Arc::new(AtomicBool::new(false)),
+            capture,
+            latency: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.packets.version
+    }
+
+    /// Encodes packets for another version from now on, as after the handshake.
+    /// Existing clones keep the version they had.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.packets = PacketManager::new(version);
+    }
+
+    /// Keep-alive round trip time, zero until measured.
+    pub fn latency(&self) -> Duration {
+        Duration::from_millis(self.latency.load(Ordering::Relaxed) as u64)
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.latency.store(latency.as_millis().min(u32::MAX as u128) as u32, Ordering::Relaxed);
+    }
+
+    fn encode(&self, packet: &dyn Packet) -> Result<Outbound, SendError> {
+        let mut buf = FrameEncoder::new().begin();
+        if !self.packets.encode(packet, &mut buf) {
+            return Err(SendError::UnsupportedPacket(packet.name()));
+        }
+        if let Some(capture) = &self.capture {
+            let payload = FrameEncoder::new().payload(&buf);
+            capture.record(self.id, self.packets.version, packet.state(), PacketBound::Client, payload);
+        }
+        Ok(Outbound::Packet(buf))
+    }
+
+    /// Queues a packet, waiting for room in the queue.
+    pub fn send(&self, packet: &dyn Packet) -> impl Future<Output = Result<(), SendError>> + Send + '_ {
+        // Encoded before waiting, the packet does not have to be `Sync`
+        let outbound = self.encode(packet);
+        async move { self.queue(outbound?).await }
+    }
+
+    /// Queues a packet without waiting, disconnecting the player if the queue
+    /// is full. Meant for broadcasts, which must not wait on a slow player.
+    pub fn try_send(&self, packet: &dyn Packet) -> Result<(), SendError> {
+        match self.queue.try_send(self.encode(packet)?) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.close();
+                Err(SendError::Overflow)
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(SendError::Closed),
+        }
+    }
+
+    /// Queues bytes that are not a packet, written without framing nor
+    /// encryption, such as the legacy ping response.
+    pub async fn send_raw(&self, data: &[u8]) -> Result<(), SendError> {
+        self.queue(Outbound::Raw(BytesMut::from(data))).await
+    }
+
+    /// Compresses the packets queued from now on.
+    pub async fn set_compression(&self, threshold: Option<i32>) -> Result<(), SendError> {
+        self.queue(Outbound::Compression(threshold)).await
+    }
+
+    /// Encrypts the packets queued from now on.
+    pub async fn enable_encryption(&self, encryptor: StreamEncryptor) -> Result<(), SendError> {
+        self.queue(Outbound::Encryption(Box::new(encryptor))).await
+    }
+
+    async fn queue(&self, outbound: Outbound) -> Result<(), SendError> {
+        if self.is_closed() {
+            return Err(SendError::Closed);
+        }
+        self.queue.send(outbound).await.map_err(|_| SendError::Closed)
+    }
+
+    /// Closes the connection right away, dropping the queued packets.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    /// Closes the connection once the packets queued so far are written, such
+    /// as a disconnect packet. Nothing can be queued anymore. Closes it right
+    /// away if the queue is full.
+    pub fn shutdown(&self) {
+        if self.closing.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        if self.queue.try_send(Outbound::Close).is_err() {
+            self.close();
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow() || self.closing.load(Ordering::Relaxed) || self.queue.is_closed()
+    }
+
+    /// Waits until the connection is closed, by any handle or the writer task.
+    /// After a shutdown, waits until the queued packets are written.
+    pub async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        tokio::select! {
+            _ = closed.wait_for(|closed| *closed) => {}
+            _ = self.queue.closed() => {}
+        }
+    }
+}
+
+/// Writes queued packets until every handle is dropped, the connection is
+/// closed or writing fails.
+async fn write_queue<W>(mut stream: W, mut queue: mpsc::Receiver<Outbound>, mut closed: watch::Receiver<bool>)
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut encoder = FrameEncoder::new();
+    let mut encryptor: Option<StreamEncryptor> = None;
+    loop {
+        let outbound = tokio::select! {
+            outbound = queue.recv() => outbound,
+            // Dropped with the last handle, the queue is drained first
+            Ok(_) = closed.wait_for(|closed| *closed) => None,
+        };
+        let data = match outbound {
+            Some(Outbound::Packet(buf)) => {
+                let mut data = encoder.finish(buf);
+                if let Some(encryptor) = &mut encryptor {
+                    encryptor.encrypt(&mut data);
+                }
+                data
+            }
+            Some(Outbound::Raw(data)) => data,
+            Some(Outbound::Compression(threshold)) => {
+                encoder.set_compression(threshold);
+                continue;
+            }
+            Some(Outbound::Encryption(new_encryptor)) => {
+                encryptor = Some(*new_encryptor);
+                continue;
+            }
+            Some(Outbound::Close) | None => break,
+        };
+        if stream.write_all(&data).await.is_err() {
+            break;
+        }
+        // Flush once caught up
+        if queue.is_empty() && stream.flush().await.is_err() {
+            break;
+        }
+    }
+    // Stops the senders, then the stream
+    queue.close();
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::{OUTBOUND_QUEUE_LEN, PlayerHandle, SendError};
+    use crate::frame::FrameDecoder;
+    use crate::packet::ClientBoundPlayKeepAlivePacket;
+    use crate::protocol::ProtocolVersion;
+    use crate::ids;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    pub async fn test_connection_send_in_order() {
+        let (stream, mut client) = tokio::io::duplex(4096);
+        let mut handle = PlayerHandle::spawn(0, stream, None);
+        handle.set_version(ProtocolVersion::V1_21_1);
+        let other = handle.clone();
+
+        handle.send(&ClientBoundPlayKeepAlivePacket { id: 1 }).await.unwrap();
+        handle.set_compression(Some(0)).await.unwrap();
+        other.send(&ClientBoundPlayKeepAlivePacket { id: 2 }).await.unwrap();
+        drop((handle, other));
+
+        let mut data = Vec::new();
+        client.read_to_end(&mut data).await.unwrap();
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&data);
+        let mut ids = Vec::new();
+        while let Some(mut frame) = decoder.next_frame().unwrap() {
+            assert_eq!(frame.read_varint().unwrap(), ids::v1_21_1::play::clientbound::KEEP_ALIVE);
+            ids.push(frame.read_i64().unwrap());
+            // The second packet is compressed
+            decoder.set_compression(Some(0));
+        }
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[tokio::test]
+    pub async fn test_connection_shutdown() {
+        let (stream, mut client) = tokio::io::duplex(4096);
+        let handle = PlayerHandle::spawn(0, stream, None);
+        let other = handle.clone();
+        handle.send(&ClientBoundPlayKeepAlivePacket { id: 1 }).await.unwrap();
+        handle.shutdown();
+        assert!(other.is_closed());
+        assert_eq!(other.send(&ClientBoundPlayKeepAlivePacket { id: 2 }).await, Err(SendError::Closed));
+        other.closed().await;
+
+        // Written before closing, though a handle is left
+        let mut data = Vec::new();
+        client.read_to_end(&mut data).await.unwrap();
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&data);
+        let mut frame = decoder.next_frame().unwrap().unwrap();
+        frame.read_varint().unwrap();
+        assert_eq!(frame.read_i64().unwrap(), 1);
+        assert!(decoder.next_frame().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn test_connection_overflow() {
+        // Nothing reads the stream, the writer task blocks once it is full
+        let (stream, _client) = tokio::io::duplex(64);
+        let handle = PlayerHandle::spawn(0, stream, None);
+        let packet = ClientBoundPlayKeepAlivePacket { id: 0 };
+
+        let mut result = Ok(());
+        for _ in 0..OUTBOUND_QUEUE_LEN + 16 {
+            result = handle.try_send(&packet);
+            if result.is_err() {
+                break;
+            }
+        }
+        assert_eq!(result, Err(SendError::Overflow));
+        handle.closed().await;
+        assert!(handle.is_closed());
+        assert_eq!(handle.send(&packet).await, Err(SendError::Closed));
+    }
+}
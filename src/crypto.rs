@@ -0,0 +1,168 @@
+use aes::Aes128;
+use cfb8::cipher::inout::InOutBuf;
+use cfb8::cipher::{BlockDecryptMut, BlockEncryptMut, InvalidLength, KeyIvInit};
+use rand::RngCore;
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+
+// ENCRYPTION //
+
+// Same key size as vanilla
+const RSA_KEY_BITS: usize = 1024;
+
+/// RSA keypair generated at startup, used to exchange the shared secret during login.
+pub struct ServerKeyPair {
+    private_key: RsaPrivateKey,
+    pub public_key_der: Vec<u8>,
+}
+
+impl ServerKeyPair {
+    pub fn generate() -> ServerKeyPair {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), RSA_KEY_BITS)
+            .expect("Failed to generate RSA keypair");
+        let public_key_der = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .expect("Failed to encode RSA public key")
+            .into_vec();
+
+        ServerKeyPair {
+            private_key,
+            public_key_der,
+        }
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, rsa::Error> {
+        self.private_key.decrypt(Pkcs1v15Encrypt, data)
+    }
+}
+
+pub fn generate_verify_token() -> Vec<u8> {
+    let mut token = vec![0; 4];
+    rand::thread_rng().fill_bytes(&mut token);
+    token
+}
+
+/// Random AES key chosen by a client, sent encrypted with the server's public key.
+pub fn generate_shared_secret() -> Vec<u8> {
+    let mut secret = vec![0; 16];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Encrypts with a server's DER encoded public key, as a client does during login.
+pub fn encrypt_with_public_key(public_key_der: &[u8], data: &[u8]) -> Result<Vec<u8>, rsa::Error> {
+    let public_key = RsaPublicKey::from_public_key_der(public_key_der).map_err(|e| rsa::Error::Pkcs8(e.into()))?;
+    public_key.encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, data)
+}
+
+/// Encrypts every outgoing byte with AES-128-CFB8.
+pub struct StreamEncryptor(cfb8::Encryptor<Aes128>);
+
+impl StreamEncryptor {
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        let (blocks, _) = InOutBuf::from(data).into_chunks();
+        self.0.encrypt_blocks_inout_mut(blocks);
+    }
+}
+
+/// Decrypts every incoming byte with AES-128-CFB8.
+pub struct StreamDecryptor(cfb8::Decryptor<Aes128>);
+
+impl StreamDecryptor {
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        let (blocks, _) = InOutBuf::from(data).into_chunks();
+        self.0.decrypt_blocks_inout_mut(blocks);
+    }
+}
+
+/// The shared secret is used as both the key and the IV, in each direction.
+pub fn stream_ciphers(shared_secret: &[u8]) -> Result<(StreamEncryptor, StreamDecryptor), InvalidLength> {
+    Ok((
+        StreamEncryptor(cfb8::Encryptor::new_from_slices(shared_secret, shared_secret)?),
+        StreamDecryptor(cfb8::Decryptor::new_from_slices(shared_secret, shared_secret)?),
+    ))
+}
+
+/// Server hash sent to the session server, a SHA-1 digest printed as a signed
+/// (two's complement) hexadecimal number like Java's `BigInteger.toString(16)`.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    let mut digest: [u8; 20] = hasher.finalize().into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // Two's complement negation, big endian
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                let (val, overflow) = byte.overflowing_add(1);
+                *byte = val;
+                carry = overflow;
+            }
+        }
+    }
+
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{}", hex)
+    } else {
+        hex.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::{ServerKeyPair, server_hash, stream_ciphers};
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
+
+    #[test]
+    pub fn test_crypto_rsa_round_trip() {
+        let key_pair = ServerKeyPair::generate();
+        let public_key = RsaPublicKey::from_public_key_der(&key_pair.public_key_der).unwrap();
+
+        let secret = [7u8; 16];
+        let encrypted = public_key
+            .encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, &secret)
+            .unwrap();
+        assert_eq!(key_pair.decrypt(&encrypted).unwrap(), secret);
+    }
+
+    #[test]
+    pub fn test_crypto_stream_round_trip() {
+        let secret = [0x42u8; 16];
+        let (mut encryptor, _) = stream_ciphers(&secret).unwrap();
+        let (_, mut decryptor) = stream_ciphers(&secret).unwrap();
+
+        // Encrypt in several pieces, the cipher state carries over between calls
+        let plain: Vec<u8> = (0..100).collect();
+        let mut data = plain.clone();
+        encryptor.encrypt(&mut data[..10]);
+        encryptor.encrypt(&mut data[10..]);
+        assert_ne!(data, plain);
+
+        decryptor.decrypt(&mut data[..55]);
+        decryptor.decrypt(&mut data[55..]);
+        assert_eq!(data, plain);
+    }
+
+    #[test]
+    pub fn test_crypto_invalid_secret() {
+        assert!(stream_ciphers(&[0u8; 5]).is_err());
+    }
+
+    #[test]
+    pub fn test_crypto_server_hash() {
+        // Reference values from the protocol documentation
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+}
@@ -0,0 +1,166 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+// FAVICON //
+
+// How often the icon file is checked for changes
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+// Only size the client displays
+pub const FAVICON_SIZE: u32 = 64;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[derive(Debug)]
+pub enum FaviconError {
+    Io(io::Error),
+    /// Not a PNG file, or a truncated one
+    NotPng,
+    /// Width and height of an image that is not 64x64
+    InvalidSize(u32, u32),
+}
+
+impl fmt::Display for FaviconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaviconError::Io(err) => write!(f, "{}", err),
+            FaviconError::NotPng => write!(f, "not a PNG image"),
+            FaviconError::InvalidSize(width, height) => {
+                write!(f, "image is {}x{}, it must be {}x{}", width, height, FAVICON_SIZE, FAVICON_SIZE)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FaviconError {}
+
+impl From<io::Error> for FaviconError {
+    fn from(err: io::Error) -> Self {
+        FaviconError::Io(err)
+    }
+}
+
+/// Checks a server icon, returning the data URI sent in the status response.
+pub fn encode(png: &[u8]) -> Result<String, FaviconError> {
+    // Signature, then the IHDR chunk: length, type, width and height
+    if png.len() < 24 || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+        return Err(FaviconError::NotPng);
+    }
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+    if width != FAVICON_SIZE || height != FAVICON_SIZE {
+        return Err(FaviconError::InvalidSize(width, height));
+    }
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+/// Server icon shown in the server list, read from a PNG file.
+#[derive(Clone)]
+pub struct Favicon {
+    path: PathBuf,
+    data_uri: Arc<RwLock<Option<String>>>,
+}
+
+impl Favicon {
+    /// Empty until `load` is called.
+    pub fn new(path: impl Into<PathBuf>) -> Favicon {
+        Favicon {
+            path: path.into(),
+            data_uri: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn data_uri(&self) -> Option<String> {
+        self.data_uri.read().unwrap().clone()
+    }
+
+    /// Reads the file again, returning whether there is an icon. A missing
+    /// file removes the icon, an invalid one keeps the previous icon.
+    pub async fn load(&self) -> Result<bool, FaviconError> {
+        let data_uri = match tokio::fs::read(&self.path).await {
+            Ok(png) => Some(encode(&png)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        let loaded = data_uri.is_some();
+        *self.data_uri.write().unwrap() = data_uri;
+        Ok(loaded)
+    }
+
+    /// Reloads the icon whenever the file is modified, created or removed.
+    pub async fn reload_on_change(self) {
+        let mut modified = self.modified().await;
+        let mut checks = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            checks.tick().await;
+            let current = self.modified().await;
+            if current == modified {
+                continue;
+            }
+            modified = current;
+            match self.load().await {
+                Ok(true) => println!("Reloaded server icon {}", self.path.display()),
+                Ok(false) => println!("Removed server icon {}", self.path.display()),
+                Err(err) => eprintln!("Invalid server icon {}, keeping the previous one: {}", self.path.display(), err),
+            }
+        }
+    }
+
+    async fn modified(&self) -> Option<SystemTime> {
+        tokio::fs::metadata(&self.path).await.and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::favicon::{self, Favicon, FaviconError, PNG_SIGNATURE};
+
+    // Enough of a PNG for the checks, the signature and the IHDR chunk
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&width.to_be_bytes());
+        png.extend_from_slice(&height.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0]);
+        png
+    }
+
+    #[test]
+    pub fn test_favicon_encode() {
+        let data_uri = favicon::encode(&png(64, 64)).unwrap();
+        assert!(data_uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+        assert!(matches!(favicon::encode(&png(128, 128)), Err(FaviconError::InvalidSize(128, 128))));
+        assert!(matches!(favicon::encode(b"GIF89a"), Err(FaviconError::NotPng)));
+        assert!(matches!(favicon::encode(&png(64, 64)[..20]), Err(FaviconError::NotPng)));
+    }
+
+    #[tokio::test]
+    pub async fn test_favicon_load() {
+        let path = std::env::temp_dir().join(format!("voidpoc-icon-{}.png", std::process::id()));
+        let favicon = Favicon::new(&path);
+        assert!(!favicon.load().await.unwrap());
+
+        std::fs::write(&path, png(64, 64)).unwrap();
+        assert!(favicon.load().await.unwrap());
+        let data_uri = favicon.data_uri().unwrap();
+
+        // An invalid icon keeps the previous one
+        std::fs::write(&path, png(32, 32)).unwrap();
+        assert!(favicon.load().await.is_err());
+        assert_eq!(favicon.data_uri(), Some(data_uri));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!favicon.load().await.unwrap());
+        assert_eq!(favicon.data_uri(), None);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::text::TextComponent;
+use crate::types::{Angle, Identifier, Position, Quaternion, Slot, Vec3};
+use crate::{ByteBuf, DecodeError, Property};
+use bit_set::BitSet;
+use simdnbt::owned::{Nbt, NbtTag};
+use uuid::Uuid;
+
+pub use voidpoc_derive::PacketField;
+
+// FIELDS //
+
+/// Type with a single wire encoding, usable as a `#[derive(Packet)]` field.
+///
+/// Types with several encodings, such as `i32` as an Int or a VarInt, use their
+/// fixed size encoding here and are switched with a field attribute.
+pub trait PacketField: Sized {
+    fn write_to(&self, buf: &mut ByteBuf);
+    fn read_from(buf: &mut ByteBuf) -> Result<Self, DecodeError>;
+}
+
+macro_rules! packet_field {
+    ($ty:ty, $write:ident, $read:ident) => {
+        impl PacketField for $ty {
+            fn write_to(&self, buf: &mut ByteBuf) {
+                buf.$write(*self);
+            }
+
+            fn read_from(buf: &mut ByteBuf) -> Result<Self, DecodeError> {
+                buf.$read()
+            }
+        }
+    };
+    (ref $ty:ty, $write:ident, $read:ident) => {
+        impl PacketField for $ty {
+            fn write_to(&self, buf: &mut ByteBuf) {
+                buf.$write(self);
+            }
+
+            fn read_from(buf: &mut ByteBuf) -> Result<Self, DecodeError> {
+                buf.$read()
+            }
+        }
+    };
+}
+
+packet_field!(u8, write_u8, read_u8);
+packet_field!(u16, write_u16, read_u16);
+packet_field!(u32, write_u32, read_u32);
+packet_field!(u64, write_u64, read_u64);
+packet_field!(i8, write_i8, read_i8);
+packet_field!(i16, write_i16, read_i16);
+packet_field!(i32, write_i32, read_i32);
+packet_field!(i64, write_i64, read_i64);
+packet_field!(f32, write_f32, read_f32);
+packet_field!(f64, write_f64, read_f64);
+packet_field!(bool, write_bool, read_bool);
+packet_field!(Angle, write_angle, read_angle);
+packet_field!(ref String, write_string, read_string);
+packet_field!(ref Uuid, write_uuid, read_uuid);
+packet_field!(ref Property, write_property, read_property);
+packet_field!(ref Position, write_position, read_position);
+packet_field!(ref Identifier, write_identifier, read_identifier);
+packet_field!(ref Vec3, write_vec3, read_vec3);
+packet_field!(ref Quaternion, write_quaternion, read_quaternion);
+packet_field!(ref Slot, write_slot, read_slot);
+packet_field!(ref BitSet, write_bitset, read_bitset);
+packet_field!(ref NbtTag, write_nbt_tag, read_nbt_tag);
+packet_field!(ref TextComponent, write_component, read_component);
+
+/// Network NBT, an unnamed root compound.
+impl PacketField for Nbt {
+    fn write_to(&self, buf: &mut ByteBuf) {
+        let mut data = Vec::new();
+        self.write_unnamed(&mut data);
+        buf.write_buf(&data);
+    }
+
+    fn read_from(buf: &mut ByteBuf) -> Result<Self, DecodeError> {
+        let mut cursor = std::io::Cursor::new(buf.as_slice());
+        let nbt = simdnbt::owned::read_unnamed(&mut cursor).map_err(|e| DecodeError::InvalidData(e.to_string()))?;
+        buf.skip(cursor.position() as usize)?;
+        Ok(nbt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ByteBuf;
+    use crate::field::PacketField;
+    use crate::packet::{Packet, PacketBound, PacketStatus};
+    use crate::types::Position;
+
+    #[derive(PacketField, Debug, PartialEq)]
+    struct Entry {
+        name: String,
+        #[packet(varint)]
+        count: i32,
+    }
+
+    #[derive(Packet, Debug, Default, PartialEq)]
+    #[packet(name = "minecraft:update_tags", state = Play, bound = Client)]
+    struct TestPacket {
+        id: i32,
+        #[packet(varint)]
+        varint: i32,
+        #[packet(varlong)]
+        varlong: i64,
+        #[packet(max_len = 16)]
+        name: String,
+        #[packet(length_prefixed)]
+        names: Vec<String>,
+        #[packet(length_prefixed, varint)]
+        ids: Vec<i32>,
+        #[packet(length_prefixed)]
+        bytes: Vec<u8>,
+        #[packet(varint)]
+        maybe_id: Option<i32>,
+        position: Option<Position>,
+        #[packet(length_prefixed)]
+        entries: Vec<Entry>,
+        #[packet(rest)]
+        rest: Vec<u8>,
+    }
+
+    #[test]
+    pub fn test_field_derive_constants() {
+        assert_eq!(TestPacket::ID, 0x7F);
+        assert_eq!(TestPacket::NAME, "minecraft:update_tags");
+        assert_eq!(TestPacket::STATE, PacketStatus::Play);
+        assert_eq!(TestPacket::BOUND, PacketBound::Client);
+        assert_eq!(TestPacket::default().id(), 0x7F);
+    }
+
+    #[test]
+    pub fn test_field_derive_encoding() {
+        let packet = TestPacket {
+            id: 1,
+            varint: 300,
+            varlong: -1,
+            name: "a".to_string(),
+            names: vec!["b".to_string()],
+            ids: vec![128],
+            bytes: vec![0xAB],
+            maybe_id: Some(2),
+            position: None,
+            entries: vec![Entry {
+                name: "c".to_string(),
+                count: 3,
+            }],
+            rest: vec![0x01, 0x02],
+        };
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+
+        let mut expected = ByteBuf::new();
+        expected.write_i32(1);
+        expected.write_varint(300);
+        expected.write_varlong(-1);
+        expected.write_string("a");
+        expected.write_varint(1);
+        expected.write_string("b");
+        expected.write_varint(1);
+        expected.write_varint(128);
+        expected.write_byte_array(&[0xAB]);
+        expected.write_bool(true);
+        expected.write_varint(2);
+        expected.write_bool(false);
+        expected.write_varint(1);
+        expected.write_string("c");
+        expected.write_varint(3);
+        expected.write_buf(&[0x01, 0x02]);
+        assert_eq!(buf.as_slice(), expected.as_slice());
+
+        let mut decoded = TestPacket::default();
+        decoded.decode(&mut buf).unwrap();
+        assert_eq!(decoded, packet);
+        assert_eq!(buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_field_derive_max_len() {
+        let packet = TestPacket {
+            name: "a name longer than 16 bytes".to_string(),
+            ..TestPacket::default()
+        };
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+
+        let mut decoded = TestPacket::default();
+        assert!(decoded.decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::ByteBuf;
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::fmt;
+use std::io::{Read, Write};
+
+// FRAMING //
+
+// Vanilla never sends frames whose length does not fit in a 3 byte varint
+pub const MAX_FRAME_LEN: i32 = 2097151;
+// Upper bound of the uncompressed size of a compressed packet, same as vanilla
+pub const MAX_DATA_LEN: i32 = 8388608;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum FrameError {
+    LengthTooLong,
+    InvalidLength(i32),
+    InvalidDataLength(i32),
+    Compression(String),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::LengthTooLong => write!(f, "frame length varint is too long"),
+            FrameError::InvalidLength(len) => write!(f, "invalid frame length {}", len),
+            FrameError::InvalidDataLength(len) => write!(f, "invalid uncompressed data length {}", len),
+            FrameError::Compression(err) => write!(f, "failed to inflate frame: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Splits the raw socket stream into length-prefixed frames.
+///
+/// Bytes are buffered until a whole frame is available, so frames split across
+/// several reads and several frames coalesced in a single read are both handled.
+/// Once compression is enabled, frames are inflated before being handed out.
+pub struct FrameDecoder {
+    buf: ByteBuf,
+    compression_threshold: Option<i32>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> FrameDecoder {
+        FrameDecoder {
+            buf: ByteBuf::new(),
+            compression_threshold: None,
+        }
+    }
+
+    pub fn set_compression(&mut self, threshold: Option<i32>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Appends freshly read bytes. The space used by the frames already handed
+    /// out is reclaimed by the buffer instead of shifting the pending bytes.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buf.write_buf(data);
+    }
+
+    /// Returns the next complete frame (packet id + packet data), or `None` if
+    /// more bytes are needed. Uncompressed frames share the decoder buffer.
+    pub fn next_frame(&mut self) -> Result<Option<ByteBuf>, FrameError> {
+        let (len, header_len) = match Self::peek_varint(self.buf.as_slice())? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        if !(0..=MAX_FRAME_LEN).contains(&len) {
+            return Err(FrameError::InvalidLength(len));
+        }
+
+        let len = len as usize;
+        if self.buf.remaining_len() < header_len + len {
+            return Ok(None);
+        }
+
+        self.buf.skip(header_len).expect("frame length checked above");
+        let body = self.buf.split_to(len).expect("frame length checked above");
+        let frame = match self.compression_threshold {
+            Some(threshold) => Self::inflate(body, threshold)?,
+            None => body,
+        };
+        Ok(Some(frame))
+    }
+
+    fn inflate(mut body: ByteBuf, threshold: i32) -> Result<ByteBuf, FrameError> {
+        let (data_len, header_len) = match Self::peek_varint(body.as_slice())? {
+            Some(v) => v,
+            None => return Err(FrameError::InvalidLength(body.len() as i32)),
+        };
+        body.skip(header_len).expect("varint length checked above");
+
+        if data_len == 0 {
+            // Below the threshold, sent uncompressed
+            return Ok(body);
+        }
+        if data_len < threshold || data_len > MAX_DATA_LEN {
+            return Err(FrameError::InvalidDataLength(data_len));
+        }
+
+        let mut data = BytesMut::with_capacity(data_len as usize).writer();
+        std::io::copy(&mut ZlibDecoder::new(body.as_slice()).take(data_len as u64), &mut data)
+            .map_err(|e| FrameError::Compression(e.to_string()))?;
+        let data = data.into_inner();
+        if data.len() != data_len as usize {
+            return Err(FrameError::InvalidDataLength(data_len));
+        }
+        Ok(ByteBuf::from_bytes(data))
+    }
+
+    fn peek_varint(data: &[u8]) -> Result<Option<(i32, usize)>, FrameError> {
+        let mut value = 0;
+        for (i, byte) in data.iter().enumerate() {
+            if i >= 5 {
+                return Err(FrameError::LengthTooLong);
+            }
+            value |= (*byte as i32 & 0x7F) << (7 * i);
+            if (*byte & 0x80) == 0 {
+                return Ok(Some((value, i + 1)));
+            }
+        }
+        if data.len() >= 5 {
+            return Err(FrameError::LengthTooLong);
+        }
+        Ok(None)
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        FrameDecoder::new()
+    }
+}
+
+/// Wraps an encoded packet (packet id + packet data) into a frame ready to be written.
+///
+/// Packets are written into a buffer from `begin`, which keeps free room in
+/// front of the packet. Once the packet is written, `finish` fills the headers
+/// in that room, so uncompressed frames are never copied.
+pub struct FrameEncoder {
+    compression_threshold: Option<i32>,
+}
+
+impl FrameEncoder {
+    // Frame length, then the data length of an uncompressed packet in compressed mode
+    const HEADER_ROOM: usize = 5 + 1;
+    // Frame length, then the data length of a compressed packet
+    const COMPRESSED_HEADER_ROOM: usize = 5 + 5;
+
+    pub fn new() -> FrameEncoder {
+        FrameEncoder {
+            compression_threshold: None,
+        }
+    }
+
+    pub fn set_compression(&mut self, threshold: Option<i32>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Returns an empty buffer to write a packet into.
+    pub fn begin(&self) -> ByteBuf {
+        self.begin_with_capacity(0)
+    }
+
+    pub fn begin_with_capacity(&self, capacity: usize) -> ByteBuf {
+        let mut buf = ByteBuf::with_capacity(Self::HEADER_ROOM + capacity);
+        buf.write_buf(&[0; Self::HEADER_ROOM]);
+        buf
+    }
+
+    /// Packet written so far in a buffer from `begin`, without the header room.
+    pub fn payload<'b>(&self, buf: &'b ByteBuf) -> &'b [u8] {
+        &buf.as_slice()[Self::HEADER_ROOM..]
+    }
+
+    /// Turns a buffer from `begin` into a complete frame.
+    pub fn finish(&self, buf: ByteBuf) -> BytesMut {
+        let mut data = buf.into_inner();
+        let payload_len = data.len() - Self::HEADER_ROOM;
+
+        match self.compression_threshold {
+            Some(threshold) if payload_len >= threshold as usize => {
+                let mut out = BytesMut::with_capacity(Self::COMPRESSED_HEADER_ROOM + payload_len / 2);
+                out.put_bytes(0, Self::COMPRESSED_HEADER_ROOM);
+                let mut encoder = ZlibEncoder::new(out.writer(), Compression::default());
+                encoder.write_all(&data[Self::HEADER_ROOM..]).expect("Failed to deflate packet");
+                let mut out = encoder.finish().expect("Failed to deflate packet").into_inner();
+
+                let start = put_varint_before(&mut out, Self::COMPRESSED_HEADER_ROOM, payload_len as i32);
+                let frame_len = out.len() - start;
+                let start = put_varint_before(&mut out, start, frame_len as i32);
+                out.advance(start);
+                out
+            }
+            Some(_) => {
+                // A zero data length marks the packet as uncompressed
+                let end = Self::HEADER_ROOM - 1;
+                data[end] = 0;
+                let start = put_varint_before(&mut data, end, (payload_len + 1) as i32);
+                data.advance(start);
+                data
+            }
+            None => {
+                let start = put_varint_before(&mut data, Self::HEADER_ROOM, payload_len as i32);
+                data.advance(start);
+                data
+            }
+        }
+    }
+
+    pub fn encode(&self, payload: &[u8]) -> BytesMut {
+        let mut buf = self.begin_with_capacity(payload.len());
+        buf.write_buf(payload);
+        self.finish(buf)
+    }
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        FrameEncoder::new()
+    }
+}
+
+/// Writes `val` as a varint ending right before `end`, returning where it starts.
+fn put_varint_before(data: &mut [u8], end: usize, val: i32) -> usize {
+    let mut varint = ByteBuf::with_capacity(5);
+    varint.write_varint(val);
+    let start = end - varint.len();
+    data[start..end].copy_from_slice(varint.as_slice());
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ByteBuf;
+    use crate::frame::{FrameDecoder, FrameEncoder, FrameError};
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut buf = ByteBuf::new();
+        buf.write_varint(payload.len() as i32);
+        buf.write_buf(payload);
+        buf.read_all().to_vec()
+    }
+
+    #[test]
+    pub fn test_frame_single() {
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&frame(&[0x00, 0x01, 0x02]));
+
+        let mut f = decoder.next_frame().unwrap().unwrap();
+        assert_eq!(f.read_all(), vec![0x00, 0x01, 0x02]);
+        assert!(decoder.next_frame().unwrap().is_none());
+        assert_eq!(decoder.buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_frame_split_across_reads() {
+        let mut decoder = FrameDecoder::new();
+        let payload: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let data = frame(&payload);
+
+        // Split inside the two byte length prefix, then inside the body
+        decoder.feed(&data[..1]);
+        assert!(decoder.next_frame().unwrap().is_none());
+        decoder.feed(&data[1..50]);
+        assert!(decoder.next_frame().unwrap().is_none());
+        decoder.feed(&data[50..]);
+
+        let mut f = decoder.next_frame().unwrap().unwrap();
+        assert_eq!(f.read_all(), payload);
+        assert!(decoder.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_frame_coalesced() {
+        let mut decoder = FrameDecoder::new();
+        let mut data = frame(&[0x00, 0x0A]);
+        data.extend(frame(&[0x03]));
+        data.extend(frame(&[0x01, 0x02, 0x03, 0x04]));
+        // Start of a fourth frame which is not complete yet
+        data.extend(&frame(&[0x05, 0x06])[..2]);
+        decoder.feed(&data);
+
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), vec![0x00, 0x0A]);
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), vec![0x03]);
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), vec![0x01, 0x02, 0x03, 0x04]);
+        assert!(decoder.next_frame().unwrap().is_none());
+        assert_eq!(decoder.buf.remaining_len(), 2);
+
+        decoder.feed(&[0x06]);
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), vec![0x05, 0x06]);
+        assert_eq!(decoder.buf.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_frame_invalid_length() {
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        assert!(matches!(decoder.next_frame(), Err(FrameError::LengthTooLong)));
+
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&[0xFF, 0xFF, 0xFF, 0x7F]);
+        assert!(matches!(decoder.next_frame(), Err(FrameError::InvalidLength(_))));
+    }
+
+    #[test]
+    pub fn test_frame_compressed_round_trip() {
+        let mut encoder = FrameEncoder::new();
+        encoder.set_compression(Some(64));
+        let mut decoder = FrameDecoder::new();
+        decoder.set_compression(Some(64));
+
+        let small = vec![0x27, 0x01, 0x02];
+        let big: Vec<u8> = (0..4096).map(|i| (i % 7) as u8).collect();
+        let mut data = encoder.encode(&small);
+        let big_frame = encoder.encode(&big);
+        // Repetitive data has to come out smaller than it went in
+        assert!(big_frame.len() < big.len());
+        data.extend(big_frame);
+        decoder.feed(&data);
+
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), small);
+        assert_eq!(decoder.next_frame().unwrap().unwrap().read_all(), big);
+        assert!(decoder.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_frame_compressed_below_threshold() {
+        let mut encoder = FrameEncoder::new();
+        encoder.set_compression(Some(256));
+
+        // Packet length, then a zero data length, then the raw packet
+        assert_eq!(encoder.encode(&[0x01, 0x02]), vec![0x03, 0x00, 0x01, 0x02]);
+    }
+
+    #[test]
+    pub fn test_frame_compressed_invalid_data_length() {
+        let mut decoder = FrameDecoder::new();
+        decoder.set_compression(Some(256));
+
+        // Claims to be compressed while being smaller than the threshold
+        decoder.feed(&frame(&[0x10, 0x78, 0x9C]));
+        assert!(matches!(decoder.next_frame(), Err(FrameError::InvalidDataLength(16))));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::PacketType;
+use crate::packet::{PacketBound, PacketStatus};
+use crate::protocol::ProtocolVersion;
+
+// PACKET IDS //
+
+// One module per version, state and direction, with a constant per packet such
+// as `ids::v1_21_1::play::clientbound::KEEP_ALIVE`, generated by build.rs. The
+// latest version is re-exported here, as `ids::play::clientbound::KEEP_ALIVE`.
+include!(concat!(env!("OUT_DIR"), "/packet_ids.rs"));
+
+/// Id of a packet from its resource name, such as `minecraft:keep_alive`.
+pub fn id(version: ProtocolVersion, state: PacketStatus, bound: PacketBound, name: &str) -> Option<PacketType> {
+    names(version, state, bound).iter().position(|n| *n == name).map(|id| id as PacketType)
+}
+
+/// Resource name of a packet from its id.
+pub fn name(version: ProtocolVersion, state: PacketStatus, bound: PacketBound, id: PacketType) -> Option<&'static str> {
+    usize::try_from(id).ok().and_then(|id| names(version, state, bound).get(id).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ids;
+    use crate::packet::{PacketBound, PacketStatus};
+    use crate::protocol::ProtocolVersion;
+
+    #[test]
+    pub fn test_ids_lookup() {
+        let version = ProtocolVersion::V1_21_4;
+        assert_eq!(ids::id(version, PacketStatus::Play, PacketBound::Client, "minecraft:keep_alive"), Some(0x27));
+        assert_eq!(ids::id(version, PacketStatus::Play, PacketBound::Server, "minecraft:keep_alive"), Some(0x1A));
+        assert_eq!(ids::id(version, PacketStatus::Handshake, PacketBound::Server, "minecraft:intention"), Some(0x00));
+        assert_eq!(ids::id(version, PacketStatus::Handshake, PacketBound::Client, "minecraft:intention"), None);
+        assert_eq!(ids::id(version, PacketStatus::Play, PacketBound::Client, "keep_alive"), None);
+
+        assert_eq!(ids::name(version, PacketStatus::Play, PacketBound::Client, 0x2C), Some("minecraft:login"));
+        assert_eq!(ids::name(version, PacketStatus::Login, PacketBound::Server, 0x7F), None);
+        assert_eq!(ids::name(version, PacketStatus::Login, PacketBound::Server, -1), None);
+    }
+
+    #[test]
+    pub fn test_ids_constants() {
+        assert_eq!(ids::play::clientbound::LEVEL_CHUNK_WITH_LIGHT, 0x28);
+        assert_eq!(ids::play::serverbound::CLIENT_TICK_END, 0x0B);
+        assert_eq!(ids::configuration::clientbound::REGISTRY_DATA, 0x07);
+        assert_eq!(ids::login::clientbound::LOGIN_FINISHED, 0x02);
+        assert_eq!(ids::v1_21_1::play::clientbound::KEEP_ALIVE, 0x26);
+        assert_eq!(ids::v1_21_1::login::clientbound::GAME_PROFILE, 0x02);
+        assert_eq!(ids::v1_21_3::play::serverbound::PICK_ITEM, 0x22);
+    }
+}
@@ -0,0 +1,1920 @@
+use crate::Property;
+use crate::protocol::ProtocolVersion;
+use crate::text::TextComponent;
+use crate::types::{Identifier, Position, Slot};
+use crate::{ByteBuf, DecodeError};
+use simdnbt::owned::NbtTag;
+use uuid::Uuid;
+
+// ITEM COMPONENTS //
+
+// Slots nested in components (containers, bundles...) deeper than this are rejected
+pub const MAX_SLOT_DEPTH: usize = 16;
+
+// Hidden effects nested in a potion effect deeper than this are rejected
+const MAX_EFFECT_DEPTH: usize = 16;
+
+// Registry order of the item components, their id on the wire
+const COMPONENTS_V1_21_1: [&str; 57] = [
+    "custom_data", "max_stack_size", "max_damage", "damage", "unbreakable", "custom_name", "item_name", "lore",
+    "rarity", "enchantments", "can_place_on", "can_break", "attribute_modifiers", "custom_model_data",
+    "hide_additional_tooltip", "hide_tooltip", "repair_cost", "creative_slot_lock", "enchantment_glint_override",
+    "intangible_projectile", "food", "fire_resistant", "tool", "stored_enchantments", "dyed_color", "map_color",
+    "map_id", "map_decorations", "map_post_processing", "charged_projectiles", "bundle_contents", "potion_contents",
+    "suspicious_stew_effects", "writable_book_content", "written_book_content", "trim", "debug_stick_state",
+    "entity_data", "bucket_entity_data", "block_entity_data", "instrument", "ominous_bottle_amplifier",
+    "jukebox_playable", "recipes", "lodestone_tracker", "firework_explosion", "fireworks", "profile",
+    "note_block_sound", "banner_patterns", "base_color", "pot_decorations", "container", "block_state", "bees",
+    "lock", "container_loot",
+];
+
+// Same since 1.21.2, only some layouts changed in 1.21.4
+const COMPONENTS_V1_21_3: [&str; 67] = [
+    "custom_data", "max_stack_size", "max_damage", "damage", "unbreakable", "custom_name", "item_name", "item_model",
+    "lore", "rarity", "enchantments", "can_place_on", "can_break", "attribute_modifiers", "custom_model_data",
+    "hide_additional_tooltip", "hide_tooltip", "repair_cost", "creative_slot_lock", "enchantment_glint_override",
+    "intangible_projectile", "food", "consumable", "use_remainder", "use_cooldown", "damage_resistant", "tool",
+    "enchantable", "equippable", "repairable", "glider", "tooltip_style", "death_protection", "stored_enchantments",
+    "dyed_color", "map_color", "map_id", "map_decorations", "map_post_processing", "charged_projectiles",
+    "bundle_contents", "potion_contents", "suspicious_stew_effects", "writable_book_content", "written_book_content",
+    "trim", "debug_stick_state", "entity_data", "bucket_entity_data", "block_entity_data", "instrument",
+    "ominous_bottle_amplifier", "jukebox_playable", "recipes", "lodestone_tracker", "firework_explosion", "fireworks",
+    "profile", "note_block_sound", "banner_patterns", "base_color", "pot_decorations", "container", "block_state",
+    "bees", "lock", "container_loot",
+];
+
+fn component_names(version: ProtocolVersion) -> &'static [&'static str] {
+    match version {
+        ProtocolVersion::V1_21_1 => &COMPONENTS_V1_21_1,
+        ProtocolVersion::V1_21_3 | ProtocolVersion::V1_21_4 => &COMPONENTS_V1_21_3,
+    }
+}
+
+/// Registry entry referenced by id, or sent inline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Holder<T> {
+    Reference(i32),
+    Direct(Box<T>),
+}
+
+/// Set of registry entries, by tag or by ids.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdSet {
+    Tag(Identifier),
+    Ids(Vec<i32>),
+}
+
+/// Text of a book, with its version for players who filter text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filterable<T> {
+    pub raw: T,
+    pub filtered: Option<T>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEvent {
+    pub name: Identifier,
+    pub fixed_range: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectDetails {
+    pub amplifier: i32,
+    /// In ticks, -1 for infinite
+    pub duration: i32,
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
+    /// Weaker effect of the same type applied once this one ends
+    pub hidden_effect: Option<Box<EffectDetails>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PotionEffect {
+    pub effect: i32,
+    pub details: EffectDetails,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enchantments {
+    /// Enchantment ids and their level
+    pub levels: Vec<(i32, i32)>,
+    pub show_in_tooltip: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Exact(String),
+    Range { min: Option<String>, max: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyMatcher {
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockPredicate {
+    pub blocks: Option<IdSet>,
+    pub properties: Option<Vec<PropertyMatcher>>,
+    pub nbt: Option<NbtTag>,
+}
+
+/// Blocks an item can be placed on or can break in adventure mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdventurePredicate {
+    pub predicates: Vec<BlockPredicate>,
+    pub show_in_tooltip: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    pub attribute: i32,
+    pub id: Identifier,
+    pub amount: f64,
+    /// 0 add value, 1 add multiplied base, 2 add multiplied total
+    pub operation: i32,
+    /// Equipment slot group the modifier applies in
+    pub slot: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifiers {
+    pub modifiers: Vec<AttributeModifier>,
+    pub show_in_tooltip: bool,
+}
+
+/// Values for item model definitions. Until 1.21.3 this is a single integer,
+/// sent as the first float.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomModelData {
+    pub floats: Vec<f32>,
+    pub flags: Vec<bool>,
+    pub strings: Vec<String>,
+    pub colors: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Food {
+    pub nutrition: i32,
+    pub saturation: f32,
+    pub can_always_eat: bool,
+    /// Until 1.21.1, part of `Consumable` since
+    pub eat_seconds: f32,
+    /// Until 1.21.1, `UseRemainder` since
+    pub using_converts_to: Slot,
+    /// Until 1.21.1, with their probability, part of `Consumable` since
+    pub effects: Vec<(PotionEffect, f32)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsumeEffect {
+    ApplyEffects { effects: Vec<PotionEffect>, probability: f32 },
+    RemoveEffects(IdSet),
+    ClearAllEffects,
+    TeleportRandomly { diameter: f32 },
+    PlaySound(Holder<SoundEvent>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Consumable {
+    pub consume_seconds: f32,
+    /// 0 none, 1 eat, 2 drink, 3 block...
+    pub animation: i32,
+    pub sound: Holder<SoundEvent>,
+    pub has_consume_particles: bool,
+    pub on_consume_effects: Vec<ConsumeEffect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseCooldown {
+    pub seconds: f32,
+    pub cooldown_group: Option<Identifier>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolRule {
+    pub blocks: IdSet,
+    pub speed: Option<f32>,
+    pub correct_for_drops: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tool {
+    pub rules: Vec<ToolRule>,
+    pub default_mining_speed: f32,
+    pub damage_per_block: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equippable {
+    pub slot: i32,
+    pub equip_sound: Holder<SoundEvent>,
+    pub model: Option<Identifier>,
+    pub camera_overlay: Option<Identifier>,
+    pub allowed_entities: Option<IdSet>,
+    pub dispensable: bool,
+    pub swappable: bool,
+    pub damage_on_hurt: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PotionContents {
+    pub potion: Option<i32>,
+    pub custom_color: Option<i32>,
+    pub custom_effects: Vec<PotionEffect>,
+    /// Since 1.21.2
+    pub custom_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrittenBook {
+    pub title: Filterable<String>,
+    pub author: String,
+    /// 0 original, 1 copy, 2 copy of copy, 3 tattered
+    pub generation: i32,
+    pub pages: Vec<Filterable<TextComponent>>,
+    pub resolved: bool,
+}
+
+/// Armor material of a trim override: a registry id until 1.21.1, an
+/// equipment asset since. Overrides of the other kind are not sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArmorAsset {
+    Id(i32),
+    Asset(Identifier),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrimMaterial {
+    pub asset_name: String,
+    pub ingredient: i32,
+    /// Until 1.21.3
+    pub item_model_index: f32,
+    pub overrides: Vec<(ArmorAsset, String)>,
+    pub description: TextComponent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrimPattern {
+    pub asset_id: Identifier,
+    pub template_item: i32,
+    pub description: TextComponent,
+    pub decal: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trim {
+    pub material: Holder<TrimMaterial>,
+    pub pattern: Holder<TrimPattern>,
+    pub show_in_tooltip: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub sound_event: Holder<SoundEvent>,
+    /// Sent in ticks until 1.21.1
+    pub use_seconds: f32,
+    pub range: f32,
+    /// Since 1.21.2
+    pub description: TextComponent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JukeboxSong {
+    pub sound_event: Holder<SoundEvent>,
+    pub description: TextComponent,
+    pub length_in_seconds: f32,
+    pub comparator_output: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JukeboxSongRef {
+    Holder(Holder<JukeboxSong>),
+    /// Song of the registry, by name
+    Key(Identifier),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JukeboxPlayable {
+    pub song: JukeboxSongRef,
+    pub show_in_tooltip: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodestoneTracker {
+    /// Dimension and position of the lodestone
+    pub target: Option<(Identifier, Position)>,
+    pub tracked: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FireworkExplosion {
+    /// 0 small ball, 1 large ball, 2 star, 3 creeper, 4 burst
+    pub shape: i32,
+    pub colors: Vec<i32>,
+    pub fade_colors: Vec<i32>,
+    pub has_trail: bool,
+    pub has_twinkle: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fireworks {
+    pub flight_duration: i32,
+    pub explosions: Vec<FireworkExplosion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: Option<String>,
+    pub uuid: Option<Uuid>,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannerPattern {
+    pub asset_id: Identifier,
+    pub translation_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannerLayer {
+    pub pattern: Holder<BannerPattern>,
+    pub color: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bee {
+    pub entity_data: NbtTag,
+    pub ticks_in_hive: i32,
+    pub min_ticks_in_hive: i32,
+}
+
+/// Component added to an item stack, overriding the default of its item.
+///
+/// Components are not length-prefixed, so each one is decoded with the layout
+/// of its type in the protocol version of the connection. Components only
+/// sent as NBT keep their tag as-is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotComponent {
+    CustomData(NbtTag),
+    MaxStackSize(i32),
+    MaxDamage(i32),
+    Damage(i32),
+    Unbreakable { show_in_tooltip: bool },
+    CustomName(TextComponent),
+    ItemName(TextComponent),
+    /// Since 1.21.2
+    ItemModel(Identifier),
+    Lore(Vec<TextComponent>),
+    Rarity(i32),
+    Enchantments(Enchantments),
+    CanPlaceOn(AdventurePredicate),
+    CanBreak(AdventurePredicate),
+    AttributeModifiers(AttributeModifiers),
+    CustomModelData(CustomModelData),
+    HideAdditionalTooltip,
+    HideTooltip,
+    RepairCost(i32),
+    CreativeSlotLock,
+    EnchantmentGlintOverride(bool),
+    IntangibleProjectile(NbtTag),
+    Food(Food),
+    /// Since 1.21.2
+    Consumable(Consumable),
+    /// Since 1.21.2
+    UseRemainder(Slot),
+    /// Since 1.21.2
+    UseCooldown(UseCooldown),
+    /// Damage type tag, since 1.21.2
+    DamageResistant(Identifier),
+    Tool(Tool),
+    /// Since 1.21.2
+    Enchantable(i32),
+    /// Since 1.21.2
+    Equippable(Equippable),
+    /// Since 1.21.2
+    Repairable(IdSet),
+    /// Since 1.21.2
+    Glider,
+    /// Since 1.21.2
+    TooltipStyle(Identifier),
+    /// Since 1.21.2
+    DeathProtection(Vec<ConsumeEffect>),
+    /// Until 1.21.1, `DamageResistant` since
+    FireResistant,
+    StoredEnchantments(Enchantments),
+    DyedColor { color: i32, show_in_tooltip: bool },
+    MapColor(i32),
+    MapId(i32),
+    MapDecorations(NbtTag),
+    MapPostProcessing(i32),
+    ChargedProjectiles(Vec<Slot>),
+    BundleContents(Vec<Slot>),
+    PotionContents(PotionContents),
+    /// Effect ids and their duration
+    SuspiciousStewEffects(Vec<(i32, i32)>),
+    WritableBookContent(Vec<Filterable<String>>),
+    WrittenBookContent(WrittenBook),
+    Trim(Trim),
+    DebugStickState(NbtTag),
+    EntityData(NbtTag),
+    BucketEntityData(NbtTag),
+    BlockEntityData(NbtTag),
+    Instrument(Holder<Instrument>),
+    OminousBottleAmplifier(i32),
+    JukeboxPlayable(JukeboxPlayable),
+    Recipes(NbtTag),
+    LodestoneTracker(LodestoneTracker),
+    FireworkExplosion(FireworkExplosion),
+    Fireworks(Fireworks),
+    Profile(Profile),
+    NoteBlockSound(Identifier),
+    BannerPatterns(Vec<BannerLayer>),
+    BaseColor(i32),
+    PotDecorations(Vec<i32>),
+    Container(Vec<Slot>),
+    /// Block state property names and values
+    BlockState(Vec<(String, String)>),
+    Bees(Vec<Bee>),
+    Lock(NbtTag),
+    ContainerLoot(NbtTag),
+}
+
+impl SlotComponent {
+    /// Name in the data component registry, without its namespace.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlotComponent::CustomData(_) => "custom_data",
+            SlotComponent::MaxStackSize(_) => "max_stack_size",
+            SlotComponent::MaxDamage(_) => "max_damage",
+            SlotComponent::Damage(_) => "damage",
+            SlotComponent::Unbreakable { .. } => "unbreakable",
+            SlotComponent::CustomName(_) => "custom_name",
+            SlotComponent::ItemName(_) => "item_name",
+            SlotComponent::ItemModel(_) => "item_model",
+            SlotComponent::Lore(_) => "lore",
+            SlotComponent::Rarity(_) => "rarity",
+            SlotComponent::Enchantments(_) => "enchantments",
+            SlotComponent::CanPlaceOn(_) => "can_place_on",
+            SlotComponent::CanBreak(_) => "can_break",
+            SlotComponent::AttributeModifiers(_) => "attribute_modifiers",
+            SlotComponent::CustomModelData(_) => "custom_model_data",
+            SlotComponent::HideAdditionalTooltip => "hide_additional_tooltip",
+            SlotComponent::HideTooltip => "hide_tooltip",
+            SlotComponent::RepairCost(_) => "repair_cost",
+            SlotComponent::CreativeSlotLock => "creative_slot_lock",
+            SlotComponent::EnchantmentGlintOverride(_) => "enchantment_glint_override",
+            SlotComponent::IntangibleProjectile(_) => "intangible_projectile",
+            SlotComponent::Food(_) => "food",
+            SlotComponent::Consumable(_) => "consumable",
+            SlotComponent::UseRemainder(_) => "use_remainder",
+            SlotComponent::UseCooldown(_) => "use_cooldown",
+            SlotComponent::DamageResistant(_) => "damage_resistant",
+            SlotComponent::Tool(_) => "tool",
+            SlotComponent::Enchantable(_) => "enchantable",
+            SlotComponent::Equippable(_) => "equippable",
+            SlotComponent::Repairable(_) => "repairable",
+            SlotComponent::Glider => "glider",
+            SlotComponent::TooltipStyle(_) => "tooltip_style",
+            SlotComponent::DeathProtection(_) => "death_protection",
+            SlotComponent::FireResistant => "fire_resistant",
+            SlotComponent::StoredEnchantments(_) => "stored_enchantments",
+            SlotComponent::DyedColor { .. } => "dyed_color",
+            SlotComponent::MapColor(_) => "map_color",
+            SlotComponent::MapId(_) => "map_id",
+            SlotComponent::MapDecorations(_) => "map_decorations",
+            SlotComponent::MapPostProcessing(_) => "map_post_processing",
+            SlotComponent::ChargedProjectiles(_) => "charged_projectiles",
+            SlotComponent::BundleContents(_) => "bundle_contents",
+            SlotComponent::PotionContents(_) => "potion_contents",
+            SlotComponent::SuspiciousStewEffects(_) => "suspicious_stew_effects",
+            SlotComponent::WritableBookContent(_) => "writable_book_content",
+            SlotComponent::WrittenBookContent(_) => "written_book_content",
+            SlotComponent::Trim(_) => "trim",
+            SlotComponent::DebugStickState(_) => "debug_stick_state",
+            SlotComponent::EntityData(_) => "entity_data",
+            SlotComponent::BucketEntityData(_) => "bucket_entity_data",
+            SlotComponent::BlockEntityData(_) => "block_entity_data",
+            SlotComponent::Instrument(_) => "instrument",
+            SlotComponent::OminousBottleAmplifier(_) => "ominous_bottle_amplifier",
+            SlotComponent::JukeboxPlayable(_) => "jukebox_playable",
+            SlotComponent::Recipes(_) => "recipes",
+            SlotComponent::LodestoneTracker(_) => "lodestone_tracker",
+            SlotComponent::FireworkExplosion(_) => "firework_explosion",
+            SlotComponent::Fireworks(_) => "fireworks",
+            SlotComponent::Profile(_) => "profile",
+            SlotComponent::NoteBlockSound(_) => "note_block_sound",
+            SlotComponent::BannerPatterns(_) => "banner_patterns",
+            SlotComponent::BaseColor(_) => "base_color",
+            SlotComponent::PotDecorations(_) => "pot_decorations",
+            SlotComponent::Container(_) => "container",
+            SlotComponent::BlockState(_) => "block_state",
+            SlotComponent::Bees(_) => "bees",
+            SlotComponent::Lock(_) => "lock",
+            SlotComponent::ContainerLoot(_) => "container_loot",
+        }
+    }
+
+    /// Wire id of the component in `version`, `None` when it does not exist there.
+    pub fn id(&self, version: ProtocolVersion) -> Option<i32> {
+        let name = self.name();
+        component_names(version).iter().position(|component| *component == name).map(|id| id as i32)
+    }
+
+    /// Writes the id then the data of the component, which must exist in `version`.
+    pub fn write_to(&self, buf: &mut ByteBuf, version: ProtocolVersion) {
+        let id = self.id(version).expect("Item component does not exist in this version");
+        buf.write_varint(id);
+        match self {
+            SlotComponent::CustomData(tag)
+            | SlotComponent::IntangibleProjectile(tag)
+            | SlotComponent::MapDecorations(tag)
+            | SlotComponent::DebugStickState(tag)
+            | SlotComponent::EntityData(tag)
+            | SlotComponent::BucketEntityData(tag)
+            | SlotComponent::BlockEntityData(tag)
+            | SlotComponent::Recipes(tag)
+            | SlotComponent::Lock(tag)
+            | SlotComponent::ContainerLoot(tag) => {
+                buf.write_nbt_tag(tag);
+            }
+            SlotComponent::MaxStackSize(val)
+            | SlotComponent::MaxDamage(val)
+            | SlotComponent::Damage(val)
+            | SlotComponent::Rarity(val)
+            | SlotComponent::RepairCost(val)
+            | SlotComponent::Enchantable(val)
+            | SlotComponent::MapId(val)
+            | SlotComponent::MapPostProcessing(val)
+            | SlotComponent::OminousBottleAmplifier(val)
+            | SlotComponent::BaseColor(val) => {
+                buf.write_varint(*val);
+            }
+            SlotComponent::HideAdditionalTooltip
+            | SlotComponent::HideTooltip
+            | SlotComponent::CreativeSlotLock
+            | SlotComponent::Glider
+            | SlotComponent::FireResistant => {}
+            SlotComponent::ItemModel(id)
+            | SlotComponent::DamageResistant(id)
+            | SlotComponent::TooltipStyle(id)
+            | SlotComponent::NoteBlockSound(id) => {
+                buf.write_identifier(id);
+            }
+            SlotComponent::Unbreakable { show_in_tooltip } => {
+                buf.write_bool(*show_in_tooltip);
+            }
+            SlotComponent::CustomName(text) | SlotComponent::ItemName(text) => {
+                buf.write_component(text);
+            }
+            SlotComponent::Lore(lines) => {
+                buf.write_prefixed_array(lines, |buf, line| {
+                    buf.write_component(line);
+                });
+            }
+            SlotComponent::Enchantments(enchantments) | SlotComponent::StoredEnchantments(enchantments) => {
+                buf.write_prefixed_array(&enchantments.levels, |buf, (id, level)| {
+                    buf.write_varint(*id);
+                    buf.write_varint(*level);
+                });
+                buf.write_bool(enchantments.show_in_tooltip);
+            }
+            SlotComponent::CanPlaceOn(predicate) | SlotComponent::CanBreak(predicate) => {
+                buf.write_prefixed_array(&predicate.predicates, write_block_predicate);
+                buf.write_bool(predicate.show_in_tooltip);
+            }
+            SlotComponent::AttributeModifiers(attributes) => {
+                buf.write_prefixed_array(&attributes.modifiers, |buf, modifier| {
+                    buf.write_varint(modifier.attribute);
+                    buf.write_identifier(&modifier.id);
+                    buf.write_f64(modifier.amount);
+                    buf.write_varint(modifier.operation);
+                    buf.write_varint(modifier.slot);
+                });
+                buf.write_bool(attributes.show_in_tooltip);
+            }
+            SlotComponent::CustomModelData(data) => {
+                if version >= ProtocolVersion::V1_21_4 {
+                    buf.write_prefixed_array(&data.floats, |buf, val| {
+                        buf.write_f32(*val);
+                    });
+                    buf.write_prefixed_array(&data.flags, |buf, val| {
+                        buf.write_bool(*val);
+                    });
+                    buf.write_prefixed_array(&data.strings, |buf, val| {
+                        buf.write_string(val);
+                    });
+                    buf.write_prefixed_array(&data.colors, |buf, val| {
+                        buf.write_i32(*val);
+                    });
+                } else {
+                    buf.write_varint(data.floats.first().copied().unwrap_or_default() as i32);
+                }
+            }
+            SlotComponent::EnchantmentGlintOverride(val) => {
+                buf.write_bool(*val);
+            }
+            SlotComponent::Food(food) => {
+                buf.write_varint(food.nutrition);
+                buf.write_f32(food.saturation);
+                buf.write_bool(food.can_always_eat);
+                if version == ProtocolVersion::V1_21_1 {
+                    buf.write_f32(food.eat_seconds);
+                    buf.write_slot_for(&food.using_converts_to, version);
+                    buf.write_prefixed_array(&food.effects, |buf, (effect, probability)| {
+                        write_potion_effect(buf, effect);
+                        buf.write_f32(*probability);
+                    });
+                }
+            }
+            SlotComponent::Consumable(consumable) => {
+                buf.write_f32(consumable.consume_seconds);
+                buf.write_varint(consumable.animation);
+                write_holder(buf, &consumable.sound, write_sound_event);
+                buf.write_bool(consumable.has_consume_particles);
+                buf.write_prefixed_array(&consumable.on_consume_effects, write_consume_effect);
+            }
+            SlotComponent::UseRemainder(slot) => {
+                buf.write_slot_for(slot, version);
+            }
+            SlotComponent::UseCooldown(cooldown) => {
+                buf.write_f32(cooldown.seconds);
+                buf.write_prefixed_option(cooldown.cooldown_group.as_ref(), |buf, group| {
+                    buf.write_identifier(group);
+                });
+            }
+            SlotComponent::Tool(tool) => {
+                buf.write_prefixed_array(&tool.rules, |buf, rule| {
+                    write_id_set(buf, &rule.blocks);
+                    buf.write_prefixed_option(rule.speed.as_ref(), |buf, speed| {
+                        buf.write_f32(*speed);
+                    });
+                    buf.write_prefixed_option(rule.correct_for_drops.as_ref(), |buf, correct| {
+                        buf.write_bool(*correct);
+                    });
+                });
+                buf.write_f32(tool.default_mining_speed);
+                buf.write_varint(tool.damage_per_block);
+            }
+            SlotComponent::Equippable(equippable) => {
+                buf.write_varint(equippable.slot);
+                write_holder(buf, &equippable.equip_sound, write_sound_event);
+                buf.write_prefixed_option(equippable.model.as_ref(), |buf, model| {
+                    buf.write_identifier(model);
+                });
+                buf.write_prefixed_option(equippable.camera_overlay.as_ref(), |buf, overlay| {
+                    buf.write_identifier(overlay);
+                });
+                buf.write_prefixed_option(equippable.allowed_entities.as_ref(), write_id_set);
+                buf.write_bool(equippable.dispensable);
+                buf.write_bool(equippable.swappable);
+                buf.write_bool(equippable.damage_on_hurt);
+            }
+            SlotComponent::Repairable(items) => {
+                write_id_set(buf, items);
+            }
+            SlotComponent::DeathProtection(effects) => {
+                buf.write_prefixed_array(effects, write_consume_effect);
+            }
+            SlotComponent::DyedColor { color, show_in_tooltip } => {
+                buf.write_i32(*color);
+                buf.write_bool(*show_in_tooltip);
+            }
+            SlotComponent::MapColor(color) => {
+                buf.write_i32(*color);
+            }
+            SlotComponent::ChargedProjectiles(slots)
+            | SlotComponent::BundleContents(slots)
+            | SlotComponent::Container(slots) => {
+                buf.write_prefixed_array(slots, |buf, slot| {
+                    buf.write_slot_for(slot, version);
+                });
+            }
+            SlotComponent::PotionContents(potion) => {
+                buf.write_prefixed_option(potion.potion.as_ref(), |buf, id| {
+                    buf.write_varint(*id);
+                });
+                buf.write_prefixed_option(potion.custom_color.as_ref(), |buf, color| {
+                    buf.write_i32(*color);
+                });
+                buf.write_prefixed_array(&potion.custom_effects, write_potion_effect);
+                if version >= ProtocolVersion::V1_21_3 {
+                    buf.write_prefixed_option(potion.custom_name.as_ref(), |buf, name| {
+                        buf.write_string(name);
+                    });
+                }
+            }
+            SlotComponent::SuspiciousStewEffects(effects) => {
+                buf.write_prefixed_array(effects, |buf, (effect, duration)| {
+                    buf.write_varint(*effect);
+                    buf.write_varint(*duration);
+                });
+            }
+            SlotComponent::WritableBookContent(pages) => {
+                buf.write_prefixed_array(pages, |buf, page| {
+                    write_filterable(buf, page, |buf, text| {
+                        buf.write_string(text);
+                    });
+                });
+            }
+            SlotComponent::WrittenBookContent(book) => {
+                write_filterable(buf, &book.title, |buf, title| {
+                    buf.write_string(title);
+                });
+                buf.write_string(&book.author);
+                buf.write_varint(book.generation);
+                buf.write_prefixed_array(&book.pages, |buf, page| {
+                    write_filterable(buf, page, |buf, text| {
+                        buf.write_component(text);
+                    });
+                });
+                buf.write_bool(book.resolved);
+            }
+            SlotComponent::Trim(trim) => {
+                write_holder(buf, &trim.material, |buf, material| write_trim_material(buf, material, version));
+                write_holder(buf, &trim.pattern, |buf, pattern| {
+                    buf.write_identifier(&pattern.asset_id);
+                    buf.write_varint(pattern.template_item);
+                    buf.write_component(&pattern.description);
+                    buf.write_bool(pattern.decal);
+                });
+                buf.write_bool(trim.show_in_tooltip);
+            }
+            SlotComponent::Instrument(instrument) => {
+                write_holder(buf, instrument, |buf, instrument| {
+                    write_holder(buf, &instrument.sound_event, write_sound_event);
+                    if version == ProtocolVersion::V1_21_1 {
+                        buf.write_varint((instrument.use_seconds * 20.0).round() as i32);
+                        buf.write_f32(instrument.range);
+                    } else {
+                        buf.write_f32(instrument.use_seconds);
+                        buf.write_f32(instrument.range);
+                        buf.write_component(&instrument.description);
+                    }
+                });
+            }
+            SlotComponent::JukeboxPlayable(playable) => {
+                match &playable.song {
+                    JukeboxSongRef::Holder(song) => {
+                        buf.write_bool(true);
+                        write_holder(buf, song, |buf, song| {
+                            write_holder(buf, &song.sound_event, write_sound_event);
+                            buf.write_component(&song.description);
+                            buf.write_f32(song.length_in_seconds);
+                            buf.write_varint(song.comparator_output);
+                        });
+                    }
+                    JukeboxSongRef::Key(key) => {
+                        buf.write_bool(false);
+                        buf.write_identifier(key);
+                    }
+                }
+                buf.write_bool(playable.show_in_tooltip);
+            }
+            SlotComponent::LodestoneTracker(tracker) => {
+                buf.write_prefixed_option(tracker.target.as_ref(), |buf, (dimension, pos)| {
+                    buf.write_identifier(dimension);
+                    buf.write_position(pos);
+                });
+                buf.write_bool(tracker.tracked);
+            }
+            SlotComponent::FireworkExplosion(explosion) => {
+                write_firework_explosion(buf, explosion);
+            }
+            SlotComponent::Fireworks(fireworks) => {
+                buf.write_varint(fireworks.flight_duration);
+                buf.write_prefixed_array(&fireworks.explosions, write_firework_explosion);
+            }
+            SlotComponent::Profile(profile) => {
+                buf.write_prefixed_option(profile.name.as_ref(), |buf, name| {
+                    buf.write_string(name);
+                });
+                buf.write_prefixed_option(profile.uuid.as_ref(), |buf, uuid| {
+                    buf.write_uuid(uuid);
+                });
+                buf.write_properties(&profile.properties);
+            }
+            SlotComponent::BannerPatterns(layers) => {
+                buf.write_prefixed_array(layers, |buf, layer| {
+                    write_holder(buf, &layer.pattern, |buf, pattern| {
+                        buf.write_identifier(&pattern.asset_id);
+                        buf.write_string(&pattern.translation_key);
+                    });
+                    buf.write_varint(layer.color);
+                });
+            }
+            SlotComponent::PotDecorations(items) => {
+                buf.write_prefixed_array(items, |buf, item| {
+                    buf.write_varint(*item);
+                });
+            }
+            SlotComponent::BlockState(properties) => {
+                buf.write_prefixed_array(properties, |buf, (name, value)| {
+                    buf.write_string(name);
+                    buf.write_string(value);
+                });
+            }
+            SlotComponent::Bees(bees) => {
+                buf.write_prefixed_array(bees, |buf, bee| {
+                    buf.write_nbt_tag(&bee.entity_data);
+                    buf.write_varint(bee.ticks_in_hive);
+                    buf.write_varint(bee.min_ticks_in_hive);
+                });
+            }
+        }
+    }
+
+    /// Reads a component with the ids and layouts of `version`.
+    pub fn read_from(buf: &mut ByteBuf, version: ProtocolVersion) -> Result<SlotComponent, DecodeError> {
+        SlotComponent::read_nested(buf, version, 0)
+    }
+
+    /// Reads a component of a slot nested in `depth` others.
+    pub(crate) fn read_nested(buf: &mut ByteBuf, version: ProtocolVersion, depth: usize) -> Result<SlotComponent, DecodeError> {
+        let id = buf.read_varint()?;
+        let name = usize::try_from(id)
+            .ok()
+            .and_then(|id| component_names(version).get(id))
+            .ok_or_else(|| DecodeError::InvalidData(format!("unknown item component {}", id)))?;
+        let read_slots = |buf: &mut ByteBuf| buf.read_prefixed_array(|buf| buf.read_slot_nested(version, depth + 1));
+
+        let component = match *name {
+            "custom_data" => SlotComponent::CustomData(buf.read_nbt_tag()?),
+            "max_stack_size" => SlotComponent::MaxStackSize(buf.read_varint()?),
+            "max_damage" => SlotComponent::MaxDamage(buf.read_varint()?),
+            "damage" => SlotComponent::Damage(buf.read_varint()?),
+            "unbreakable" => SlotComponent::Unbreakable {
+                show_in_tooltip: buf.read_bool()?,
+            },
+            "custom_name" => SlotComponent::CustomName(buf.read_component()?),
+            "item_name" => SlotComponent::ItemName(buf.read_component()?),
+            "item_model" => SlotComponent::ItemModel(buf.read_identifier()?),
+            "lore" => SlotComponent::Lore(buf.read_prefixed_array(|buf| buf.read_component())?),
+            "rarity" => SlotComponent::Rarity(buf.read_varint()?),
+            "enchantments" => SlotComponent::Enchantments(read_enchantments(buf)?),
+            "can_place_on" => SlotComponent::CanPlaceOn(read_adventure_predicate(buf)?),
+            "can_break" => SlotComponent::CanBreak(read_adventure_predicate(buf)?),
+            "attribute_modifiers" => SlotComponent::AttributeModifiers(AttributeModifiers {
+                modifiers: buf.read_prefixed_array(|buf| {
+                    Ok(AttributeModifier {
+                        attribute: buf.read_varint()?,
+                        id: buf.read_identifier()?,
+                        amount: buf.read_f64()?,
+                        operation: buf.read_varint()?,
+                        slot: buf.read_varint()?,
+                    })
+                })?,
+                show_in_tooltip: buf.read_bool()?,
+            }),
+            "custom_model_data" if version >= ProtocolVersion::V1_21_4 => SlotComponent::CustomModelData(CustomModelData {
+                floats: buf.read_prefixed_array(|buf| buf.read_f32())?,
+                flags: buf.read_prefixed_array(|buf| buf.read_bool())?,
+                strings: buf.read_prefixed_array(|buf| buf.read_string())?,
+                colors: buf.read_prefixed_array(|buf| buf.read_i32())?,
+            }),
+            "custom_model_data" => SlotComponent::CustomModelData(CustomModelData {
+                floats: vec![buf.read_varint()? as f32],
+                ..CustomModelData::default()
+            }),
+            "hide_additional_tooltip" => SlotComponent::HideAdditionalTooltip,
+            "hide_tooltip" => SlotComponent::HideTooltip,
+            "repair_cost" => SlotComponent::RepairCost(buf.read_varint()?),
+            "creative_slot_lock" => SlotComponent::CreativeSlotLock,
+            "enchantment_glint_override" => SlotComponent::EnchantmentGlintOverride(buf.read_bool()?),
+            "intangible_projectile" => SlotComponent::IntangibleProjectile(buf.read_nbt_tag()?),
+            "food" => {
+                let nutrition = buf.read_varint()?;
+                let saturation = buf.read_f32()?;
+                let can_always_eat = buf.read_bool()?;
+                let mut food = Food {
+                    nutrition,
+                    saturation,
+                    can_always_eat,
+                    eat_seconds: 1.6,
+                    using_converts_to: Slot::empty(),
+                    effects: Vec::new(),
+                };
+                if version == ProtocolVersion::V1_21_1 {
+                    food.eat_seconds = buf.read_f32()?;
+                    food.using_converts_to = buf.read_slot_nested(version, depth + 1)?;
+                    food.effects = buf.read_prefixed_array(|buf| Ok((read_potion_effect(buf)?, buf.read_f32()?)))?;
+                }
+                SlotComponent::Food(food)
+            }
+            "consumable" => SlotComponent::Consumable(Consumable {
+                consume_seconds: buf.read_f32()?,
+                animation: buf.read_varint()?,
+                sound: read_holder(buf, read_sound_event)?,
+                has_consume_particles: buf.read_bool()?,
+                on_consume_effects: buf.read_prefixed_array(read_consume_effect)?,
+            }),
+            "use_remainder" => SlotComponent::UseRemainder(buf.read_slot_nested(version, depth + 1)?),
+            "use_cooldown" => SlotComponent::UseCooldown(UseCooldown {
+                seconds: buf.read_f32()?,
+                cooldown_group: buf.read_prefixed_option(|buf| buf.read_identifier())?,
+            }),
+            "damage_resistant" => SlotComponent::DamageResistant(buf.read_identifier()?),
+            "tool" => SlotComponent::Tool(Tool {
+                rules: buf.read_prefixed_array(|buf| {
+                    Ok(ToolRule {
+                        blocks: read_id_set(buf)?,
+                        speed: buf.read_prefixed_option(|buf| buf.read_f32())?,
+                        correct_for_drops: buf.read_prefixed_option(|buf| buf.read_bool())?,
+                    })
+                })?,
+                default_mining_speed: buf.read_f32()?,
+                damage_per_block: buf.read_varint()?,
+            }),
+            "enchantable" => SlotComponent::Enchantable(buf.read_varint()?),
+            "equippable" => SlotComponent::Equippable(Equippable {
+                slot: buf.read_varint()?,
+                equip_sound: read_holder(buf, read_sound_event)?,
+                model: buf.read_prefixed_option(|buf| buf.read_identifier())?,
+                camera_overlay: buf.read_prefixed_option(|buf| buf.read_identifier())?,
+                allowed_entities: buf.read_prefixed_option(read_id_set)?,
+                dispensable: buf.read_bool()?,
+                swappable: buf.read_bool()?,
+                damage_on_hurt: buf.read_bool()?,
+            }),
+            "repairable" => SlotComponent::Repairable(read_id_set(buf)?),
+            "glider" => SlotComponent::Glider,
+            "tooltip_style" => SlotComponent::TooltipStyle(buf.read_identifier()?),
+            "death_protection" => SlotComponent::DeathProtection(buf.read_prefixed_array(read_consume_effect)?),
+            "fire_resistant" => SlotComponent::FireResistant,
+            "stored_enchantments" => SlotComponent::StoredEnchantments(read_enchantments(buf)?),
+            "dyed_color" => SlotComponent::DyedColor {
+                color: buf.read_i32()?,
+                show_in_tooltip: buf.read_bool()?,
+            },
+            "map_color" => SlotComponent::MapColor(buf.read_i32()?),
+            "map_id" => SlotComponent::MapId(buf.read_varint()?),
+            "map_decorations" => SlotComponent::MapDecorations(buf.read_nbt_tag()?),
+            "map_post_processing" => SlotComponent::MapPostProcessing(buf.read_varint()?),
+            "charged_projectiles" => SlotComponent::ChargedProjectiles(read_slots(buf)?),
+            "bundle_contents" => SlotComponent::BundleContents(read_slots(buf)?),
+            "potion_contents" => SlotComponent::PotionContents(PotionContents {
+                potion: buf.read_prefixed_option(|buf| buf.read_varint())?,
+                custom_color: buf.read_prefixed_option(|buf| buf.read_i32())?,
+                custom_effects: buf.read_prefixed_array(read_potion_effect)?,
+                custom_name: match version >= ProtocolVersion::V1_21_3 {
+                    true => buf.read_prefixed_option(|buf| buf.read_string())?,
+                    false => None,
+                },
+            }),
+            "suspicious_stew_effects" => {
+                SlotComponent::SuspiciousStewEffects(buf.read_prefixed_array(|buf| Ok((buf.read_varint()?, buf.read_varint()?)))?)
+            }
+            "writable_book_content" => SlotComponent::WritableBookContent(
+                buf.read_prefixed_array(|buf| read_filterable(buf, |buf| buf.read_string()))?,
+            ),
+            "written_book_content" => SlotComponent::WrittenBookContent(WrittenBook {
+                title: read_filterable(buf, |buf| buf.read_string())?,
+                author: buf.read_string()?,
+                generation: buf.read_varint()?,
+                pages: buf.read_prefixed_array(|buf| read_filterable(buf, |buf| buf.read_component()))?,
+                resolved: buf.read_bool()?,
+            }),
+            "trim" => SlotComponent::Trim(Trim {
+                material: read_holder(buf, |buf| read_trim_material(buf, version))?,
+                pattern: read_holder(buf, |buf| {
+                    Ok(TrimPattern {
+                        asset_id: buf.read_identifier()?,
+                        template_item: buf.read_varint()?,
+                        description: buf.read_component()?,
+                        decal: buf.read_bool()?,
+                    })
+                })?,
+                show_in_tooltip: buf.read_bool()?,
+            }),
+            "debug_stick_state" => SlotComponent::DebugStickState(buf.read_nbt_tag()?),
+            "entity_data" => SlotComponent::EntityData(buf.read_nbt_tag()?),
+            "bucket_entity_data" => SlotComponent::BucketEntityData(buf.read_nbt_tag()?),
+            "block_entity_data" => SlotComponent::BlockEntityData(buf.read_nbt_tag()?),
+            "instrument" => SlotComponent::Instrument(read_holder(buf, |buf| {
+                let sound_event = read_holder(buf, read_sound_event)?;
+                if version == ProtocolVersion::V1_21_1 {
+                    Ok(Instrument {
+                        sound_event,
+                        use_seconds: buf.read_varint()? as f32 / 20.0,
+                        range: buf.read_f32()?,
+                        description: TextComponent::text(""),
+                    })
+                } else {
+                    Ok(Instrument {
+                        sound_event,
+                        use_seconds: buf.read_f32()?,
+                        range: buf.read_f32()?,
+                        description: buf.read_component()?,
+                    })
+                }
+            })?),
+            "ominous_bottle_amplifier" => SlotComponent::OminousBottleAmplifier(buf.read_varint()?),
+            "jukebox_playable" => {
+                let song = if buf.read_bool()? {
+                    JukeboxSongRef::Holder(read_holder(buf, |buf| {
+                        Ok(JukeboxSong {
+                            sound_event: read_holder(buf, read_sound_event)?,
+                            description: buf.read_component()?,
+                            length_in_seconds: buf.read_f32()?,
+                            comparator_output: buf.read_varint()?,
+                        })
+                    })?)
+                } else {
+                    JukeboxSongRef::Key(buf.read_identifier()?)
+                };
+                SlotComponent::JukeboxPlayable(JukeboxPlayable {
+                    song,
+                    show_in_tooltip: buf.read_bool()?,
+                })
+            }
+            "recipes" => SlotComponent::Recipes(buf.read_nbt_tag()?),
+            "lodestone_tracker" => SlotComponent::LodestoneTracker(LodestoneTracker {
+                target: buf.read_prefixed_option(|buf| Ok((buf.read_identifier()?, buf.read_position()?)))?,
+                tracked: buf.read_bool()?,
+            }),
+            "firework_explosion" => SlotComponent::FireworkExplosion(read_firework_explosion(buf)?),
+            "fireworks" => SlotComponent::Fireworks(Fireworks {
+                flight_duration: buf.read_varint()?,
+                explosions: buf.read_prefixed_array(read_firework_explosion)?,
+            }),
+            "profile" => SlotComponent::Profile(Profile {
+                name: buf.read_prefixed_option(|buf| buf.read_string_max(16))?,
+                uuid: buf.read_prefixed_option(|buf| buf.read_uuid())?,
+                properties: buf.read_properties()?,
+            }),
+            "note_block_sound" => SlotComponent::NoteBlockSound(buf.read_identifier()?),
+            "banner_patterns" => SlotComponent::BannerPatterns(buf.read_prefixed_array(|buf| {
+                Ok(BannerLayer {
+                    pattern: read_holder(buf, |buf| {
+                        Ok(BannerPattern {
+                            asset_id: buf.read_identifier()?,
+                            translation_key: buf.read_string()?,
+                        })
+                    })?,
+                    color: buf.read_varint()?,
+                })
+            })?),
+            "base_color" => SlotComponent::BaseColor(buf.read_varint()?),
+            "pot_decorations" => SlotComponent::PotDecorations(buf.read_prefixed_array(|buf| buf.read_varint())?),
+            "container" => SlotComponent::Container(read_slots(buf)?),
+            "block_state" => {
+                SlotComponent::BlockState(buf.read_prefixed_array(|buf| Ok((buf.read_string()?, buf.read_string()?)))?)
+            }
+            "bees" => SlotComponent::Bees(buf.read_prefixed_array(|buf| {
+                Ok(Bee {
+                    entity_data: buf.read_nbt_tag()?,
+                    ticks_in_hive: buf.read_varint()?,
+                    min_ticks_in_hive: buf.read_varint()?,
+                })
+            })?),
+            "lock" => SlotComponent::Lock(buf.read_nbt_tag()?),
+            "container_loot" => SlotComponent::ContainerLoot(buf.read_nbt_tag()?),
+            name => return Err(DecodeError::InvalidData(format!("unsupported item component {}", name))),
+        };
+        Ok(component)
+    }
+}
+
+fn write_holder<T>(buf: &mut ByteBuf, holder: &Holder<T>, write: impl FnOnce(&mut ByteBuf, &T)) {
+    match holder {
+        Holder::Reference(id) => {
+            buf.write_varint(id + 1);
+        }
+        Holder::Direct(val) => {
+            buf.write_varint(0);
+            write(buf, val);
+        }
+    }
+}
+
+fn read_holder<T>(
+    buf: &mut ByteBuf,
+    read: impl FnOnce(&mut ByteBuf) -> Result<T, DecodeError>,
+) -> Result<Holder<T>, DecodeError> {
+    match buf.read_varint()? {
+        0 => Ok(Holder::Direct(Box::new(read(buf)?))),
+        id => Ok(Holder::Reference(id - 1)),
+    }
+}
+
+fn write_id_set(buf: &mut ByteBuf, set: &IdSet) {
+    match set {
+        IdSet::Tag(tag) => {
+            buf.write_varint(0);
+            buf.write_identifier(tag);
+        }
+        IdSet::Ids(ids) => {
+            buf.write_varint(ids.len() as i32 + 1);
+            for id in ids {
+                buf.write_varint(*id);
+            }
+        }
+    }
+}
+
+fn read_id_set(buf: &mut ByteBuf) -> Result<IdSet, DecodeError> {
+    let len = buf.read_len()?;
+    if len == 0 {
+        return Ok(IdSet::Tag(buf.read_identifier()?));
+    }
+    buf.ensure_remaining(len - 1)?;
+    let mut ids = Vec::with_capacity(len - 1);
+    for _ in 1..len {
+        ids.push(buf.read_varint()?);
+    }
+    Ok(IdSet::Ids(ids))
+}
+
+fn write_filterable<T>(buf: &mut ByteBuf, val: &Filterable<T>, mut write: impl FnMut(&mut ByteBuf, &T)) {
+    write(buf, &val.raw);
+    buf.write_prefixed_option(val.filtered.as_ref(), write);
+}
+
+fn read_filterable<T>(
+    buf: &mut ByteBuf,
+    mut read: impl FnMut(&mut ByteBuf) -> Result<T, DecodeError>,
+) -> Result<Filterable<T>, DecodeError> {
+    Ok(Filterable {
+        raw: read(buf)?,
+        filtered: buf.read_prefixed_option(read)?,
+    })
+}
+
+fn write_sound_event(buf: &mut ByteBuf, sound: &SoundEvent) {
+    buf.write_identifier(&sound.name);
+    buf.write_prefixed_option(sound.fixed_range.as_ref(), |buf, range| {
+        buf.write_f32(*range);
+    });
+}
+
+fn read_sound_event(buf: &mut ByteBuf) -> Result<SoundEvent, DecodeError> {
+    Ok(SoundEvent {
+        name: buf.read_identifier()?,
+        fixed_range: buf.read_prefixed_option(|buf| buf.read_f32())?,
+    })
+}
+
+fn write_effect_details(buf: &mut ByteBuf, details: &EffectDetails) {
+    buf.write_varint(details.amplifier);
+    buf.write_varint(details.duration);
+    buf.write_bool(details.ambient);
+    buf.write_bool(details.show_particles);
+    buf.write_bool(details.show_icon);
+    buf.write_prefixed_option(details.hidden_effect.as_deref(), write_effect_details);
+}
+
+fn read_effect_details(buf: &mut ByteBuf, depth: usize) -> Result<EffectDetails, DecodeError> {
+    if depth > MAX_EFFECT_DEPTH {
+        return Err(DecodeError::InvalidData("hidden effects nested too deep".to_string()));
+    }
+    Ok(EffectDetails {
+        amplifier: buf.read_varint()?,
+        duration: buf.read_varint()?,
+        ambient: buf.read_bool()?,
+        show_particles: buf.read_bool()?,
+        show_icon: buf.read_bool()?,
+        hidden_effect: buf.read_prefixed_option(|buf| Ok(Box::new(read_effect_details(buf, depth + 1)?)))?,
+    })
+}
+
+fn write_potion_effect(buf: &mut ByteBuf, effect: &PotionEffect) {
+    buf.write_varint(effect.effect);
+    write_effect_details(buf, &effect.details);
+}
+
+fn read_potion_effect(buf: &mut ByteBuf) -> Result<PotionEffect, DecodeError> {
+    Ok(PotionEffect {
+        effect: buf.read_varint()?,
+        details: read_effect_details(buf, 0)?,
+    })
+}
+
+fn write_consume_effect(buf: &mut ByteBuf, effect: &ConsumeEffect) {
+    match effect {
+        ConsumeEffect::ApplyEffects { effects, probability } => {
+            buf.write_varint(0);
+            buf.write_prefixed_array(effects, write_potion_effect);
+            buf.write_f32(*probability);
+        }
+        ConsumeEffect::RemoveEffects(effects) => {
+            buf.write_varint(1);
+            write_id_set(buf, effects);
+        }
+        ConsumeEffect::ClearAllEffects => {
+            buf.write_varint(2);
+        }
+        ConsumeEffect::TeleportRandomly { diameter } => {
+            buf.write_varint(3);
+            buf.write_f32(*diameter);
+        }
+        ConsumeEffect::PlaySound(sound) => {
+            buf.write_varint(4);
+            write_holder(buf, sound, write_sound_event);
+        }
+    }
+}
+
+fn read_consume_effect(buf: &mut ByteBuf) -> Result<ConsumeEffect, DecodeError> {
+    match buf.read_varint()? {
+        0 => Ok(ConsumeEffect::ApplyEffects {
+            effects: buf.read_prefixed_array(read_potion_effect)?,
+            probability: buf.read_f32()?,
+        }),
+        1 => Ok(ConsumeEffect::RemoveEffects(read_id_set(buf)?)),
+        2 => Ok(ConsumeEffect::ClearAllEffects),
+        3 => Ok(ConsumeEffect::TeleportRandomly {
+            diameter: buf.read_f32()?,
+        }),
+        4 => Ok(ConsumeEffect::PlaySound(read_holder(buf, read_sound_event)?)),
+        kind => Err(DecodeError::InvalidData(format!("unknown consume effect {}", kind))),
+    }
+}
+
+fn read_enchantments(buf: &mut ByteBuf) -> Result<Enchantments, DecodeError> {
+    Ok(Enchantments {
+        levels: buf.read_prefixed_array(|buf| Ok((buf.read_varint()?, buf.read_varint()?)))?,
+        show_in_tooltip: buf.read_bool()?,
+    })
+}
+
+fn write_block_predicate(buf: &mut ByteBuf, predicate: &BlockPredicate) {
+    buf.write_prefixed_option(predicate.blocks.as_ref(), write_id_set);
+    buf.write_prefixed_option(predicate.properties.as_ref(), |buf, properties| {
+        buf.write_prefixed_array(properties, |buf, property| {
+            buf.write_string(&property.name);
+            match &property.value {
+                PropertyValue::Exact(value) => {
+                    buf.write_bool(true);
+                    buf.write_string(value);
+                }
+                PropertyValue::Range { min, max } => {
+                    buf.write_bool(false);
+                    buf.write_prefixed_option(min.as_ref(), |buf, min| {
+                        buf.write_string(min);
+                    });
+                    buf.write_prefixed_option(max.as_ref(), |buf, max| {
+                        buf.write_string(max);
+                    });
+                }
+            }
+        });
+    });
+    buf.write_prefixed_option(predicate.nbt.as_ref(), |buf, nbt| {
+        buf.write_nbt_tag(nbt);
+    });
+}
+
+fn read_adventure_predicate(buf: &mut ByteBuf) -> Result<AdventurePredicate, DecodeError> {
+    let predicates = buf.read_prefixed_array(|buf| {
+        Ok(BlockPredicate {
+            blocks: buf.read_prefixed_option(read_id_set)?,
+            properties: buf.read_prefixed_option(|buf| {
+                buf.read_prefixed_array(|buf| {
+                    let name = buf.read_string()?;
+                    let value = if buf.read_bool()? {
+                        PropertyValue::Exact(buf.read_string()?)
+                    } else {
+                        PropertyValue::Range {
+                            min: buf.read_prefixed_option(|buf| buf.read_string())?,
+                            max: buf.read_prefixed_option(|buf| buf.read_string())?,
+                        }
+                    };
+                    Ok(PropertyMatcher { name, value })
+                })
+            })?,
+            nbt: buf.read_prefixed_option(|buf| buf.read_nbt_tag())?,
+        })
+    })?;
+    Ok(AdventurePredicate {
+        predicates,
+        show_in_tooltip: buf.read_bool()?,
+    })
+}
+
+fn write_trim_material(buf: &mut ByteBuf, material: &TrimMaterial, version: ProtocolVersion) {
+    buf.write_string(&material.asset_name);
+    buf.write_varint(material.ingredient);
+    if version <= ProtocolVersion::V1_21_3 {
+        buf.write_f32(material.item_model_index);
+    }
+    let overrides: Vec<_> = material
+        .overrides
+        .iter()
+        .filter(|(asset, _)| matches!(asset, ArmorAsset::Id(_)) == (version == ProtocolVersion::V1_21_1))
+        .collect();
+    buf.write_prefixed_array(&overrides, |buf, (asset, name)| {
+        match asset {
+            ArmorAsset::Id(id) => buf.write_varint(*id),
+            ArmorAsset::Asset(asset) => buf.write_identifier(asset),
+        };
+        buf.write_string(name);
+    });
+    buf.write_component(&material.description);
+}
+
+fn read_trim_material(buf: &mut ByteBuf, version: ProtocolVersion) -> Result<TrimMaterial, DecodeError> {
+    let asset_name = buf.read_string()?;
+    let ingredient = buf.read_varint()?;
+    let item_model_index = match version <= ProtocolVersion::V1_21_3 {
+        true => buf.read_f32()?,
+        false => 0.0,
+    };
+    let overrides = buf.read_prefixed_array(|buf| {
+        let asset = match version {
+            ProtocolVersion::V1_21_1 => ArmorAsset::Id(buf.read_varint()?),
+            _ => ArmorAsset::Asset(buf.read_identifier()?),
+        };
+        Ok((asset, buf.read_string()?))
+    })?;
+    Ok(TrimMaterial {
+        asset_name,
+        ingredient,
+        item_model_index,
+        overrides,
+        description: buf.read_component()?,
+    })
+}
+
+fn write_firework_explosion(buf: &mut ByteBuf, explosion: &FireworkExplosion) {
+    buf.write_varint(explosion.shape);
+    buf.write_prefixed_array(&explosion.colors, |buf, color| {
+        buf.write_i32(*color);
+    });
+    buf.write_prefixed_array(&explosion.fade_colors, |buf, color| {
+        buf.write_i32(*color);
+    });
+    buf.write_bool(explosion.has_trail);
+    buf.write_bool(explosion.has_twinkle);
+}
+
+fn read_firework_explosion(buf: &mut ByteBuf) -> Result<FireworkExplosion, DecodeError> {
+    Ok(FireworkExplosion {
+        shape: buf.read_varint()?,
+        colors: buf.read_prefixed_array(|buf| buf.read_i32())?,
+        fade_colors: buf.read_prefixed_array(|buf| buf.read_i32())?,
+        has_trail: buf.read_bool()?,
+        has_twinkle: buf.read_bool()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::item::{
+        AdventurePredicate, ArmorAsset, AttributeModifier, AttributeModifiers, BannerLayer, BannerPattern, Bee,
+        BlockPredicate, COMPONENTS_V1_21_1, COMPONENTS_V1_21_3, ConsumeEffect, Consumable, CustomModelData,
+        EffectDetails, Enchantments, Equippable, Filterable, FireworkExplosion, Fireworks, Food, Holder, IdSet,
+        Instrument, JukeboxPlayable, JukeboxSong, JukeboxSongRef, LodestoneTracker, MAX_SLOT_DEPTH, PotionContents,
+        PotionEffect, Profile, PropertyMatcher, PropertyValue, SlotComponent, SoundEvent, Tool, ToolRule, Trim,
+        TrimMaterial, TrimPattern, UseCooldown, WrittenBook,
+    };
+    use crate::protocol::ProtocolVersion;
+    use crate::text::{NamedColor, TextComponent};
+    use crate::types::{Identifier, Position, Slot};
+    use crate::{ByteBuf, Property};
+    use simdnbt::owned::{NbtCompound, NbtTag};
+    use std::collections::HashSet;
+    use uuid::Uuid;
+
+    fn id(val: &str) -> Identifier {
+        Identifier::parse(val).unwrap()
+    }
+
+    fn nbt() -> NbtTag {
+        NbtTag::Compound(NbtCompound::from_values(vec![("level".into(), NbtTag::Int(3))]))
+    }
+
+    fn sound() -> Holder<SoundEvent> {
+        Holder::Direct(Box::new(SoundEvent {
+            name: id("minecraft:item.armor.equip_gold"),
+            fixed_range: Some(16.0),
+        }))
+    }
+
+    fn effect() -> PotionEffect {
+        PotionEffect {
+            effect: 9,
+            details: EffectDetails {
+                amplifier: 1,
+                duration: 600,
+                ambient: false,
+                show_particles: true,
+                show_icon: true,
+                hidden_effect: Some(Box::new(EffectDetails {
+                    amplifier: 0,
+                    duration: 1200,
+                    ambient: false,
+                    show_particles: true,
+                    show_icon: true,
+                    hidden_effect: None,
+                })),
+            },
+        }
+    }
+
+    fn explosion() -> FireworkExplosion {
+        FireworkExplosion {
+            shape: 2,
+            colors: vec![0xFF0000, 0x00FF00],
+            fade_colors: vec![0x0000FF],
+            has_trail: true,
+            has_twinkle: false,
+        }
+    }
+
+    fn enchanted_slot() -> Slot {
+        let mut slot = Slot::new(880, 1);
+        slot.components_to_add.push(SlotComponent::Damage(3));
+        slot
+    }
+
+    /// A value of every component, with the length of its data in each
+    /// version of `ProtocolVersion::ALL` when its layout depends on the version.
+    fn components() -> Vec<(SlotComponent, Option<[usize; 3]>)> {
+        vec![
+            (SlotComponent::CustomData(nbt()), None),
+            (SlotComponent::MaxStackSize(16), None),
+            (SlotComponent::MaxDamage(250), None),
+            (SlotComponent::Damage(12), None),
+            (SlotComponent::Unbreakable { show_in_tooltip: false }, None),
+            (SlotComponent::CustomName(TextComponent::text("Excalibur").color(NamedColor::Gold)), None),
+            (SlotComponent::ItemName(TextComponent::text("Sword")), None),
+            (SlotComponent::ItemModel(id("minecraft:diamond_sword")), None),
+            (SlotComponent::Lore(vec![TextComponent::text("Pulled from a stone"), TextComponent::text("")]), None),
+            (SlotComponent::Rarity(3), None),
+            (
+                SlotComponent::Enchantments(Enchantments {
+                    levels: vec![(13, 5), (37, 3)],
+                    show_in_tooltip: true,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::CanPlaceOn(AdventurePredicate {
+                    predicates: vec![BlockPredicate {
+                        blocks: Some(IdSet::Ids(vec![1, 2, 3])),
+                        properties: Some(vec![
+                            PropertyMatcher {
+                                name: "facing".to_string(),
+                                value: PropertyValue::Exact("north".to_string()),
+                            },
+                            PropertyMatcher {
+                                name: "age".to_string(),
+                                value: PropertyValue::Range {
+                                    min: Some("2".to_string()),
+                                    max: None,
+                                },
+                            },
+                        ]),
+                        nbt: Some(nbt()),
+                    }],
+                    show_in_tooltip: true,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::CanBreak(AdventurePredicate {
+                    predicates: vec![BlockPredicate {
+                        blocks: Some(IdSet::Tag(id("minecraft:logs"))),
+                        properties: None,
+                        nbt: None,
+                    }],
+                    show_in_tooltip: false,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::AttributeModifiers(AttributeModifiers {
+                    modifiers: vec![AttributeModifier {
+                        attribute: 1,
+                        id: id("minecraft:base_attack_damage"),
+                        amount: 6.5,
+                        operation: 0,
+                        slot: 1,
+                    }],
+                    show_in_tooltip: true,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::CustomModelData(CustomModelData {
+                    floats: vec![3.0],
+                    ..CustomModelData::default()
+                }),
+                Some([1, 1, 8]),
+            ),
+            (SlotComponent::HideAdditionalTooltip, None),
+            (SlotComponent::HideTooltip, None),
+            (SlotComponent::RepairCost(4), None),
+            (SlotComponent::CreativeSlotLock, None),
+            (SlotComponent::EnchantmentGlintOverride(false), None),
+            (SlotComponent::IntangibleProjectile(NbtTag::Compound(NbtCompound::new())), None),
+            (
+                SlotComponent::Food(Food {
+                    nutrition: 4,
+                    saturation: 2.4,
+                    can_always_eat: false,
+                    eat_seconds: 1.6,
+                    using_converts_to: Slot::empty(),
+                    effects: Vec::new(),
+                }),
+                Some([12, 6, 6]),
+            ),
+            (
+                SlotComponent::Consumable(Consumable {
+                    consume_seconds: 1.6,
+                    animation: 1,
+                    sound: Holder::Reference(5),
+                    has_consume_particles: true,
+                    on_consume_effects: vec![
+                        ConsumeEffect::ApplyEffects {
+                            effects: vec![effect()],
+                            probability: 0.3,
+                        },
+                        ConsumeEffect::RemoveEffects(IdSet::Ids(vec![19])),
+                        ConsumeEffect::ClearAllEffects,
+                        ConsumeEffect::TeleportRandomly { diameter: 16.0 },
+                        ConsumeEffect::PlaySound(sound()),
+                    ],
+                }),
+                None,
+            ),
+            (SlotComponent::UseRemainder(enchanted_slot()), None),
+            (
+                SlotComponent::UseCooldown(UseCooldown {
+                    seconds: 0.5,
+                    cooldown_group: Some(id("minecraft:ender_pearl")),
+                }),
+                None,
+            ),
+            (SlotComponent::DamageResistant(id("minecraft:is_fire")), None),
+            (
+                SlotComponent::Tool(Tool {
+                    rules: vec![ToolRule {
+                        blocks: IdSet::Tag(id("minecraft:mineable/pickaxe")),
+                        speed: Some(8.0),
+                        correct_for_drops: Some(true),
+                    }],
+                    default_mining_speed: 1.0,
+                    damage_per_block: 1,
+                }),
+                None,
+            ),
+            (SlotComponent::Enchantable(15), None),
+            (
+                SlotComponent::Equippable(Equippable {
+                    slot: 4,
+                    equip_sound: sound(),
+                    model: Some(id("minecraft:gold")),
+                    camera_overlay: None,
+                    allowed_entities: Some(IdSet::Ids(Vec::new())),
+                    dispensable: true,
+                    swappable: true,
+                    damage_on_hurt: false,
+                }),
+                None,
+            ),
+            (SlotComponent::Repairable(IdSet::Tag(id("minecraft:gold_tool_materials"))), None),
+            (SlotComponent::Glider, None),
+            (SlotComponent::TooltipStyle(id("voidpoc:fancy")), None),
+            (SlotComponent::DeathProtection(vec![ConsumeEffect::ClearAllEffects]), None),
+            (SlotComponent::FireResistant, None),
+            (
+                SlotComponent::StoredEnchantments(Enchantments {
+                    levels: vec![(20, 1)],
+                    show_in_tooltip: false,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::DyedColor {
+                    color: 0xA06540,
+                    show_in_tooltip: true,
+                },
+                None,
+            ),
+            (SlotComponent::MapColor(0x46402E), None),
+            (SlotComponent::MapId(42), None),
+            (SlotComponent::MapDecorations(nbt()), None),
+            (SlotComponent::MapPostProcessing(1), None),
+            (SlotComponent::ChargedProjectiles(vec![Slot::new(802, 1)]), None),
+            (SlotComponent::BundleContents(vec![enchanted_slot(), Slot::new(1, 64)]), None),
+            (
+                SlotComponent::PotionContents(PotionContents {
+                    potion: Some(7),
+                    custom_color: Some(0xFF0000),
+                    custom_effects: vec![effect()],
+                    custom_name: None,
+                }),
+                Some([23, 24, 24]),
+            ),
+            (SlotComponent::SuspiciousStewEffects(vec![(16, 100), (14, 60)]), None),
+            (
+                SlotComponent::WritableBookContent(vec![Filterable {
+                    raw: "Dear diary".to_string(),
+                    filtered: Some("Dear ****".to_string()),
+                }]),
+                None,
+            ),
+            (
+                SlotComponent::WrittenBookContent(WrittenBook {
+                    title: Filterable {
+                        raw: "Notes".to_string(),
+                        filtered: None,
+                    },
+                    author: "Steve".to_string(),
+                    generation: 1,
+                    pages: vec![Filterable {
+                        raw: TextComponent::text("Page one"),
+                        filtered: Some(TextComponent::text("Page ***")),
+                    }],
+                    resolved: true,
+                }),
+                None,
+            ),
+            (
+                SlotComponent::Trim(Trim {
+                    material: Holder::Direct(Box::new(TrimMaterial {
+                        asset_name: "gold".to_string(),
+                        ingredient: 5,
+                        item_model_index: 0.0,
+                        overrides: Vec::new(),
+                        description: TextComponent::text("Gold"),
+                    })),
+                    pattern: Holder::Reference(3),
+                    show_in_tooltip: true,
+                }),
+                Some([21, 21, 17]),
+            ),
+            (SlotComponent::DebugStickState(nbt()), None),
+            (SlotComponent::EntityData(nbt()), None),
+            (SlotComponent::BucketEntityData(nbt()), None),
+            (SlotComponent::BlockEntityData(nbt()), None),
+            (
+                SlotComponent::Instrument(Holder::Direct(Box::new(Instrument {
+                    sound_event: Holder::Reference(0),
+                    use_seconds: 7.0,
+                    range: 256.0,
+                    description: TextComponent::text(""),
+                }))),
+                Some([8, 13, 13]),
+            ),
+            (SlotComponent::OminousBottleAmplifier(2), None),
+            (
+                SlotComponent::JukeboxPlayable(JukeboxPlayable {
+                    song: JukeboxSongRef::Holder(Holder::Direct(Box::new(JukeboxSong {
+                        sound_event: sound(),
+                        description: TextComponent::text("C418 - cat"),
+                        length_in_seconds: 185.0,
+                        comparator_output: 2,
+                    }))),
+                    show_in_tooltip: true,
+                }),
+                None,
+            ),
+            (SlotComponent::Recipes(nbt()), None),
+            (
+                SlotComponent::LodestoneTracker(LodestoneTracker {
+                    target: Some((id("minecraft:overworld"), Position::new(10, 64, -20))),
+                    tracked: true,
+                }),
+                None,
+            ),
+            (SlotComponent::FireworkExplosion(explosion()), None),
+            (
+                SlotComponent::Fireworks(Fireworks {
+                    flight_duration: 2,
+                    explosions: vec![explosion(), explosion()],
+                }),
+                None,
+            ),
+            (
+                SlotComponent::Profile(Profile {
+                    name: Some("Steve".to_string()),
+                    uuid: Some(Uuid::from_u128(0x8667ba71b85a4004af54457a9734eed7)),
+                    properties: vec![Property {
+                        name: "textures".to_string(),
+                        value: "e30=".to_string(),
+                        signature: None,
+                    }],
+                }),
+                None,
+            ),
+            (SlotComponent::NoteBlockSound(id("minecraft:block.note_block.harp")), None),
+            (
+                SlotComponent::BannerPatterns(vec![
+                    BannerLayer {
+                        pattern: Holder::Reference(4),
+                        color: 14,
+                    },
+                    BannerLayer {
+                        pattern: Holder::Direct(Box::new(BannerPattern {
+                            asset_id: id("voidpoc:void"),
+                            translation_key: "block.voidpoc.banner.void".to_string(),
+                        })),
+                        color: 15,
+                    },
+                ]),
+                None,
+            ),
+            (SlotComponent::BaseColor(11), None),
+            (SlotComponent::PotDecorations(vec![850, 851, 852, 853]), None),
+            (SlotComponent::Container(vec![Slot::new(1, 64), Slot::empty(), enchanted_slot()]), None),
+            (SlotComponent::BlockState(vec![("waterlogged".to_string(), "true".to_string())]), None),
+            (
+                SlotComponent::Bees(vec![Bee {
+                    entity_data: nbt(),
+                    ticks_in_hive: 100,
+                    min_ticks_in_hive: 600,
+                }]),
+                None,
+            ),
+            (SlotComponent::Lock(nbt()), None),
+            (SlotComponent::ContainerLoot(nbt()), None),
+        ]
+    }
+
+    #[test]
+    pub fn test_item_component_round_trip() {
+        let components = components();
+
+        // Every component of every version has a value
+        let names: HashSet<_> = components.iter().map(|(component, _)| component.name()).collect();
+        let registry: HashSet<_> = COMPONENTS_V1_21_1.into_iter().chain(COMPONENTS_V1_21_3).collect();
+        assert_eq!(names, registry);
+        assert_eq!(names.len(), components.len());
+
+        for (i, version) in ProtocolVersion::ALL.into_iter().enumerate() {
+            let mut written = 0;
+            for (component, lens) in &components {
+                let Some(id) = component.id(version) else {
+                    continue;
+                };
+                let mut buf = ByteBuf::new();
+                component.write_to(&mut buf, version);
+                if let Some(lens) = lens {
+                    // Ids are all below 128, a single byte
+                    assert_eq!(buf.len() - 1, lens[i], "{} in {}", component.name(), version);
+                }
+                assert_eq!(buf.as_slice()[0] as i32, id);
+                let read = SlotComponent::read_from(&mut buf, version).unwrap();
+                assert_eq!(read, *component, "{}", version);
+                assert_eq!(buf.remaining_len(), 0, "{} in {}", component.name(), version);
+                written += 1;
+            }
+            assert_eq!(written, super::component_names(version).len());
+        }
+
+        // All in a single slot, read back in order
+        for version in ProtocolVersion::ALL {
+            let mut slot = Slot::new(1, 1);
+            slot.components_to_add = components
+                .iter()
+                .map(|(component, _)| component.clone())
+                .filter(|component| component.id(version).is_some())
+                .collect();
+            let mut buf = ByteBuf::new();
+            buf.write_slot_for(&slot, version);
+            assert_eq!(buf.read_slot_for(version).unwrap(), slot);
+            assert_eq!(buf.remaining_len(), 0);
+        }
+
+        assert_eq!(SlotComponent::Lore(Vec::new()).id(ProtocolVersion::V1_21_1), Some(7));
+        assert_eq!(SlotComponent::Lore(Vec::new()).id(ProtocolVersion::V1_21_4), Some(8));
+        assert_eq!(SlotComponent::Glider.id(ProtocolVersion::V1_21_1), None);
+        assert_eq!(SlotComponent::FireResistant.id(ProtocolVersion::V1_21_3), None);
+    }
+
+    #[test]
+    pub fn test_item_component_versions() {
+        let round_trip = |component: &SlotComponent, version: ProtocolVersion| {
+            let mut buf = ByteBuf::new();
+            component.write_to(&mut buf, version);
+            let read = SlotComponent::read_from(&mut buf, version).unwrap();
+            assert_eq!(buf.remaining_len(), 0);
+            read
+        };
+
+        // Eating was moved to `Consumable` in 1.21.2
+        let food = Food {
+            nutrition: 4,
+            saturation: 2.4,
+            can_always_eat: true,
+            eat_seconds: 0.8,
+            using_converts_to: Slot::new(840, 1),
+            effects: vec![(effect(), 0.5)],
+        };
+        let component = SlotComponent::Food(food.clone());
+        assert_eq!(round_trip(&component, ProtocolVersion::V1_21_1), component);
+        let SlotComponent::Food(newer) = round_trip(&component, ProtocolVersion::V1_21_3) else {
+            panic!("not food");
+        };
+        assert_eq!((newer.nutrition, newer.saturation, newer.can_always_eat), (4, 2.4, true));
+        assert_eq!((newer.eat_seconds, newer.using_converts_to, newer.effects), (1.6, Slot::empty(), Vec::new()));
+
+        // A single integer until 1.21.4
+        let data = SlotComponent::CustomModelData(CustomModelData {
+            floats: vec![7.0, 0.5],
+            flags: vec![true, false],
+            strings: vec!["open".to_string()],
+            colors: vec![0x00FF00],
+        });
+        assert_eq!(round_trip(&data, ProtocolVersion::V1_21_4), data);
+        let mut buf = ByteBuf::new();
+        data.write_to(&mut buf, ProtocolVersion::V1_21_4);
+        assert_eq!(buf.len(), 1 + (1 + 8) + (1 + 2) + (1 + 5) + (1 + 4));
+        let expected = SlotComponent::CustomModelData(CustomModelData {
+            floats: vec![7.0],
+            ..CustomModelData::default()
+        });
+        assert_eq!(round_trip(&data, ProtocolVersion::V1_21_3), expected);
+        assert_eq!(round_trip(&data, ProtocolVersion::V1_21_1), expected);
+
+        // Custom potion names since 1.21.2
+        let potion = PotionContents {
+            potion: None,
+            custom_color: None,
+            custom_effects: Vec::new(),
+            custom_name: Some("haste".to_string()),
+        };
+        let component = SlotComponent::PotionContents(potion.clone());
+        assert_eq!(round_trip(&component, ProtocolVersion::V1_21_3), component);
+        let expected = SlotComponent::PotionContents(PotionContents {
+            custom_name: None,
+            ..potion
+        });
+        assert_eq!(round_trip(&component, ProtocolVersion::V1_21_1), expected);
+
+        // Use duration in ticks and no description until 1.21.2
+        let instrument = |description: &str| {
+            SlotComponent::Instrument(Holder::Direct(Box::new(Instrument {
+                sound_event: sound(),
+                use_seconds: 7.5,
+                range: 256.0,
+                description: TextComponent::text(description),
+            })))
+        };
+        assert_eq!(round_trip(&instrument("Ponder"), ProtocolVersion::V1_21_4), instrument("Ponder"));
+        assert_eq!(round_trip(&instrument("Ponder"), ProtocolVersion::V1_21_1), instrument(""));
+        let mut buf = ByteBuf::new();
+        instrument("").write_to(&mut buf, ProtocolVersion::V1_21_1);
+        buf.skip(buf.len() - 6).unwrap();
+        assert_eq!(buf.read_varint().unwrap(), 150);
+
+        // Overrides by armor material id until 1.21.1, by equipment asset since,
+        // the model index was dropped in 1.21.4
+        let trim = |item_model_index: f32, overrides: Vec<(ArmorAsset, String)>| {
+            SlotComponent::Trim(Trim {
+                material: Holder::Direct(Box::new(TrimMaterial {
+                    asset_name: "gold".to_string(),
+                    ingredient: 5,
+                    item_model_index,
+                    overrides,
+                    description: TextComponent::text("Gold"),
+                })),
+                pattern: Holder::Direct(Box::new(TrimPattern {
+                    asset_id: id("minecraft:coast"),
+                    template_item: 1000,
+                    description: TextComponent::text("Coast"),
+                    decal: false,
+                })),
+                show_in_tooltip: true,
+            })
+        };
+        let by_id = (ArmorAsset::Id(4), "gold_darker".to_string());
+        let by_asset = (ArmorAsset::Asset(id("minecraft:gold")), "gold_darker".to_string());
+        let both = trim(0.6, vec![by_id.clone(), by_asset.clone()]);
+        assert_eq!(round_trip(&both, ProtocolVersion::V1_21_1), trim(0.6, vec![by_id]));
+        assert_eq!(round_trip(&both, ProtocolVersion::V1_21_3), trim(0.6, vec![by_asset.clone()]));
+        assert_eq!(round_trip(&both, ProtocolVersion::V1_21_4), trim(0.0, vec![by_asset]));
+
+        // Songs not sent inline are referenced by name
+        let song = SlotComponent::JukeboxPlayable(JukeboxPlayable {
+            song: JukeboxSongRef::Key(id("minecraft:cat")),
+            show_in_tooltip: false,
+        });
+        for version in ProtocolVersion::ALL {
+            assert_eq!(round_trip(&song, version), song);
+        }
+    }
+
+    #[test]
+    pub fn test_item_component_invalid() {
+        // Unknown component id
+        let mut buf = ByteBuf::new();
+        buf.write_varint(67);
+        assert!(SlotComponent::read_from(&mut buf, ProtocolVersion::V1_21_4).is_err());
+
+        // Bundles nested in bundles, deeper than allowed
+        let mut slot = Slot::new(1, 1);
+        for _ in 0..=MAX_SLOT_DEPTH {
+            let mut bundle = Slot::new(2, 1);
+            bundle.components_to_add.push(SlotComponent::BundleContents(vec![slot]));
+            slot = bundle;
+        }
+        let mut buf = ByteBuf::new();
+        buf.write_slot(&slot);
+        assert!(buf.read_slot().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// KEEP ALIVE //
+
+// Same timings as vanilla
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum KeepAliveError {
+    /// No answer to the pending keep-alive, sent that long ago
+    TimedOut(Duration),
+    /// Answer to a keep-alive that was never sent, or already answered
+    UnexpectedId(i64),
+}
+
+impl fmt::Display for KeepAliveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepAliveError::TimedOut(elapsed) => write!(f, "keep-alive not answered in {}s", elapsed.as_secs()),
+            KeepAliveError::UnexpectedId(id) => write!(f, "unexpected keep-alive id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for KeepAliveError {}
+
+/// Keep-alive exchange of a connection, in Configuration and Play.
+///
+/// The server sends a random id every `KEEP_ALIVE_INTERVAL` once the previous
+/// one was answered, and gives up on clients silent for `KEEP_ALIVE_TIMEOUT`.
+pub struct KeepAlive {
+    last_sent: Instant,
+    pending: Option<i64>,
+    latency: Option<Duration>,
+}
+
+impl KeepAlive {
+    /// The first keep-alive is due an interval after `now`.
+    pub fn new(now: Instant) -> KeepAlive {
+        KeepAlive {
+            last_sent: now,
+            pending: None,
+            latency: None,
+        }
+    }
+
+    /// Id of the keep-alive to send now, if one is due. Meant to be called
+    /// regularly, more often than the interval.
+    pub fn poll(&mut self, now: Instant) -> Result<Option<i64>, KeepAliveError> {
+        let elapsed = now.saturating_duration_since(self.last_sent);
+        if self.pending.is_some() {
+            if elapsed >= KEEP_ALIVE_TIMEOUT {
+                return Err(KeepAliveError::TimedOut(elapsed));
+            }
+            return Ok(None);
+        }
+        if elapsed < KEEP_ALIVE_INTERVAL {
+            return Ok(None);
+        }
+        let id = rand::random();
+        self.last_sent = now;
+        self.pending = Some(id);
+        Ok(Some(id))
+    }
+
+    /// Checks an answer, returning the round trip time.
+    pub fn receive(&mut self, id: i64, now: Instant) -> Result<Duration, KeepAliveError> {
+        if self.pending != Some(id) {
+            return Err(KeepAliveError::UnexpectedId(id));
+        }
+        self.pending = None;
+        let round_trip = now.saturating_duration_since(self.last_sent);
+        // Smoothed like vanilla, a single slow answer only weighs a quarter
+        self.latency = Some(match self.latency {
+            Some(latency) => (latency * 3 + round_trip) / 4,
+            None => round_trip,
+        });
+        Ok(round_trip)
+    }
+
+    /// Smoothed round trip time, `None` until the first answer.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keepalive::{KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, KeepAlive, KeepAliveError};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    pub fn test_keep_alive_exchange() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        assert_eq!(keep_alive.poll(start + Duration::from_secs(1)), Ok(None));
+
+        let sent = start + KEEP_ALIVE_INTERVAL;
+        let id = keep_alive.poll(sent).unwrap().unwrap();
+        // Nothing new while the previous one is pending
+        assert_eq!(keep_alive.poll(sent + KEEP_ALIVE_INTERVAL), Ok(None));
+        assert_eq!(keep_alive.receive(id + 1, sent), Err(KeepAliveError::UnexpectedId(id + 1)));
+
+        assert_eq!(keep_alive.receive(id, sent + Duration::from_millis(80)), Ok(Duration::from_millis(80)));
+        assert_eq!(keep_alive.latency(), Some(Duration::from_millis(80)));
+        assert_eq!(keep_alive.receive(id, sent), Err(KeepAliveError::UnexpectedId(id)));
+
+        let sent = sent + KEEP_ALIVE_INTERVAL;
+        let id = keep_alive.poll(sent).unwrap().unwrap();
+        keep_alive.receive(id, sent + Duration::from_millis(40)).unwrap();
+        assert_eq!(keep_alive.latency(), Some(Duration::from_millis(70)));
+    }
+
+    #[test]
+    pub fn test_keep_alive_timeout() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        let sent = start + KEEP_ALIVE_INTERVAL;
+        keep_alive.poll(sent).unwrap().unwrap();
+        assert_eq!(keep_alive.poll(sent + KEEP_ALIVE_TIMEOUT - Duration::from_millis(1)), Ok(None));
+        assert_eq!(
+            keep_alive.poll(sent + KEEP_ALIVE_TIMEOUT),
+            Err(KeepAliveError::TimedOut(KEEP_ALIVE_TIMEOUT))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::status::ServerStatusResponse;
+
+// LEGACY PING //
+
+// Protocol advertised to pre-1.7 clients, above any of theirs so they show
+// the server as incompatible, like vanilla
+const LEGACY_PROTOCOL: i32 = 127;
+
+/// Server list ping of clients older than 1.7, which is not length-prefixed.
+/// It starts with 0xFE, a byte no modern handshake starts with in practice.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3, a lone 0xFE
+    Beta,
+    /// 1.4 to 1.6, 0xFE 0x01, followed by a `MC|PingHost` plugin message since 1.6
+    V1_4,
+}
+
+impl LegacyPing {
+    /// Recognizes a legacy ping from the first bytes received on a connection.
+    pub fn detect(data: &[u8]) -> Option<LegacyPing> {
+        match data {
+            [0xFE] => Some(LegacyPing::Beta),
+            [0xFE, 0x01, ..] => Some(LegacyPing::V1_4),
+            _ => None,
+        }
+    }
+
+    /// Kick packet answering the ping, the connection is closed right after.
+    pub fn response(self, status: &ServerStatusResponse) -> Vec<u8> {
+        let players = &status.players;
+        let text = match self {
+            // Fields are separated by §, so the MOTD is sent without formatting
+            LegacyPing::Beta => {
+                let motd = status.description.to_plain().replace('§', "");
+                format!("{}§{}§{}", motd, players.online, players.max)
+            }
+            LegacyPing::V1_4 => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                LEGACY_PROTOCOL,
+                status.version.name,
+                status.description.to_legacy(),
+                players.online,
+                players.max
+            ),
+        };
+
+        // Packet 0xFF, then the string as UTF-16 prefixed by its length in code units
+        let text: Vec<u16> = text.encode_utf16().collect();
+        let mut data = Vec::with_capacity(3 + text.len() * 2);
+        data.push(0xFF);
+        data.extend_from_slice(&(text.len() as u16).to_be_bytes());
+        for unit in text {
+            data.extend_from_slice(&unit.to_be_bytes());
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::legacy::LegacyPing;
+    use crate::status::ClientBoundStatusResponsePacket;
+    use crate::text::TextComponent;
+
+    fn decode(data: &[u8]) -> String {
+        assert_eq!(data[0], 0xFF);
+        let len = u16::from_be_bytes([data[1], data[2]]) as usize;
+        let units: Vec<u16> = data[3..].chunks(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]])).collect();
+        assert_eq!(units.len(), len);
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    pub fn test_legacy_detect() {
+        assert_eq!(LegacyPing::detect(&[0xFE]), Some(LegacyPing::Beta));
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01]), Some(LegacyPing::V1_4));
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]), Some(LegacyPing::V1_4));
+        // Modern handshake
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00, 0x81, 0x06]), None);
+        assert_eq!(LegacyPing::detect(&[]), None);
+    }
+
+    #[test]
+    pub fn test_legacy_response() {
+        let mut status = ClientBoundStatusResponsePacket::default().response;
+        status.description = TextComponent::from_legacy("A §avoid");
+        status.players.online = 3;
+        status.players.max = 20;
+        status.version.name = "1.21.4".to_string();
+
+        assert_eq!(decode(&LegacyPing::Beta.response(&status)), "A void§3§20");
+        assert_eq!(
+            decode(&LegacyPing::V1_4.response(&status)),
+            "§1\u{0}127\u{0}1.21.4\u{0}A §avoid\u{0}3\u{0}20"
+        );
+    }
+}
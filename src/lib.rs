@@ -0,0 +1,43 @@
+// Lets the derive macros refer to `::voidpoc` from inside this crate too
+extern crate self as voidpoc;
+
+pub mod auth;
+pub mod buf;
+pub mod capture;
+pub mod client;
+pub mod clock;
+pub mod config;
+pub mod configuration;
+pub mod connection;
+pub mod crypto;
+pub mod favicon;
+pub mod field;
+pub mod frame;
+pub mod handshake;
+pub mod ids;
+pub mod item;
+pub mod keepalive;
+pub mod legacy;
+pub mod login;
+pub mod packet;
+pub mod phase;
+pub mod play;
+pub mod players;
+pub mod protocol;
+pub mod proxy;
+pub mod shutdown;
+pub mod status;
+pub mod text;
+pub mod types;
+pub mod vhost;
+
+pub use crate::buf::{ByteBuf, DecodeError};
+
+pub type PacketType = i32;
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
@@ -1,35 +1,66 @@
 use crate::packet::{Packet, PacketFactory};
-use crate::{ByteBuf, PacketType, Property};
-use std::any::Any;
+use crate::Property;
+use crate::text::TextComponent;
 use uuid::Uuid;
 
 // LOGIN //
 
 #[derive(Eq, PartialEq)]
 pub enum EServerLoginPacket {
-    ServerBoundLoginStartPacket = 0x00,
-    ServerBoundLoginLoginAcknowledgedPacket = 0x03,
+    ServerBoundLoginStartPacket,
+    ServerBoundLoginEncryptionResponsePacket,
+    ServerBoundLoginLoginAcknowledgedPacket,
 }
 
 pub struct ServerLoginPacketFactory {}
 
 impl PacketFactory<EServerLoginPacket> for ServerLoginPacketFactory {
-    fn decode<T: Packet + 'static>(&self, id: i32, buf: &mut ByteBuf) -> Box<T> {
-        let packet: Box<dyn Any> = match id {
-            0x00 => Box::new(ServerBoundLoginStartPacket::default()) as Box<dyn Any>,
-            0x03 => Box::new(ServerBoundLoginLoginAcknowledgedPacket::default()) as Box<dyn Any>,
-            _ => panic!("Unknown packet id"),
-        };
-        let mut packet: Box<T> = packet.downcast::<T>().expect("Failed to downcast Packet");
-
-        packet.decode(buf);
-        packet
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ServerBoundLoginStartPacket::ID => Some(Box::new(ServerBoundLoginStartPacket::default())),
+            ServerBoundLoginEncryptionResponsePacket::ID => Some(Box::new(ServerBoundLoginEncryptionResponsePacket::default())),
+            ServerBoundLoginLoginAcknowledgedPacket::ID => Some(Box::new(ServerBoundLoginLoginAcknowledgedPacket::default())),
+            _ => None,
+        }
     }
 
     fn from_id(&self, id: i32) -> Option<EServerLoginPacket> {
         match id {
-            0x00 => Some(EServerLoginPacket::ServerBoundLoginStartPacket),
-            0x03 => Some(EServerLoginPacket::ServerBoundLoginLoginAcknowledgedPacket),
+            ServerBoundLoginStartPacket::ID => Some(EServerLoginPacket::ServerBoundLoginStartPacket),
+            ServerBoundLoginEncryptionResponsePacket::ID => Some(EServerLoginPacket::ServerBoundLoginEncryptionResponsePacket),
+            ServerBoundLoginLoginAcknowledgedPacket::ID => Some(EServerLoginPacket::ServerBoundLoginLoginAcknowledgedPacket),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq)]
+pub enum EClientLoginPacket {
+    ClientBoundLoginEncryptionRequestPacket,
+    ClientBoundLoginSetCompressionPacket,
+    ClientBoundLoginLoginSuccessPacket,
+    ClientBoundLoginDisconnectPacket,
+}
+
+pub struct ClientLoginPacketFactory {}
+
+impl PacketFactory<EClientLoginPacket> for ClientLoginPacketFactory {
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ClientBoundLoginEncryptionRequestPacket::ID => Some(Box::new(ClientBoundLoginEncryptionRequestPacket::default())),
+            ClientBoundLoginSetCompressionPacket::ID => Some(Box::new(ClientBoundLoginSetCompressionPacket::default())),
+            ClientBoundLoginLoginSuccessPacket::ID => Some(Box::new(ClientBoundLoginLoginSuccessPacket::default())),
+            ClientBoundLoginDisconnectPacket::ID => Some(Box::new(ClientBoundLoginDisconnectPacket::default())),
+            _ => None,
+        }
+    }
+
+    fn from_id(&self, id: i32) -> Option<EClientLoginPacket> {
+        match id {
+            ClientBoundLoginEncryptionRequestPacket::ID => Some(EClientLoginPacket::ClientBoundLoginEncryptionRequestPacket),
+            ClientBoundLoginSetCompressionPacket::ID => Some(EClientLoginPacket::ClientBoundLoginSetCompressionPacket),
+            ClientBoundLoginLoginSuccessPacket::ID => Some(EClientLoginPacket::ClientBoundLoginLoginSuccessPacket),
+            ClientBoundLoginDisconnectPacket::ID => Some(EClientLoginPacket::ClientBoundLoginDisconnectPacket),
             _ => None,
         }
     }
@@ -41,7 +72,10 @@ impl PacketFactory<EServerLoginPacket> for ServerLoginPacketFactory {
 
 // Login Start
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:hello", state = Login, bound = Server)]
 pub struct ServerBoundLoginStartPacket {
+    #[packet(max_len = 16)]
     pub name: String,
     pub uuid: Uuid,
 }
@@ -55,24 +89,21 @@ impl Default for ServerBoundLoginStartPacket {
     }
 }
 
-impl Packet for ServerBoundLoginStartPacket {
-    fn id(&self) -> PacketType {
-        0x00
-    }
+// Encryption Response 0x01
 
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_string(&self.name);
-        buf.write_uuid(&self.uuid);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        self.name = buf.read_string();
-        self.uuid = buf.read_uuid();
-    }
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:key", state = Login, bound = Server)]
+pub struct ServerBoundLoginEncryptionResponsePacket {
+    #[packet(length_prefixed)]
+    pub shared_secret: Vec<u8>,
+    #[packet(length_prefixed)]
+    pub verify_token: Vec<u8>,
 }
 
 // Login Acknowledged
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:login_acknowledged", state = Login, bound = Server)]
 pub struct ServerBoundLoginLoginAcknowledgedPacket {}
 
 impl Default for ServerBoundLoginLoginAcknowledgedPacket {
@@ -81,58 +112,68 @@ impl Default for ServerBoundLoginLoginAcknowledgedPacket {
     }
 }
 
-impl Packet for ServerBoundLoginLoginAcknowledgedPacket {
-    fn id(&self) -> PacketType {
-        0x03
-    }
+// CLIENTBOUND //
 
-    fn encode(&self, buf: &mut ByteBuf) {
-    }
+// Disconnect (login)
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:login_disconnect", state = Login, bound = Client)]
+pub struct ClientBoundLoginDisconnectPacket {
+    /// JSON, unlike the NBT of later states
+    #[packet(json)]
+    pub reason: TextComponent,
+}
+
+impl ClientBoundLoginDisconnectPacket {
+    pub fn new(reason: impl Into<TextComponent>) -> ClientBoundLoginDisconnectPacket {
+        ClientBoundLoginDisconnectPacket { reason: reason.into() }
     }
 }
 
-// CLIENTBOUND //
+// Encryption Request 0x01
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:hello", state = Login, bound = Client)]
+pub struct ClientBoundLoginEncryptionRequestPacket {
+    #[packet(max_len = 20)]
+    pub server_id: String,
+    #[packet(length_prefixed)]
+    pub public_key: Vec<u8>,
+    #[packet(length_prefixed)]
+    pub verify_token: Vec<u8>,
+    pub should_authenticate: bool,
+}
+
+// Set Compression 0x03
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:login_compression", state = Login, bound = Client)]
+pub struct ClientBoundLoginSetCompressionPacket {
+    #[packet(varint)]
+    pub threshold: i32,
+}
 
 // Login Success
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:login_finished", state = Login, bound = Client)]
 pub struct ClientBoundLoginLoginSuccessPacket {
     pub uuid: Uuid,
+    #[packet(max_len = 16)]
     pub username: String,
+    #[packet(length_prefixed)]
     pub properties: Vec<Property>,
+    #[packet(until = V1_21_1)]
+    pub strict_error_handling: bool,
 }
 
 impl Default for ClientBoundLoginLoginSuccessPacket {
     fn default() -> Self {
         Self {
-            uuid: Uuid::new_v4(),
+            uuid: Uuid::nil(),
             username: String::new(),
-            properties: vec![
-                Property {
-                    name: "textures".to_string(),
-                    value: "ewogICJ0aW1lc3RhbXAiIDogMTc0MTA5NzkwNjQ2OCwKICAicHJvZmlsZUlkIiA6ICI3ZmQyZmQyY2I2ZDc0ZGRmYjY0MjZjMzI5Mjk2YWRmOCIsCiAgInByb2ZpbGVOYW1lIiA6ICJkYW5kYW4yNjExIiwKICAidGV4dHVyZXMiIDogewogICAgIlNLSU4iIDogewogICAgICAidXJsIiA6ICJodHRwOi8vdGV4dHVyZXMubWluZWNyYWZ0Lm5ldC90ZXh0dXJlLzc3YzQ2MzAyYWU2MmRhOTI0MDVmMjRmZGJjN2FmZGFhOTc3NzRiMGRkODg5MjBkODk3MjNiYTlmMDhiZWI5MDkiCiAgICB9CiAgfQp9".to_string(),
-                    signature: None,
-                }
-            ],
+            properties: Vec::new(),
+            strict_error_handling: false,
         }
     }
 }
-
-impl Packet for ClientBoundLoginLoginSuccessPacket {
-    fn id(&self) -> PacketType {
-        0x02
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_uuid(&self.uuid);
-        buf.write_string(&self.username);
-        buf.write_properties(&self.properties);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        self.uuid = buf.read_uuid();
-        self.username = buf.read_string();
-        self.properties = buf.read_properties();
-    }
-}
\ No newline at end of file
@@ -1,415 +1,305 @@
-mod configuration;
-mod handshake;
-mod login;
-mod packet;
-mod status;
-mod play;
-
-use crate::configuration::{ClientBoundConfigurationClientBoundKnownPacks, ClientBoundConfigurationFinishConfigurationPacket, ClientBoundConfigurationRegistryDataPacket, EServerConfigurationPacket, RegistryEntry};
-use crate::handshake::ServerBoundHandshakePacket;
-use crate::login::{
-    ClientBoundLoginLoginSuccessPacket, EServerLoginPacket, ServerBoundLoginStartPacket,
+use simdnbt::owned::{BaseNbt, Nbt, NbtCompound, NbtTag};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::task::JoinSet;
+use voidpoc::auth::{self, SessionService};
+use voidpoc::capture::Capture;
+use voidpoc::clock::{GameClock, TIME_UPDATE_INTERVAL};
+use voidpoc::config::ServerConfig;
+use voidpoc::connection::{PlayerHandle, SendError};
+use voidpoc::configuration::{ClientBoundConfigurationClientBoundKnownPacks, ClientBoundConfigurationDisconnectPacket, ClientBoundConfigurationFinishConfigurationPacket, ClientBoundConfigurationKeepAlivePacket, ClientBoundConfigurationRegistryDataPacket, EServerConfigurationPacket, RegistryEntry, ServerBoundConfigurationClientInformationPacket, ServerBoundConfigurationKeepAlivePacket};
+use voidpoc::crypto::{self, ServerKeyPair, StreamDecryptor};
+use voidpoc::frame::FrameDecoder;
+use voidpoc::handshake::ServerBoundHandshakePacket;
+use voidpoc::ids;
+use voidpoc::keepalive::KeepAlive;
+use voidpoc::legacy::LegacyPing;
+use voidpoc::login::{
+    ClientBoundLoginDisconnectPacket, ClientBoundLoginEncryptionRequestPacket, ClientBoundLoginLoginSuccessPacket,
+    ClientBoundLoginSetCompressionPacket, EServerLoginPacket,
+    ServerBoundLoginEncryptionResponsePacket, ServerBoundLoginStartPacket,
 };
-use crate::packet::{ClientBoundPlayKeepAlivePacket, Packet, PacketFactory, PacketManager, PacketStatus};
-use crate::status::{
+use voidpoc::packet::{ClientBoundPlayKeepAlivePacket, Packet, PacketBound, PacketFactory, PacketManager, PacketStatus};
+use voidpoc::phase::{ClientIdentity, ClientSettings, ConnectionPhase, Intent, PhaseError};
+use voidpoc::players::PlayerRegistry;
+use voidpoc::play::{ClientBoundPlayChunkDataPacket, ClientBoundPlayDisconnectPacket, ClientBoundPlayGameEventPacket, ClientBoundPlayLoginPacket, ClientBoundPlayPlayerPosition, ClientBoundPlayPongResponsePacket, ClientBoundPlaySetChunkCenterPacket, ClientBoundPlaySetTimePacket, ServerBoundPlayKeepAlivePacket, ServerBoundPlayPingRequestPacket};
+use voidpoc::protocol::ProtocolVersion;
+use voidpoc::shutdown::{DRAIN_TIMEOUT, SAVE_TIMEOUT, SaveHooks, Shutdown};
+use voidpoc::status::{
     ClientBoundStatusPingResponsePacket, ClientBoundStatusResponsePacket, EServerStatusPacket,
-    ServerBoundStatusPingPacket,
+    ServerBoundStatusPingPacket, ServerStatusResponse,
 };
-use simdnbt::owned::{BaseNbt, Nbt, NbtCompound, NbtTag};
-use std::io::Read;
-use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::Mutex;
-use uuid::Uuid;
-use crate::play::{ClientBoundPlayChunkDataPacket, ClientBoundPlayGameEventPacket, ClientBoundPlayLoginPacket, ClientBoundPlayPlayerPosition, ClientBoundPlaySetChunkCenterPacket};
-
-type PacketType = i32;
-
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
-struct Property {
-    name: String,
-    value: String,
-    signature: Option<String>,
+use voidpoc::text::{NamedColor, TextComponent};
+use voidpoc::vhost::{VirtualHost, VirtualHosts};
+use voidpoc::{ByteBuf, DecodeError};
+
+// How often keep-alives are checked, they are sent every 15s
+const KEEP_ALIVE_CHECK: Duration = Duration::from_secs(1);
+
+/// Why a packet ends the connection.
+#[derive(Debug)]
+enum PacketError {
+    Decode(DecodeError),
+    /// Well-formed, but not allowed in the current phase
+    Phase(PhaseError),
 }
 
-struct ByteBuf {
-    data: Vec<u8>,
-    read_offset: usize,
-}
-
-impl ByteBuf {
-    const SEGMENT_BITS: i32 = 0x7F;
-    const CONTINUE_BIT: i32 = 0x80;
-
-    pub fn new() -> ByteBuf {
-        ByteBuf {
-            data: Vec::new(),
-            read_offset: 0,
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::Decode(err) => write!(f, "{}", err),
+            PacketError::Phase(err) => write!(f, "{}", err),
         }
     }
+}
 
-    pub fn dump(&self) {
-        for i in 0..self.data.len() {
-            print!("{:02X} ", self.data[i]);
-        }
-        println!();
-    }
-
-    pub fn pop_front_i8(&mut self) -> i8 {
-        let val = self.data[0] as i8;
-        self.data = self.data[1..].to_vec();
-        val
-    }
-
-    pub fn write_u8(&mut self, val: u8) -> &Self {
-        self.data.push(val);
-        self
-    }
-
-    pub fn read_u8(&mut self) -> u8 {
-        let val = self.data[self.read_offset];
-        self.read_offset += 1;
-        val
-    }
-
-    pub fn write_u16(&mut self, val: u16) -> &Self {
-        self.write_u8((val >> 8) as u8);
-        self.write_u8((val & 0xFF) as u8);
-        self
-    }
-
-    pub fn read_u16(&mut self) -> u16 {
-        let val =
-            (self.data[self.read_offset] as u16) << 8 | self.data[self.read_offset + 1] as u16;
-        self.read_offset += 2;
-        val
-    }
-
-    pub fn write_u32(&mut self, val: u32) -> &Self {
-        self.write_u8((val >> 24) as u8);
-        self.write_u8((val >> 16) as u8);
-        self.write_u8((val >> 8) as u8);
-        self.write_u8((val) as u8);
-        self
-    }
-
-    pub fn read_u32(&mut self) -> u32 {
-        let val = (self.data[self.read_offset] as u32) << 24
-            | (self.data[self.read_offset + 1] as u32) << 16
-            | (self.data[self.read_offset + 2] as u32) << 8
-            | (self.data[self.read_offset + 3] as u32);
-        self.read_offset += 4;
-        val
-    }
-
-    pub fn write_u64(&mut self, val: u64) -> &Self {
-        self.write_u8((val >> 56) as u8);
-        self.write_u8((val >> 48) as u8);
-        self.write_u8((val >> 40) as u8);
-        self.write_u8((val >> 32) as u8);
-        self.write_u8((val >> 24) as u8);
-        self.write_u8((val >> 16) as u8);
-        self.write_u8((val >> 8) as u8);
-        self
-    }
-
-    pub fn read_u64(&mut self) -> u64 {
-        let val = (self.data[self.read_offset] as u64) << 56
-            | (self.data[self.read_offset + 1] as u64) << 48
-            | (self.data[self.read_offset + 2] as u64) << 40
-            | (self.data[self.read_offset + 3] as u64) << 32
-            | (self.data[self.read_offset + 4] as u64) << 24
-            | (self.data[self.read_offset + 5] as u64) << 16
-            | (self.data[self.read_offset + 6] as u64) << 8
-            | (self.data[self.read_offset + 7] as u64);
-        self.read_offset += 8;
-        val
+impl From<DecodeError> for PacketError {
+    fn from(err: DecodeError) -> Self {
+        PacketError::Decode(err)
     }
+}
 
-    pub fn write_i8(&mut self, val: i8) -> &Self {
-        self.write_u8(val as u8);
-        self
+impl From<PhaseError> for PacketError {
+    fn from(err: PhaseError) -> Self {
+        PacketError::Phase(err)
     }
+}
 
-    pub fn read_i8(&mut self) -> i8 {
-        let val = self.data[self.read_offset] as i8;
-        self.read_offset += 1;
-        val
-    }
+/// Read half of the client socket, transparently decrypting once the
+/// encryption handshake is done. Writing goes through the `PlayerHandle`.
+struct ClientSocket {
+    stream: OwnedReadHalf,
+    decryptor: Option<StreamDecryptor>,
+}
 
-    pub fn write_i16(&mut self, val: i16) -> &Self {
-        self.write_u8((val >> 8) as u8);
-        self.write_u8((val & 0xFF) as u8);
-        self
+impl ClientSocket {
+    pub fn new(stream: OwnedReadHalf) -> ClientSocket {
+        ClientSocket {
+            stream,
+            decryptor: None,
+        }
     }
 
-    pub fn write_i32(&mut self, val: i32) -> &Self {
-        self.write_u8((val >> 24) as u8);
-        self.write_u8((val >> 16) as u8);
-        self.write_u8((val >> 8) as u8);
-        self.write_u8((val) as u8);
-        self
+    pub async fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.stream.read(buf).await?;
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.decrypt(&mut buf[..size]);
+        }
+        Ok(size)
     }
 
-    pub fn write_i64(&mut self, val: i64) -> &Self {
-        self.write_u8((val >> 56) as u8);
-        self.write_u8((val >> 48) as u8);
-        self.write_u8((val >> 40) as u8);
-        self.write_u8((val >> 32) as u8);
-        self.write_u8((val >> 24) as u8);
-        self.write_u8((val >> 16) as u8);
-        self.write_u8((val >> 8) as u8);
-        self.write_u8(val as u8);
-        self
-    }
+}
 
-    pub fn read_i64(&mut self) -> i64 {
-        let val = (self.data[self.read_offset] as i64) << 56
-            | (self.data[self.read_offset + 1] as i64) << 48
-            | (self.data[self.read_offset + 2] as i64) << 40
-            | (self.data[self.read_offset + 3] as i64) << 32
-            | (self.data[self.read_offset + 4] as i64) << 24
-            | (self.data[self.read_offset + 5] as i64) << 16
-            | (self.data[self.read_offset + 6] as i64) << 8
-            | (self.data[self.read_offset + 7] as i64);
-        self.read_offset += 8;
-        val
-    }
+struct ClientConnection {
+    id: u64,
+    socket: ClientSocket,
+    handle: PlayerHandle,
+    phase: ConnectionPhase,
+    config: Arc<ServerConfig>,
+    key_pair: Arc<ServerKeyPair>,
+    session_service: Arc<SessionService>,
+    decoder: FrameDecoder,
+    packets: PacketManager,
+    keep_alive: KeepAlive,
+    capture: Option<Capture>,
+    players: PlayerRegistry,
+    clock: GameClock,
+    hosts: Arc<VirtualHosts>,
+    /// Picked from the handshake, the default host until then
+    host: Arc<VirtualHost>,
+}
 
-    pub fn write_f32(&mut self, val: f32) -> &Self {
-        self.write_u32(val.to_bits());
-        self
+impl ClientConnection {
+    pub async fn send_packet(&mut self, packet: Box<dyn Packet>) {
+        // Queued for the writer task, encoded for the protocol version of the client
+        match self.handle.send(packet.as_ref()).await {
+            Ok(()) => {}
+            Err(SendError::UnsupportedPacket(name)) => eprintln!("{} does not exist in {}, not sent", name, self.packets.version),
+            // Closing, the read loop stops next
+            Err(_) => {}
+        }
     }
 
-    pub fn read_f32(&mut self) -> f32 {
-        f32::from_bits(self.read_u32())
+    /// Switches both directions to the compressed frame format.
+    pub async fn set_compression(&mut self, threshold: Option<i32>) {
+        self.decoder.set_compression(threshold);
+        let _ = self.handle.set_compression(threshold).await;
     }
 
-    pub fn write_f64(&mut self, val: f64) -> &Self {
-        self.write_u64(val.to_bits());
-        self
+    /// Encrypts both directions from now on.
+    pub async fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), cfb8::cipher::InvalidLength> {
+        let (encryptor, decryptor) = crypto::stream_ciphers(shared_secret)?;
+        self.socket.decryptor = Some(decryptor);
+        let _ = self.handle.enable_encryption(encryptor).await;
+        Ok(())
     }
 
-    pub fn read_f64(&mut self) -> f64 {
-        f64::from_bits(self.read_u64())
+    /// Server list entry, for the protocol version of the client.
+    pub fn status_response(&self) -> ServerStatusResponse {
+        let mut response = ClientBoundStatusResponsePacket::default().response;
+        response.version.name = self.packets.version.name().to_string();
+        response.version.protocol = self.packets.version.protocol();
+        response.description = TextComponent::from_legacy(&self.host.motd);
+        response.players = self.players.status(self.host.name.as_deref(), self.host.max_players, &self.config.status_sample);
+        response.favicon = self.host.favicon.data_uri();
+        response
     }
 
-    pub fn write_varint(&mut self, mut val: i32) -> &Self {
-        loop {
-            if (val & !ByteBuf::SEGMENT_BITS) == 0 {
-                self.write_u8(val as u8);
-                return self;
+    /// Sends a keep-alive when due in Configuration and Play, closing the
+    /// connection of clients that stopped answering.
+    pub async fn keep_alive(&mut self) {
+        let status = self.phase.status();
+        if status != PacketStatus::Configuration && status != PacketStatus::Play {
+            return;
+        }
+        match self.keep_alive.poll(Instant::now()) {
+            Ok(Some(id)) if status == PacketStatus::Configuration => {
+                self.send_packet(Box::new(ClientBoundConfigurationKeepAlivePacket { id })).await;
+            }
+            Ok(Some(id)) => self.send_packet(Box::new(ClientBoundPlayKeepAlivePacket { id })).await,
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{}, closing connection", err);
+                self.disconnect(TextComponent::translate("disconnect.timeout", vec![])).await;
             }
-            self.write_u8(((val & ByteBuf::SEGMENT_BITS) | ByteBuf::CONTINUE_BIT) as u8);
-            val >>= 7;
         }
     }
 
-    pub fn write_string(&mut self, val: &str) -> &Self {
-        self.write_varint(val.len() as i32);
-        self.write_buf(val.as_bytes());
-        self
-    }
-
-    pub fn read_string(&mut self) -> String {
-        let len = self.read_varint();
-        let mut str = String::new();
-        for _ in 0..len {
-            str.push(self.read_u8() as char);
+    /// Sends the game time to players in Play, which clients use to follow the server TPS.
+    pub async fn send_time(&mut self) {
+        if self.phase.status() != PacketStatus::Play {
+            return;
         }
-        str
-    }
-
-    pub fn read_varint(&mut self) -> i32 {
-        let mut value = 0;
-        let mut position = 0;
-        let mut current_byte;
-
-        loop {
-            current_byte = self.read_i8();
-            value |= (current_byte as i32 & ByteBuf::SEGMENT_BITS) << position;
-
-            if (current_byte as i32 & ByteBuf::CONTINUE_BIT) == 0 {
-                break;
+        let game_time = self.clock.game_time();
+        self.send_packet(Box::new(ClientBoundPlaySetTimePacket {
+            world_age: game_time,
+            time_of_day: game_time,
+            time_of_day_increasing: true,
+        }))
+        .await;
+    }
+
+    /// Checks a keep-alive answer, updating the latency shown to other players.
+    pub async fn keep_alive_received(&mut self, id: i64) {
+        match self.keep_alive.receive(id, Instant::now()) {
+            Ok(_) => {
+                if let Some(latency) = self.keep_alive.latency() {
+                    self.handle.set_latency(latency);
+                }
             }
-            position += 7;
-            if position >= 32 {
-                panic!("HANNNN");
+            Err(err) => {
+                eprintln!("{}, closing connection", err);
+                self.disconnect(TextComponent::translate("disconnect.timeout", vec![])).await;
             }
         }
-        value
-    }
-
-    pub fn write_buf(&mut self, buf: &[u8]) -> &Self {
-        self.data.extend_from_slice(&buf);
-        self
-    }
-
-    pub fn read_buf(&mut self) -> Vec<u8> {
-        let buf = self.data[self.read_offset..].to_vec();
-        self.read_offset = self.data.len();
-        buf
     }
 
-    pub fn write_uuid(&mut self, uuid: &Uuid) -> &Self {
-        // Encode the most significant 64 bits first, then the least significant 64 bits
-        self.write_i64(uuid.as_u128() as i64);
-        self.write_i64((uuid.as_u128() >> 64) as i64);
-        self
-    }
-
-    pub fn read_uuid(&mut self) -> Uuid {
-        let most_significant = self.read_i64() as u64;
-        let least_significant = self.read_i64() as u64;
-
-        Uuid::from_u128((most_significant as u128) | ((least_significant as u128) << 64))
-    }
-
-    pub fn write_bool(&mut self, val: bool) -> &Self {
-        self.write_u8(if val { 1 } else { 0 });
-        self
-    }
-
-    pub fn read_bool(&mut self) -> bool {
-        self.read_u8() == 1
-    }
-
-    pub fn write_property(&mut self, prop: &Property) -> &Self {
-        self.write_string(&prop.name);
-        self.write_string(&prop.value);
-        self.write_bool(prop.signature.is_some());
-        if let Some(signature) = &prop.signature {
-            self.write_string(signature);
-        }
-        self
-    }
-
-    pub fn read_property(&mut self) -> Property {
-        let name = self.read_string();
-        let value = self.read_string();
-        let signature = if self.read_bool() {
-            Some(self.read_string())
-        } else {
-            None
+    /// Shows `reason` to the player with the disconnect packet of the current
+    /// state, then closes the connection once everything queued is sent.
+    /// Handshake and Status have no such packet, the connection just closes.
+    pub async fn disconnect(&mut self, reason: impl Into<TextComponent>) {
+        let reason = reason.into();
+        let packet: Option<Box<dyn Packet>> = match self.phase.status() {
+            PacketStatus::Login => Some(Box::new(ClientBoundLoginDisconnectPacket::new(reason))),
+            PacketStatus::Configuration => Some(Box::new(ClientBoundConfigurationDisconnectPacket::new(reason))),
+            PacketStatus::Play => Some(Box::new(ClientBoundPlayDisconnectPacket::new(reason))),
+            _ => None,
         };
-        Property {
-            name,
-            value,
-            signature,
+        if let Some(packet) = packet {
+            self.send_packet(packet).await;
         }
+        self.handle.shutdown();
     }
 
-    pub fn write_properties(&mut self, props: &[Property]) -> &Self {
-        self.write_varint(props.len() as i32);
-        for prop in props {
-            self.write_property(prop);
+    /// Lists the player as online once in Play, disconnecting an older
+    /// connection of the same player, or this one if the host filled up
+    /// since login.
+    pub async fn join(&mut self) {
+        let ConnectionPhase::Play { identity, settings } = &self.phase else {
+            return;
+        };
+        let added = self.players.add(
+            identity,
+            settings.allow_server_listings,
+            self.host.name.clone(),
+            self.host.max_players,
+            self.handle.clone(),
+        );
+        let Ok(previous) = added else {
+            eprintln!("{} cannot join (server full), closing connection", identity.username);
+            self.disconnect(TextComponent::translate("multiplayer.disconnect.server_full", vec![])).await;
+            return;
+        };
+        println!("{} joined ({} online)", identity.username, self.players.len());
+        if let Some(previous) = previous {
+            println!("{} logged in from another location, closing previous connection", identity.username);
+            let reason = TextComponent::translate("multiplayer.disconnect.duplicate_login", vec![]);
+            let _ = previous.handle.try_send(&ClientBoundPlayDisconnectPacket::new(reason));
+            previous.handle.shutdown();
         }
-        self
     }
 
-    pub fn read_properties(&mut self) -> Vec<Property> {
-        let len = self.read_varint();
-        let mut props = Vec::with_capacity(len as usize);
-        for _ in 0..len {
-            props.push(self.read_property());
+    /// Removes the player from the online players, on disconnect.
+    pub fn leave(&self) {
+        if let ConnectionPhase::Play { identity, .. } = &self.phase
+            && self.players.remove(identity.uuid, self.id)
+        {
+            println!("{} left ({} online)", identity.username, self.players.len());
         }
-        props
-    }
-
-    pub fn write_nbt(&mut self, nbt: &Nbt) -> &Self {
-        let mut buf = Vec::new();
-        nbt.write(&mut buf);
-        self.write_buf(&buf);
-        self
-    }
-
-    pub fn cursor_at(&mut self, pos: usize) -> std::io::Cursor<&[u8]> {
-        std::io::Cursor::new(&self.data[pos..])
-    }
-
-    pub fn read_nbt(&mut self) -> Nbt {
-        let mut cursor = self.cursor_at(self.read_offset);
-        let nbt = simdnbt::owned::read(&mut cursor).expect("Failed to read nbt from cursor");
-        self.read_offset = cursor.position() as usize;
-        nbt
-    }
-
-    pub fn write_nbt_compound(&mut self, nbt: &NbtCompound) -> &Self {
-        let mut buf = Vec::new();
-        nbt.write(&mut buf);
-        self.write_buf(&buf);
-        self
-    }
-
-    pub fn read_nbt_compound(&mut self) -> NbtCompound {
-        let mut cursor = self.cursor_at(self.read_offset);
-        let nbt = simdnbt::owned::read_compound(&mut cursor).expect("Failed to read nbt compound from cursor");
-        self.read_offset = cursor.position() as usize;
-        nbt
     }
 
-    pub fn clear(&mut self) -> &mut Self {
-        self.data.clear();
-        self.read_offset = 0;
-        self
-    }
+    /// Checks the whitelist and player limit of the virtual host once the
+    /// identity is known, disconnecting players who cannot join.
+    pub async fn admit(&mut self, identity: &ClientIdentity) -> bool {
+        let reason = if !self.host.allows(&identity.username) {
+            "multiplayer.disconnect.not_whitelisted"
+        } else if self.players.count(self.host.name.as_deref()) as i64 >= self.host.max_players as i64 {
+            "multiplayer.disconnect.server_full"
+        } else {
+            return true;
+        };
+        eprintln!("{} cannot join ({}), closing connection", identity.username, reason);
+        self.disconnect(TextComponent::translate(reason, vec![])).await;
+        false
+    }
+
+    /// Ends the login sequence as `identity`, enabling compression first if configured.
+    pub async fn finish_login(&mut self, identity: ClientIdentity) -> Result<(), PhaseError> {
+        let packet = Box::new(ClientBoundLoginLoginSuccessPacket {
+            uuid: identity.uuid,
+            username: identity.username.clone(),
+            properties: identity.properties.clone(),
+            ..ClientBoundLoginLoginSuccessPacket::default()
+        });
+        self.phase.login_success(identity)?;
 
-    pub fn len(&self) -> usize {
-        self.data.len()
-    }
+        // Compression has to be enabled before Login Success
+        if let Some(threshold) = self.config.compression() {
+            let packet = Box::new(ClientBoundLoginSetCompressionPacket { threshold });
+            self.send_packet(packet).await;
+            self.set_compression(Some(threshold)).await;
+        }
 
-    pub fn remaining_len(&self) -> usize {
-        self.data.len() - self.read_offset
+        self.send_packet(packet).await;
+        Ok(())
     }
 
-    pub fn reset_read_offset(&mut self) {
-        self.read_offset = 0;
-    }
+    /// Identity known by the session server, replacing the one claimed in Login Start.
+    pub async fn authenticate(&self, username: &str, shared_secret: &[u8]) -> Result<ClientIdentity, auth::AuthError> {
+        let hash = crypto::server_hash("", shared_secret, &self.key_pair.public_key_der);
+        let profile = self.session_service.has_joined(username, &hash).await?;
 
-    pub fn read_all(&mut self) -> Vec<u8> {
-        let buf = self.data.clone();
-        self.clear();
-        buf
-    }
-}
-
-struct ClientIdentity {
-    uuid: Uuid,
-    username: String,
-}
-
-struct ClientConnection<'a> {
-    socket: tokio::sync::MutexGuard<'a, TcpStream>,
-    state: i32,
-    identity: Option<ClientIdentity>,
-}
-
-impl<'a> ClientConnection<'a> {
-    pub async fn send_packet(&mut self, packet: Box<dyn Packet>) {
-        // Encode packet
-        let mut content_buf = ByteBuf::new();
-        content_buf.write_varint(packet.id());
-        packet.encode(&mut content_buf);
-
-        // Len buffer
-        let mut p_id_buf = ByteBuf::new();
-        let len = content_buf.len();
-        p_id_buf.write_varint(len as i32);
-
-        // Final buffer
-        let mut buf = ByteBuf::new();
-        buf.write_varint(content_buf.len() as i32);
-
-        buf.write_buf(&content_buf.data);
-
-        // Write final buffer to socket
-        let data = buf.read_all();
-
-        {
-            self.socket.write_all(&data).await.unwrap();
-            self.socket.flush().await.unwrap();
-        }
+        println!("Authenticated {} ({})", profile.username, profile.uuid);
+        Ok(ClientIdentity {
+            uuid: profile.uuid,
+            username: profile.username,
+            properties: profile.properties,
+        })
     }
 }
 
@@ -427,31 +317,73 @@ fn generate_registry_nbt(name: String) -> RegistryEntry {
     }
 }
 
-pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut ByteBuf) {
-    let packet_type = buf.read_varint();
+async fn read_complete(connection: &mut ClientConnection, buf: &mut ByteBuf) -> Result<(), PacketError> {
+    let wire_type = buf.read_varint()?;
     println!("--------------------");
-    println!("Received packet type: {}", packet_type);
+    println!("Received packet type: {}", wire_type);
+
+    let status = connection.phase.status();
+
+    // Handlers work with the ids of the latest protocol version
+    let Some(packet_type) = connection.packets.latest_id(status, PacketBound::Server, wire_type) else {
+        if status == PacketStatus::Configuration || status == PacketStatus::Play {
+            eprintln!("Unknown {:?} packet type: {}", status, wire_type);
+            return Ok(());
+        }
+        return Err(DecodeError::UnknownPacketId(wire_type).into());
+    };
 
-    match PacketStatus::from_id(connection.state) {
-        Some(PacketStatus::Handshake) => {
+    match status {
+        PacketStatus::Handshake => {
             let packet: Box<ServerBoundHandshakePacket> =
-                PacketManager::HANDSHAKE.decode(packet_type, buf);
-            connection.state = packet.next_state;
+                connection.packets.decode(status, packet_type, buf)?;
+            let intent = connection.phase.handshake(packet.next_state)?;
+            connection.host = connection.hosts.resolve(&packet.server_address);
+            if let Some(name) = &connection.host.name {
+                println!("Virtual host {}", name);
+            }
+
+            match PacketManager::from_protocol(packet.protocol_version) {
+                Some(packets) => {
+                    connection.packets = packets;
+                    connection.handle.set_version(packets.version);
+                }
+                // Status still answers, advertising the latest version
+                None if intent == Intent::Status => {}
+                None => {
+                    eprintln!("Unsupported protocol version {}, closing connection", packet.protocol_version);
+                    let latest = ProtocolVersion::LATEST.name();
+                    let reason = if packet.protocol_version < ProtocolVersion::LATEST.protocol() {
+                        format!("Outdated client! Please use {}", latest)
+                    } else {
+                        format!("Outdated server! I'm still on {}", latest)
+                    };
+                    connection.disconnect(reason).await;
+                    return Ok(());
+                }
+            }
+
+            if intent == Intent::Transfer && !connection.config.accepts_transfers {
+                eprintln!("Transfers are disabled, closing connection");
+                connection.disconnect(TextComponent::translate("multiplayer.disconnect.transfers_disabled", vec![])).await;
+            }
         }
-        Some(PacketStatus::Status) => {
+        PacketStatus::Status => {
             let t = PacketManager::STATUS
                 .from_id(packet_type)
-                .expect(format!("Unknown status packet type: {}", packet_type).as_str());
+                .ok_or(DecodeError::UnknownPacketId(packet_type))?;
 
             match t {
                 EServerStatusPacket::ServerBoundStatusRequestPacket => {
                     let packet_to_send: Box<ClientBoundStatusResponsePacket> =
-                        Box::new(ClientBoundStatusResponsePacket::default());
+                        Box::new(ClientBoundStatusResponsePacket {
+                            response: connection.status_response(),
+                        });
                     connection.send_packet(packet_to_send).await;
                 }
                 EServerStatusPacket::ServerBoundStatusPingPacket => {
                     let packet: Box<ServerBoundStatusPingPacket> =
-                        PacketManager::STATUS.decode(packet_type, buf);
+                        connection.packets.decode(status, packet_type, buf)?;
                     let packet_to_send: Box<ClientBoundStatusPingResponsePacket> =
                         Box::new(ClientBoundStatusPingResponsePacket {
                             timestamp: packet.timestamp,
@@ -460,30 +392,89 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                 }
             }
         }
-        Some(PacketStatus::Login) => {
+        PacketStatus::Login => {
             let t = PacketManager::LOGIN
                 .from_id(packet_type)
-                .expect(format!("Unknown status packet type: {}", packet_type).as_str());
+                .ok_or(DecodeError::UnknownPacketId(packet_type))?;
 
             match t {
                 EServerLoginPacket::ServerBoundLoginStartPacket => {
                     let packet: Box<ServerBoundLoginStartPacket> =
-                        PacketManager::LOGIN.decode(packet_type, buf);
+                        connection.packets.decode(status, packet_type, buf)?;
                     println!("Login start from {} ({})", packet.uuid, packet.name);
 
-                    connection.identity = Some(ClientIdentity {
-                        uuid: packet.uuid,
+                    // The claimed UUID is never trusted, online mode replaces this identity
+                    // with the session server profile after encryption
+                    let identity = ClientIdentity {
+                        uuid: auth::offline_uuid(&packet.name),
                         username: packet.name.clone(),
-                    });
+                        properties: Vec::new(),
+                    };
+
+                    if !connection.config.encryption_enabled() {
+                        if connection.admit(&identity).await {
+                            connection.finish_login(identity).await?;
+                        }
+                        return Ok(());
+                    }
 
-                    let packet = Box::new(ClientBoundLoginLoginSuccessPacket::default());
+                    let verify_token = crypto::generate_verify_token();
+                    let packet = Box::new(ClientBoundLoginEncryptionRequestPacket {
+                        server_id: String::new(),
+                        public_key: connection.key_pair.public_key_der.clone(),
+                        verify_token: verify_token.clone(),
+                        should_authenticate: connection.config.online_mode,
+                    });
+                    connection.phase.request_encryption(identity, verify_token)?;
                     connection.send_packet(packet).await;
                 }
+                EServerLoginPacket::ServerBoundLoginEncryptionResponsePacket => {
+                    let packet: Box<ServerBoundLoginEncryptionResponsePacket> =
+                        connection.packets.decode(status, packet_type, buf)?;
+
+                    let (identity, expected_token) = connection.phase.encryption_response()?;
+                    let (identity, expected_token) = (identity.clone(), expected_token.to_vec());
+                    let verify_token = connection.key_pair.decrypt(&packet.verify_token);
+                    let shared_secret = connection.key_pair.decrypt(&packet.shared_secret);
+
+                    let shared_secret = match (verify_token, shared_secret) {
+                        (Ok(token), Ok(secret)) if expected_token == token => secret,
+                        _ => {
+                            eprintln!("Encryption response rejected, closing connection");
+                            connection.disconnect("Invalid encryption response").await;
+                            return Ok(());
+                        }
+                    };
+
+                    // Everything after the encryption response is encrypted
+                    if connection.enable_encryption(&shared_secret).await.is_err() {
+                        eprintln!("Invalid shared secret length, closing connection");
+                        connection.disconnect("Invalid encryption response").await;
+                        return Ok(());
+                    }
+
+                    let identity = if connection.config.online_mode {
+                        match connection.authenticate(&identity.username, &shared_secret).await {
+                            Ok(identity) => identity,
+                            Err(err) => {
+                                eprintln!("Authentication failed, closing connection: {}", err);
+                                connection.disconnect(TextComponent::translate("multiplayer.disconnect.unverified_username", vec![])).await;
+                                return Ok(());
+                            }
+                        }
+                    } else {
+                        identity
+                    };
+
+                    if connection.admit(&identity).await {
+                        connection.finish_login(identity).await?;
+                    }
+                }
                 EServerLoginPacket::ServerBoundLoginLoginAcknowledgedPacket => {
-                    connection.state = 3;
+                    connection.phase.login_acknowledged()?;
 
                     // Print the identity
-                    if let Some(identity) = &connection.identity {
+                    if let Some(identity) = connection.phase.identity() {
                         println!(
                             "Login acknowledged from: {} ({})",
                             identity.username, identity.uuid
@@ -491,7 +482,7 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                     }
 
                     // Send packs
-                    let p = Box::new(ClientBoundConfigurationClientBoundKnownPacks::default());
+                    let p = Box::new(ClientBoundConfigurationClientBoundKnownPacks::core(connection.packets.version));
                     connection.send_packet(p).await;
 
                     // Send registries
@@ -569,8 +560,8 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                                         ("asset_id".into(), NbtTag::String("minecraft:backyard".into())),
                                         ("height".into(), NbtTag::Int(1)),
                                         ("width".into(), NbtTag::Int(1)),
-                                        ("title".into(), NbtTag::String("{\"color\": \"gray\", \"translate\": \"painting.minecraft.skeleton.title\"}".into())),
-                                        ("author".into(), NbtTag::String("{\"color\": \"gray\", \"translate\": \"painting.minecraft.skeleton.title\"}".into())),
+                                        ("title".into(), TextComponent::translate("painting.minecraft.backyard.title", vec![]).color(NamedColor::Yellow).to_nbt()),
+                                        ("author".into(), TextComponent::translate("painting.minecraft.backyard.author", vec![]).color(NamedColor::Gray).to_nbt()),
                                     ])
                                 )))
                             }
@@ -628,6 +619,7 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                     connection.send_packet(p).await;
 
                     // Finish config
+                    connection.phase.finish_configuration()?;
                     let p = Box::new(ClientBoundConfigurationFinishConfigurationPacket::default());
                     connection.send_packet(p).await;
                 }
@@ -636,26 +628,41 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                 }
             }
         }
-        Some(PacketStatus::Configuration) => {
+        PacketStatus::Configuration => {
             let t = PacketManager::CONFIGURATION
                 .from_id(packet_type);
 
             if (t.is_none()) {
                 eprintln!("Unknown configuration packet type: {}", packet_type);
                 // Finish config
-                return;
+                return Ok(());
             }
 
             let t = t.unwrap();
 
             match t {
+                EServerConfigurationPacket::ServerBoundConfigurationKeepAlivePacket => {
+                    let packet: Box<ServerBoundConfigurationKeepAlivePacket> =
+                        connection.packets.decode(status, packet_type, buf)?;
+                    connection.keep_alive_received(packet.id).await;
+                }
+                EServerConfigurationPacket::ServerBoundConfigurationClientInformationPacket => {
+                    let packet: Box<ServerBoundConfigurationClientInformationPacket> =
+                        connection.packets.decode(status, packet_type, buf)?;
+                    connection.phase.client_settings(ClientSettings::from(packet.as_ref()))?;
+                }
                 EServerConfigurationPacket::ServerBoundConfigurationAcknowledgeFinishConfiguration => {
                     println!("Acknowledge finish configuration");
+                    connection.phase.configuration_acknowledged()?;
 
-                    let p = Box::new(ClientBoundPlayLoginPacket::default());
+                    let p = Box::new(ClientBoundPlayLoginPacket {
+                        dimension_names: vec![connection.host.world.clone()],
+                        max_players: connection.host.max_players,
+                        dimension_name: connection.host.world.clone(),
+                        ..ClientBoundPlayLoginPacket::default()
+                    });
                     connection.send_packet(p).await;
-
-                    connection.state = 4;
+                    connection.join().await;
 
                     // Send play packets
                     // Send chunk center
@@ -693,271 +700,229 @@ pub async fn read_complete<'a>(connection: &mut ClientConnection<'a>, buf: &mut
                 }
             }
         }
-        Some(PacketStatus::Play) => {
-            if packet_type == 11 {
-                let p = Box::new(ClientBoundPlayKeepAlivePacket::default());
-                connection.send_packet(p).await;
-                return;
+        PacketStatus::Play => {
+            if packet_type == ids::play::serverbound::KEEP_ALIVE {
+                let packet: Box<ServerBoundPlayKeepAlivePacket> =
+                    connection.packets.decode(status, packet_type, buf)?;
+                connection.keep_alive_received(packet.id).await;
+                return Ok(());
             }
-            if packet_type == 0x1A {
-                return;
+            if packet_type == ids::play::serverbound::PING_REQUEST {
+                let packet: Box<ServerBoundPlayPingRequestPacket> =
+                    connection.packets.decode(status, packet_type, buf)?;
+                connection.send_packet(Box::new(ClientBoundPlayPongResponsePacket { id: packet.id })).await;
+                return Ok(());
             }
             eprintln!("Play packet type: {}", packet_type);
         }
-        _ => {
-            eprintln!("Unknown packet status: {}", packet_type);
-        }
     }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    let listener = TcpListener::bind("0.0.0.0:25565").await.unwrap();
+    let config = Arc::new(ServerConfig::load("voidpoc.json"));
+    let key_pair = Arc::new(ServerKeyPair::generate());
+    let session_service = Arc::new(SessionService::new(&config.session_server));
+    let listener = TcpListener::bind(&config.address).await.unwrap();
+    let capture = match &config.capture {
+        Some(path) => Some(Capture::create(path).await.expect("Failed to create capture file")),
+        None => None,
+    };
+    let next_connection_id = AtomicU64::new(0);
+    let players = PlayerRegistry::new();
+    let hosts = Arc::new(VirtualHosts::new(&config));
+    for favicon in hosts.favicons() {
+        match favicon.load().await {
+            Ok(true) => println!("Loaded server icon {}", favicon.path().display()),
+            Ok(false) => {}
+            Err(err) => eprintln!("Invalid server icon {}, the server list shows none: {}", favicon.path().display(), err),
+        }
+        tokio::spawn(favicon.clone().reload_on_change());
+    }
+    let shutdown = Shutdown::new();
+    tokio::spawn(trigger_on_signal(shutdown.clone(), config.shutdown_message.clone()));
+    let clock = GameClock::new();
+    tokio::spawn(clock.clone().run(shutdown.clone()));
 
-    loop {
-        let (socket, _) = listener.accept().await.unwrap();
-        let socket = Arc::new(Mutex::new(socket));
+    let mut save_hooks = SaveHooks::new();
+    if let Some(capture) = capture.clone() {
+        save_hooks.register("capture", move || async move { capture.flush().await.map_err(|err| err.to_string()) });
+    }
+    let mut connections = JoinSet::new();
 
-        tokio::spawn(async move {
-            let socket = socket.lock().await;
+    loop {
+        let (socket, address) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    eprintln!("Failed to accept connection: {}", err);
+                    continue;
+                }
+            },
+            _ = shutdown.triggered() => break,
+        };
+        // Forget the connections that already ended
+        while connections.try_join_next().is_some() {}
+
+        let config = config.clone();
+        let key_pair = key_pair.clone();
+        let session_service = session_service.clone();
+        let capture = capture.clone();
+        let shutdown = shutdown.clone();
+        let players = players.clone();
+        let clock = clock.clone();
+        let hosts = hosts.clone();
+        let id = next_connection_id.fetch_add(1, Ordering::Relaxed);
+
+        connections.spawn(async move {
+            // Packets are written by the handle's task, other subsystems can queue through clones
+            let (read_half, write_half) = socket.into_split();
+            let handle = PlayerHandle::spawn(id, write_half, capture.clone());
 
             let mut connection = ClientConnection {
-                socket,
-                state: 0,
-                identity: None,
+                id,
+                socket: ClientSocket::new(read_half),
+                handle,
+                phase: ConnectionPhase::Handshake,
+                config,
+                key_pair,
+                session_service,
+                decoder: FrameDecoder::new(),
+                packets: PacketManager::default(),
+                keep_alive: KeepAlive::new(Instant::now()),
+                capture,
+                players,
+                clock,
+                host: hosts.default_host(),
+                hosts,
             };
+            let mut keep_alive_checks = tokio::time::interval(KEEP_ALIVE_CHECK);
+            let mut time_updates = tokio::time::interval(TIME_UPDATE_INTERVAL);
+            let mut first_read = true;
 
             //println!("Accepted connection from: {}", addr);
 
-            let mut buf = ByteBuf::new();
-            let mut packet_len: i32 = 0;
-
             // Read data from the socket
             loop {
                 let mut read_buf = vec![0; 4096];
-                let read_size = connection.socket.read(&mut read_buf).await.unwrap();
+                let read_size = tokio::select! {
+                    read_size = connection.socket.read(&mut read_buf) => match read_size {
+                        Ok(read_size) => read_size,
+                        Err(err) => {
+                            println!("Connection lost: {}: {}", address, err);
+                            break;
+                        }
+                    },
+                    _ = keep_alive_checks.tick() => {
+                        connection.keep_alive().await;
+                        continue;
+                    }
+                    _ = time_updates.tick() => {
+                        connection.send_time().await;
+                        continue;
+                    }
+                    // Closed by a handle, on queue overflow or an invalid packet
+                    _ = connection.handle.closed() => break,
+                    reason = shutdown.triggered() => {
+                        connection.disconnect(TextComponent::from_legacy(&reason)).await;
+                        break;
+                    }
+                };
 
                 if read_size == 0 {
-                    println!(
-                        "Connection closed: {}",
-                        connection.socket.peer_addr().unwrap()
-                    );
+                    println!("Connection closed: {}", address);
                     break;
                 }
 
-                buf.write_buf(&read_buf);
-
-                if packet_len == 0 && buf.len() > 0 {
-                    packet_len = buf.read_varint();
-                    //println!("Packet len: {}", packet_len);
+                // Pre-1.7 server list ping, the only thing such clients send
+                if std::mem::take(&mut first_read) && let Some(ping) = LegacyPing::detect(&read_buf[..read_size]) {
+                    println!("Legacy ping from {}", address);
+                    let response = ping.response(&connection.status_response());
+                    let _ = connection.handle.send_raw(&response).await;
+                    break;
                 }
 
-                // Wait for buf len == packet_len
-                if packet_len != 0 && buf.len() >= packet_len as usize {
-                    //println!("Packet read complete of len {}", packet_len);
-                    read_complete(&mut connection, &mut buf).await;
-                    buf.clear();
-                    packet_len = 0;
+                connection.decoder.feed(&read_buf[..read_size]);
+
+                // Handle every complete frame, leftover bytes stay in the decoder
+                loop {
+                    match connection.decoder.next_frame() {
+                        Ok(Some(mut frame)) => {
+                            if let Some(capture) = &connection.capture {
+                                capture.record(connection.id, connection.packets.version, connection.phase.status(), PacketBound::Server, frame.as_slice());
+                            }
+                            if let Err(err) = read_complete(&mut connection, &mut frame).await {
+                                eprintln!("Invalid packet, closing connection: {}", err);
+                                connection.disconnect(format!("Invalid packet: {}", err)).await;
+                                break;
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            eprintln!("Invalid frame, closing connection: {}", err);
+                            connection.disconnect(format!("Invalid frame: {}", err)).await;
+                            break;
+                        }
+                    }
+                }
+                if connection.handle.is_closed() {
+                    break;
                 }
             }
+            connection.leave();
+            // Stops the writer task once it sent what is queued, even with handles left elsewhere
+            connection.handle.shutdown();
+            // Lets a server shutting down wait for the disconnect packets
+            connection.handle.closed().await;
         });
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{ByteBuf, Property};
-
-    #[test]
-    pub fn test_buf_write_varint() {
-        let mut buf = ByteBuf::new();
-        buf.write_varint(156);
-
-        assert_eq!(buf.read_varint(), 156);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_varint_multiple() {
-        let mut buf = ByteBuf::new();
-        buf.write_varint(2611);
-        buf.write_varint(2611);
-
-        assert_eq!(buf.read_varint(), 2611);
-        assert_eq!(buf.read_varint(), 2611);
-        assert_eq!(buf.remaining_len(), 0);
+    let reason = shutdown.triggered().await;
+    println!("Shutting down ({}), disconnecting {} connections", reason, connections.len());
+    drop(listener);
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        eprintln!("{} connections did not drain in time, dropping them", connections.len());
     }
 
-    #[test]
-    pub fn test_buf_write_string() {
-        let mut buf = ByteBuf::new();
-        buf.write_string("abcde");
-
-        let str = String::from("abcde");
-        let buf_str = buf.read_string();
-
-        assert_eq!(str, buf_str);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_clear() {
-        let mut buf = ByteBuf::new();
-
-        buf.write_u8(0u8);
-        assert_eq!(buf.len(), 1);
-        buf.clear();
-        assert_eq!(buf.len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_uuid() {
-        use uuid::Uuid;
-
-        let mut buf = ByteBuf::new();
-        let uuid = Uuid::new_v4();
-        buf.write_uuid(&uuid);
-
-        let read_uuid = buf.read_uuid();
-        assert_eq!(uuid, read_uuid);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_property_no_signature() {
-        let mut buf = ByteBuf::new();
-        let property = Property {
-            name: "name".to_string(),
-            value: "value".to_string(),
-            signature: None,
-        };
-
-        buf.write_property(&property);
-
-        let read_property = buf.read_property();
-        assert_eq!(property, read_property);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_property_with_signature() {
-        let mut buf = ByteBuf::new();
-        let property = Property {
-            name: "name".to_string(),
-            value: "value".to_string(),
-            signature: Some("signature".to_string()),
-        };
-
-        buf.write_property(&property);
-
-        let read_property = buf.read_property();
-        assert_eq!(property, read_property);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_properties_no_signature() {
-        let mut buf = ByteBuf::new();
-        let properties = vec![
-            Property {
-                name: "name".to_string(),
-                value: "value".to_string(),
-                signature: None,
-            },
-            Property {
-                name: "name2".to_string(),
-                value: "value2".to_string(),
-                signature: None,
-            },
-        ];
-
-        buf.write_properties(&properties);
-
-        let read_properties = buf.read_properties();
-        assert_eq!(properties, read_properties);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_properties_with_signature() {
-        let mut buf = ByteBuf::new();
-        let properties = vec![
-            Property {
-                name: "name".to_string(),
-                value: "value".to_string(),
-                signature: Some("signature".to_string()),
-            },
-            Property {
-                name: "name2".to_string(),
-                value: "value2".to_string(),
-                signature: Some("signature2".to_string()),
-            },
-        ];
-
-        buf.write_properties(&properties);
-
-        let read_properties = buf.read_properties();
-        assert_eq!(properties, read_properties);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_properties_mixed() {
-        let mut buf = ByteBuf::new();
-        let properties = vec![
-            Property {
-                name: "name".to_string(),
-                value: "value".to_string(),
-                signature: None,
-            },
-            Property {
-                name: "name2".to_string(),
-                value: "value2".to_string(),
-                signature: Some("signature2".to_string()),
-            },
-        ];
-
-        buf.write_properties(&properties);
-
-        let read_properties = buf.read_properties();
-        assert_eq!(properties, read_properties);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_f32() {
-        let mut buf = ByteBuf::new();
-        buf.write_f32(3.14);
-
-        assert_eq!(buf.read_f32(), 3.14);
-        assert_eq!(buf.remaining_len(), 0);
-    }
-
-    #[test]
-    pub fn test_buf_write_nbt_compound() {
-        use simdnbt::owned::{BaseNbt, NbtCompound, NbtTag};
-
-        let mut buf = ByteBuf::new();
-        let nbt = NbtCompound::from_values(vec![
-            ("key".into(), NbtTag::String("value".into())),
-        ]);
-        buf.write_nbt_compound(&nbt);
-
-        let read_nbt = buf.read_nbt_compound();
-        assert_eq!(nbt, read_nbt);
-        assert_eq!(buf.remaining_len(), 0);
+    for (name, result) in save_hooks.run(SAVE_TIMEOUT).await {
+        match result {
+            Ok(()) => println!("Saved {}", name),
+            Err(err) => eprintln!("Failed to save {}: {}", name, err),
+        }
     }
+    println!("Server stopped");
+}
 
-    #[test]
-    pub fn test_buf_pop_front_i8() {
-        let mut buf = ByteBuf::new();
-        buf.write_u8(0x01);
-        buf.write_u8(0x02);
-        buf.write_u8(0x03);
-
-        assert_eq!(buf.pop_front_i8(), 0x01);
-        assert_eq!(buf.pop_front_i8(), 0x02);
-        assert_eq!(buf.pop_front_i8(), 0x03);
-        assert_eq!(buf.len(), 0);
+/// Starts the shutdown on Ctrl-C or SIGTERM, as sent by orchestrators.
+async fn trigger_on_signal(shutdown: Shutdown, reason: String) {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                eprintln!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if let Err(err) = result {
+                eprintln!("Failed to listen for Ctrl-C: {}", err);
+                return;
+            }
+        }
+        _ = terminate => {}
     }
+    shutdown.trigger(&reason);
 }
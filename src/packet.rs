@@ -1,11 +1,18 @@
 use crate::handshake::ServerHandshakePacketFactory;
-use crate::login::ServerLoginPacketFactory;
-use crate::status::ServerStatusPacketFactory;
-use crate::{ByteBuf, PacketType};
+use crate::login::{ClientLoginPacketFactory, ServerLoginPacketFactory};
+use crate::play::{ClientPlayPacketFactory, ServerPlayPacketFactory};
+use crate::protocol::ProtocolVersion;
+use crate::status::{ClientStatusPacketFactory, ServerStatusPacketFactory};
+use crate::{ByteBuf, DecodeError, PacketType};
 use std::any::Any;
-use crate::configuration::ServerConfigurationPacketFactory;
+use std::fmt::Debug;
+use crate::configuration::{ClientConfigurationPacketFactory, ServerConfigurationPacketFactory};
+
+pub use voidpoc_derive::Packet;
+
 // Types
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum PacketStatus {
     Handshake = 0,
     Status = 1,
@@ -27,39 +34,140 @@ impl PacketStatus {
     }
 }
 
+/// Side receiving a packet.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PacketBound {
+    Client,
+    Server,
+}
+
+/// Factory of the packets of a state and direction, by id in the latest
+/// protocol version.
 pub trait PacketFactory<E> {
-    fn decode<T: Packet>(&self, id: i32, buf: &mut ByteBuf) -> Box<T>;
+    /// Packet with default values, to decode into.
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>>;
     fn from_id(&self, id: i32) -> Option<E>;
-}
 
-pub struct PacketManager {}
+    fn decode<T: Packet>(&self, id: i32, buf: &mut ByteBuf) -> Result<Box<T>, DecodeError> {
+        self.decode_for(id, buf, ProtocolVersion::LATEST)
+    }
 
+    /// Fails with `UnknownPacketId` when `id` is not a `T` packet.
+    fn decode_for<T: Packet>(&self, id: i32, buf: &mut ByteBuf, version: ProtocolVersion) -> Result<Box<T>, DecodeError> {
+        let packet: Box<dyn Any> = self.decode_dyn(id, buf, version)?;
+        packet.downcast::<T>().map_err(|_| DecodeError::UnknownPacketId(id))
+    }
+
+    /// Decodes a packet whose type is only known at runtime.
+    fn decode_dyn(&self, id: i32, buf: &mut ByteBuf, version: ProtocolVersion) -> Result<Box<dyn Packet>, DecodeError> {
+        let mut packet = self.create(id).ok_or(DecodeError::UnknownPacketId(id))?;
+        packet.decode_for(buf, version)?;
+        Ok(packet)
+    }
+}
+
+/// Protocol profile of a connection, translating packet ids and layouts
+/// between its version and the latest one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PacketManager {
+    pub version: ProtocolVersion,
+}
 
 impl PacketManager {
     pub const HANDSHAKE: ServerHandshakePacketFactory = ServerHandshakePacketFactory {};
     pub const STATUS: ServerStatusPacketFactory = ServerStatusPacketFactory {};
     pub const LOGIN: ServerLoginPacketFactory = ServerLoginPacketFactory {};
     pub const CONFIGURATION: ServerConfigurationPacketFactory = ServerConfigurationPacketFactory {};
+    pub const PLAY: ServerPlayPacketFactory = ServerPlayPacketFactory {};
+
+    pub const CLIENT_STATUS: ClientStatusPacketFactory = ClientStatusPacketFactory {};
+    pub const CLIENT_LOGIN: ClientLoginPacketFactory = ClientLoginPacketFactory {};
+    pub const CLIENT_CONFIGURATION: ClientConfigurationPacketFactory = ClientConfigurationPacketFactory {};
+    pub const CLIENT_PLAY: ClientPlayPacketFactory = ClientPlayPacketFactory {};
+
+    pub fn new(version: ProtocolVersion) -> PacketManager {
+        PacketManager { version }
+    }
+
+    /// Profile for the `protocol_version` of a handshake, `None` when unsupported.
+    pub fn from_protocol(protocol_version: i32) -> Option<PacketManager> {
+        ProtocolVersion::from_protocol(protocol_version).map(PacketManager::new)
+    }
 
-    pub fn decode<T: Packet>(status: PacketStatus, id: i32, buf: &mut ByteBuf) -> Box<T> {
+    /// Latest id of a packet received with the id of this version.
+    pub fn latest_id(&self, status: PacketStatus, bound: PacketBound, id: PacketType) -> Option<PacketType> {
+        self.version.latest_id(status, bound, id)
+    }
+
+    /// Id of a packet in this version, `None` when it does not exist there.
+    pub fn packet_id(&self, packet: &dyn Packet) -> Option<PacketType> {
+        self.version.packet_id(packet.state(), packet.bound(), packet.name())
+    }
+
+    /// Decodes a serverbound packet from its latest id, with the layout of this version.
+    pub fn decode<T: Packet>(&self, status: PacketStatus, id: PacketType, buf: &mut ByteBuf) -> Result<Box<T>, DecodeError> {
         match status {
-            PacketStatus::Handshake => Self::HANDSHAKE.decode(id, buf),
-            PacketStatus::Status => Self::STATUS.decode(id, buf),
-            PacketStatus::Login => Self::LOGIN.decode(id, buf),
-            PacketStatus::Configuration => Self::CONFIGURATION.decode(id, buf),
-            _ => panic!("Unknown packet status"),
+            PacketStatus::Handshake => Self::HANDSHAKE.decode_for(id, buf, self.version),
+            PacketStatus::Status => Self::STATUS.decode_for(id, buf, self.version),
+            PacketStatus::Login => Self::LOGIN.decode_for(id, buf, self.version),
+            PacketStatus::Configuration => Self::CONFIGURATION.decode_for(id, buf, self.version),
+            PacketStatus::Play => Self::PLAY.decode_for(id, buf, self.version),
         }
     }
+
+    /// Decodes a packet of either direction from its latest id, whatever its type.
+    pub fn decode_dyn(&self, status: PacketStatus, bound: PacketBound, id: PacketType, buf: &mut ByteBuf) -> Result<Box<dyn Packet>, DecodeError> {
+        match (status, bound) {
+            (PacketStatus::Handshake, PacketBound::Server) => Self::HANDSHAKE.decode_dyn(id, buf, self.version),
+            (PacketStatus::Status, PacketBound::Server) => Self::STATUS.decode_dyn(id, buf, self.version),
+            (PacketStatus::Login, PacketBound::Server) => Self::LOGIN.decode_dyn(id, buf, self.version),
+            (PacketStatus::Configuration, PacketBound::Server) => Self::CONFIGURATION.decode_dyn(id, buf, self.version),
+            (PacketStatus::Play, PacketBound::Server) => Self::PLAY.decode_dyn(id, buf, self.version),
+            (PacketStatus::Status, PacketBound::Client) => Self::CLIENT_STATUS.decode_dyn(id, buf, self.version),
+            (PacketStatus::Login, PacketBound::Client) => Self::CLIENT_LOGIN.decode_dyn(id, buf, self.version),
+            (PacketStatus::Configuration, PacketBound::Client) => Self::CLIENT_CONFIGURATION.decode_dyn(id, buf, self.version),
+            (PacketStatus::Play, PacketBound::Client) => Self::CLIENT_PLAY.decode_dyn(id, buf, self.version),
+            (PacketStatus::Handshake, PacketBound::Client) => Err(DecodeError::UnknownPacketId(id)),
+        }
+    }
+
+    /// Writes the id and content of a packet for this version, `false` when
+    /// the packet does not exist there.
+    pub fn encode(&self, packet: &dyn Packet, buf: &mut ByteBuf) -> bool {
+        let Some(id) = self.packet_id(packet) else {
+            return false;
+        };
+        buf.write_varint(id);
+        packet.encode_for(buf, self.version);
+        true
+    }
 }
 
-pub trait Packet: Any + Send + 'static {
+/// Packet, with its id and layout in the latest protocol version.
+pub trait Packet: Any + Send + Debug + 'static {
     fn id(&self) -> PacketType;
+    /// Resource name, such as `minecraft:keep_alive`.
+    fn name(&self) -> &'static str;
+    fn state(&self) -> PacketStatus;
+    fn bound(&self) -> PacketBound;
     fn encode(&self, buf: &mut ByteBuf);
-    fn decode(&mut self, buf: &mut ByteBuf);
+    fn decode(&mut self, buf: &mut ByteBuf) -> Result<(), DecodeError>;
+
+    /// Encodes with the layout of an older version, for packets that changed.
+    fn encode_for(&self, buf: &mut ByteBuf, _version: ProtocolVersion) {
+        self.encode(buf);
+    }
+
+    /// Decodes with the layout of an older version, for packets that changed.
+    fn decode_for(&mut self, buf: &mut ByteBuf, _version: ProtocolVersion) -> Result<(), DecodeError> {
+        self.decode(buf)
+    }
 }
 
 // Keepalive 0x27
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:keep_alive", state = Play, bound = Client)]
 pub struct ClientBoundPlayKeepAlivePacket {
     pub id: i64,
 }
@@ -70,16 +178,44 @@ impl Default for ClientBoundPlayKeepAlivePacket {
     }
 }
 
-impl Packet for ClientBoundPlayKeepAlivePacket {
-    fn id(&self) -> PacketType {
-        0x27
+#[cfg(test)]
+mod tests {
+    use crate::buf::{ByteBuf, DecodeError};
+
+    #[test]
+    pub fn test_decode_unknown_packet_id() {
+        use crate::packet::{PacketFactory, PacketManager};
+        use crate::handshake::ServerBoundHandshakePacket;
+
+        let mut buf = ByteBuf::new();
+        let result = PacketManager::HANDSHAKE.decode::<ServerBoundHandshakePacket>(0x7F, &mut buf);
+        assert!(matches!(result, Err(DecodeError::UnknownPacketId(0x7F))));
     }
 
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_i64(self.id);
+    #[test]
+    pub fn test_decode_truncated_packet() {
+        use crate::packet::{PacketFactory, PacketManager};
+        use crate::handshake::ServerBoundHandshakePacket;
+
+        let mut buf = ByteBuf::new();
+        buf.write_varint(769);
+        buf.write_string("localhost");
+        let result = PacketManager::HANDSHAKE.decode::<ServerBoundHandshakePacket>(0x00, &mut buf);
+        assert!(matches!(result, Err(DecodeError::Underflow { .. })));
     }
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        self.id = buf.read_i64();
+    #[test]
+    pub fn test_decode_mismatched_packet_type() {
+        use crate::packet::{PacketFactory, PacketManager};
+        use crate::status::ServerBoundStatusPingPacket;
+
+        // A valid handshake, decoded as another packet type
+        let mut buf = ByteBuf::new();
+        buf.write_varint(769);
+        buf.write_string("localhost");
+        buf.write_u16(25565);
+        buf.write_varint(1);
+        let result = PacketManager::HANDSHAKE.decode::<ServerBoundStatusPingPacket>(0x00, &mut buf);
+        assert!(matches!(result, Err(DecodeError::UnknownPacketId(0x00))));
     }
 }
@@ -0,0 +1,367 @@
+use crate::Property;
+use crate::configuration::ServerBoundConfigurationClientInformationPacket;
+use crate::packet::PacketStatus;
+use std::fmt;
+use uuid::Uuid;
+
+// PHASES //
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PhaseError {
+    /// Handshake `next_state` that is not Status, Login nor Transfer
+    UnknownIntent(i32),
+    /// Packet that is not allowed at this point of the connection
+    Unexpected { state: PacketStatus, packet: &'static str },
+}
+
+impl fmt::Display for PhaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseError::UnknownIntent(intent) => write!(f, "unknown handshake intent {}", intent),
+            PhaseError::Unexpected { state, packet } => write!(f, "unexpected {} in {:?}", packet, state),
+        }
+    }
+}
+
+impl std::error::Error for PhaseError {}
+
+/// Player the connection logs in as, as claimed in Login Start or as known by
+/// the session server in online mode.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClientIdentity {
+    pub uuid: Uuid,
+    pub username: String,
+    pub properties: Vec<Property>,
+}
+
+/// Client options sent during Configuration.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClientSettings {
+    pub locale: String,
+    pub view_distance: i8,
+    /// 0 full, 1 commands only, 2 hidden
+    pub chat_mode: i32,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    /// 0 left, 1 right
+    pub main_hand: i32,
+    pub enable_text_filtering: bool,
+    pub allow_server_listings: bool,
+    /// 0 all, 1 decreased, 2 minimal
+    pub particle_status: i32,
+}
+
+/// Same as vanilla, for clients that never sent their settings.
+impl Default for ClientSettings {
+    fn default() -> Self {
+        ClientSettings {
+            locale: "en_us".to_string(),
+            view_distance: 2,
+            chat_mode: 0,
+            chat_colors: true,
+            displayed_skin_parts: 0,
+            main_hand: 1,
+            enable_text_filtering: false,
+            allow_server_listings: false,
+            particle_status: 0,
+        }
+    }
+}
+
+impl From<&ServerBoundConfigurationClientInformationPacket> for ClientSettings {
+    fn from(packet: &ServerBoundConfigurationClientInformationPacket) -> Self {
+        ClientSettings {
+            locale: packet.locale.clone(),
+            view_distance: packet.view_distance,
+            chat_mode: packet.chat_mode,
+            chat_colors: packet.chat_colors,
+            displayed_skin_parts: packet.displayed_skin_parts,
+            main_hand: packet.main_hand,
+            enable_text_filtering: packet.enable_text_filtering,
+            allow_server_listings: packet.allow_server_listings,
+            particle_status: packet.particle_status,
+        }
+    }
+}
+
+/// Why the client connected, the handshake `next_state`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Intent {
+    Status,
+    Login,
+    /// Login after being sent here by another server
+    Transfer,
+}
+
+impl Intent {
+    pub fn from_id(id: i32) -> Option<Intent> {
+        match id {
+            1 => Some(Intent::Status),
+            2 => Some(Intent::Login),
+            3 => Some(Intent::Transfer),
+            _ => None,
+        }
+    }
+}
+
+/// Progress of the login sequence.
+#[derive(Debug)]
+pub enum LoginStep {
+    /// Waiting for Login Start
+    Start,
+    /// Encryption Request sent, waiting for the response
+    Encryption { identity: ClientIdentity, verify_token: Vec<u8> },
+    /// Login Success sent, waiting for the acknowledgement
+    Acknowledgement { identity: ClientIdentity },
+}
+
+/// Phase of a connection, with the data known from that phase on.
+///
+/// Transitions are checked against the packets received so far, so a client
+/// cannot skip a phase.
+#[derive(Debug)]
+pub enum ConnectionPhase {
+    Handshake,
+    Status,
+    Login {
+        transfer: bool,
+        step: LoginStep,
+    },
+    Configuration {
+        identity: ClientIdentity,
+        settings: Option<ClientSettings>,
+        /// Finish Configuration sent, waiting for the acknowledgement
+        finished: bool,
+    },
+    Play {
+        identity: ClientIdentity,
+        settings: ClientSettings,
+    },
+}
+
+impl ConnectionPhase {
+    /// Protocol state used to decode the packets of this phase.
+    pub fn status(&self) -> PacketStatus {
+        match self {
+            ConnectionPhase::Handshake => PacketStatus::Handshake,
+            ConnectionPhase::Status => PacketStatus::Status,
+            ConnectionPhase::Login { .. } => PacketStatus::Login,
+            ConnectionPhase::Configuration { .. } => PacketStatus::Configuration,
+            ConnectionPhase::Play { .. } => PacketStatus::Play,
+        }
+    }
+
+    pub fn identity(&self) -> Option<&ClientIdentity> {
+        match self {
+            ConnectionPhase::Login {
+                step: LoginStep::Encryption { identity, .. } | LoginStep::Acknowledgement { identity },
+                ..
+            } => Some(identity),
+            ConnectionPhase::Configuration { identity, .. } | ConnectionPhase::Play { identity, .. } => Some(identity),
+            _ => None,
+        }
+    }
+
+    fn unexpected(&self, packet: &'static str) -> PhaseError {
+        PhaseError::Unexpected {
+            state: self.status(),
+            packet,
+        }
+    }
+
+    /// Handshake to Status or Login.
+    pub fn handshake(&mut self, next_state: i32) -> Result<Intent, PhaseError> {
+        if !matches!(self, ConnectionPhase::Handshake) {
+            return Err(self.unexpected("minecraft:intention"));
+        }
+        let intent = Intent::from_id(next_state).ok_or(PhaseError::UnknownIntent(next_state))?;
+        *self = match intent {
+            Intent::Status => ConnectionPhase::Status,
+            Intent::Login | Intent::Transfer => ConnectionPhase::Login {
+                transfer: intent == Intent::Transfer,
+                step: LoginStep::Start,
+            },
+        };
+        Ok(intent)
+    }
+
+    /// Login Start answered with an Encryption Request.
+    pub fn request_encryption(&mut self, identity: ClientIdentity, verify_token: Vec<u8>) -> Result<(), PhaseError> {
+        match self {
+            ConnectionPhase::Login { step: step @ LoginStep::Start, .. } => {
+                *step = LoginStep::Encryption { identity, verify_token };
+                Ok(())
+            }
+            _ => Err(self.unexpected("minecraft:hello")),
+        }
+    }
+
+    /// Identity and verify token of the pending Encryption Request.
+    pub fn encryption_response(&self) -> Result<(&ClientIdentity, &[u8]), PhaseError> {
+        match self {
+            ConnectionPhase::Login {
+                step: LoginStep::Encryption { identity, verify_token },
+                ..
+            } => Ok((identity, verify_token)),
+            _ => Err(self.unexpected("minecraft:key")),
+        }
+    }
+
+    /// Login Success sent, after Login Start or the Encryption Response.
+    pub fn login_success(&mut self, identity: ClientIdentity) -> Result<(), PhaseError> {
+        match self {
+            ConnectionPhase::Login {
+                step: step @ (LoginStep::Start | LoginStep::Encryption { .. }),
+                ..
+            } => {
+                *step = LoginStep::Acknowledgement { identity };
+                Ok(())
+            }
+            _ => Err(self.unexpected("minecraft:login_finished")),
+        }
+    }
+
+    /// Login to Configuration.
+    pub fn login_acknowledged(&mut self) -> Result<(), PhaseError> {
+        let phase = std::mem::replace(self, ConnectionPhase::Handshake);
+        match phase {
+            ConnectionPhase::Login {
+                step: LoginStep::Acknowledgement { identity },
+                ..
+            } => {
+                *self = ConnectionPhase::Configuration {
+                    identity,
+                    settings: None,
+                    finished: false,
+                };
+                Ok(())
+            }
+            phase => {
+                *self = phase;
+                Err(self.unexpected("minecraft:login_acknowledged"))
+            }
+        }
+    }
+
+    pub fn client_settings(&mut self, new_settings: ClientSettings) -> Result<(), PhaseError> {
+        match self {
+            ConnectionPhase::Configuration { settings, .. } => {
+                *settings = Some(new_settings);
+                Ok(())
+            }
+            ConnectionPhase::Play { settings, .. } => {
+                *settings = new_settings;
+                Ok(())
+            }
+            _ => Err(self.unexpected("minecraft:client_information")),
+        }
+    }
+
+    /// Finish Configuration sent.
+    pub fn finish_configuration(&mut self) -> Result<(), PhaseError> {
+        match self {
+            ConnectionPhase::Configuration { finished: finished @ false, .. } => {
+                *finished = true;
+                Ok(())
+            }
+            _ => Err(self.unexpected("minecraft:finish_configuration")),
+        }
+    }
+
+    /// Configuration to Play, once the client acknowledged Finish Configuration.
+    pub fn configuration_acknowledged(&mut self) -> Result<(), PhaseError> {
+        let phase = std::mem::replace(self, ConnectionPhase::Handshake);
+        match phase {
+            ConnectionPhase::Configuration {
+                identity,
+                settings,
+                finished: true,
+            } => {
+                *self = ConnectionPhase::Play {
+                    identity,
+                    settings: settings.unwrap_or_default(),
+                };
+                Ok(())
+            }
+            phase => {
+                *self = phase;
+                Err(self.unexpected("minecraft:finish_configuration"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::PacketStatus;
+    use crate::phase::{ClientIdentity, ClientSettings, ConnectionPhase, Intent, PhaseError};
+    use uuid::Uuid;
+
+    fn identity() -> ClientIdentity {
+        ClientIdentity {
+            uuid: Uuid::nil(),
+            username: "bot".to_string(),
+            properties: Vec::new(),
+        }
+    }
+
+    #[test]
+    pub fn test_phase_login_to_play() {
+        let mut phase = ConnectionPhase::Handshake;
+        assert_eq!(phase.handshake(2), Ok(Intent::Login));
+        assert_eq!(phase.status(), PacketStatus::Login);
+        assert!(phase.identity().is_none());
+
+        phase.request_encryption(identity(), vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(phase.encryption_response().unwrap().1, [1, 2, 3, 4]);
+        phase.login_success(identity()).unwrap();
+        assert!(phase.encryption_response().is_err());
+        phase.login_acknowledged().unwrap();
+        assert_eq!(phase.status(), PacketStatus::Configuration);
+
+        let settings = ClientSettings {
+            locale: "fr_fr".to_string(),
+            ..ClientSettings::default()
+        };
+        phase.client_settings(settings.clone()).unwrap();
+        phase.finish_configuration().unwrap();
+        phase.configuration_acknowledged().unwrap();
+        match &phase {
+            ConnectionPhase::Play { identity, settings: play_settings } => {
+                assert_eq!(identity.username, "bot");
+                assert_eq!(play_settings, &settings);
+            }
+            phase => panic!("unexpected phase {:?}", phase),
+        }
+    }
+
+    #[test]
+    pub fn test_phase_rejected_transitions() {
+        // Straight to Play
+        let mut phase = ConnectionPhase::Handshake;
+        assert_eq!(phase.handshake(4), Err(PhaseError::UnknownIntent(4)));
+        assert_eq!(phase.status(), PacketStatus::Handshake);
+
+        assert_eq!(phase.handshake(3), Ok(Intent::Transfer));
+        assert!(matches!(phase, ConnectionPhase::Login { transfer: true, .. }));
+        assert!(phase.handshake(2).is_err());
+
+        // Acknowledging a Login Success that was never sent
+        assert_eq!(
+            phase.login_acknowledged(),
+            Err(PhaseError::Unexpected {
+                state: PacketStatus::Login,
+                packet: "minecraft:login_acknowledged"
+            })
+        );
+        assert_eq!(phase.status(), PacketStatus::Login);
+        assert!(phase.encryption_response().is_err());
+
+        phase.login_success(identity()).unwrap();
+        phase.login_acknowledged().unwrap();
+        // Acknowledging a Finish Configuration that was never sent
+        assert!(phase.configuration_acknowledged().is_err());
+        assert_eq!(phase.status(), PacketStatus::Configuration);
+        assert_eq!(phase.identity().map(|identity| identity.username.as_str()), Some("bot"));
+    }
+}
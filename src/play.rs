@@ -1,32 +1,103 @@
-use crate::packet::{Packet, PacketFactory};
-use crate::{ByteBuf, PacketType};
+use crate::field::PacketField;
+use crate::ids;
+use crate::packet::{ClientBoundPlayKeepAlivePacket, Packet, PacketBound, PacketFactory, PacketStatus};
+use crate::protocol::ProtocolVersion;
+use crate::text::TextComponent;
+use crate::types::Position;
+use crate::{ByteBuf, DecodeError, PacketType};
 use serde::{Deserialize, Serialize};
-use std::any::Any;
 use std::fs::ReadDir;
 use bit_set::BitSet;
 use crab_nbt::nbt;
-use simdnbt::owned::{BaseNbt, Nbt, NbtCompound, NbtTag};
-// STATUS //
+use simdnbt::owned::{BaseNbt, Nbt, NbtCompound};
+// PLAY //
 
 #[derive(Eq, PartialEq)]
 pub enum EServerPlayPacket {
+    ServerBoundPlayConfirmTeleportationPacket,
+    ServerBoundPlayKeepAlivePacket,
+    ServerBoundPlayMovePlayerPosPacket,
+    ServerBoundPlayClientTickEndPacket,
+    ServerBoundPlayPingRequestPacket,
 }
 
-pub struct ServerConfigurationPacketFactory {}
+pub struct ServerPlayPacketFactory {}
 
-impl PacketFactory<EServerPlayPacket> for ServerConfigurationPacketFactory {
-    fn decode<T: Packet + 'static>(&self, id: i32, buf: &mut ByteBuf) -> Box<T> {
-        let packet: Box<dyn Any> = match id {
-            _ => panic!("Unknown packet id"),
-        };
-        let mut packet: Box<T> = packet.downcast::<T>().expect("Failed to downcast Packet");
-
-        packet.decode(buf);
-        packet
+impl PacketFactory<EServerPlayPacket> for ServerPlayPacketFactory {
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ServerBoundPlayConfirmTeleportationPacket::ID => Some(Box::new(ServerBoundPlayConfirmTeleportationPacket::default())),
+            ServerBoundPlayKeepAlivePacket::ID => Some(Box::new(ServerBoundPlayKeepAlivePacket::default())),
+            ServerBoundPlayMovePlayerPosPacket::ID => Some(Box::new(ServerBoundPlayMovePlayerPosPacket::default())),
+            ServerBoundPlayClientTickEndPacket::ID => Some(Box::new(ServerBoundPlayClientTickEndPacket::default())),
+            ServerBoundPlayPingRequestPacket::ID => Some(Box::new(ServerBoundPlayPingRequestPacket::default())),
+            _ => None,
+        }
     }
 
     fn from_id(&self, id: i32) -> Option<EServerPlayPacket> {
         match id {
+            ServerBoundPlayConfirmTeleportationPacket::ID => Some(EServerPlayPacket::ServerBoundPlayConfirmTeleportationPacket),
+            ServerBoundPlayKeepAlivePacket::ID => Some(EServerPlayPacket::ServerBoundPlayKeepAlivePacket),
+            ServerBoundPlayMovePlayerPosPacket::ID => Some(EServerPlayPacket::ServerBoundPlayMovePlayerPosPacket),
+            ServerBoundPlayClientTickEndPacket::ID => Some(EServerPlayPacket::ServerBoundPlayClientTickEndPacket),
+            ServerBoundPlayPingRequestPacket::ID => Some(EServerPlayPacket::ServerBoundPlayPingRequestPacket),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq)]
+pub enum EClientPlayPacket {
+    ClientBoundPlayLoginPacket,
+    ClientBoundPlaySetChunkCenterPacket,
+    ClientBoundPlayChunkDataPacket,
+    ClientBoundPlayGameEventPacket,
+    ClientBoundPlayPlayerPosition,
+    ClientBoundPlayKeepAlivePacket,
+    ClientBoundPlayPongResponsePacket,
+    ClientBoundPlayDisconnectPacket,
+    ClientBoundPlaySystemChatPacket,
+    ClientBoundPlaySetTitleTextPacket,
+    ClientBoundPlaySetSubtitleTextPacket,
+    ClientBoundPlaySetTimePacket,
+}
+
+pub struct ClientPlayPacketFactory {}
+
+impl PacketFactory<EClientPlayPacket> for ClientPlayPacketFactory {
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ClientBoundPlayLoginPacket::ID => Some(Box::new(ClientBoundPlayLoginPacket::default())),
+            ClientBoundPlaySetChunkCenterPacket::ID => Some(Box::new(ClientBoundPlaySetChunkCenterPacket::default())),
+            ClientBoundPlayChunkDataPacket::ID => Some(Box::new(ClientBoundPlayChunkDataPacket::default())),
+            ClientBoundPlayGameEventPacket::ID => Some(Box::new(ClientBoundPlayGameEventPacket::default())),
+            ClientBoundPlayPlayerPosition::ID => Some(Box::new(ClientBoundPlayPlayerPosition::default())),
+            ClientBoundPlayKeepAlivePacket::ID => Some(Box::new(ClientBoundPlayKeepAlivePacket::default())),
+            ClientBoundPlayPongResponsePacket::ID => Some(Box::new(ClientBoundPlayPongResponsePacket::default())),
+            ClientBoundPlayDisconnectPacket::ID => Some(Box::new(ClientBoundPlayDisconnectPacket::default())),
+            ClientBoundPlaySystemChatPacket::ID => Some(Box::new(ClientBoundPlaySystemChatPacket::default())),
+            ClientBoundPlaySetTitleTextPacket::ID => Some(Box::new(ClientBoundPlaySetTitleTextPacket::default())),
+            ClientBoundPlaySetSubtitleTextPacket::ID => Some(Box::new(ClientBoundPlaySetSubtitleTextPacket::default())),
+            ClientBoundPlaySetTimePacket::ID => Some(Box::new(ClientBoundPlaySetTimePacket::default())),
+            _ => None,
+        }
+    }
+
+    fn from_id(&self, id: i32) -> Option<EClientPlayPacket> {
+        match id {
+            ClientBoundPlayLoginPacket::ID => Some(EClientPlayPacket::ClientBoundPlayLoginPacket),
+            ClientBoundPlaySetChunkCenterPacket::ID => Some(EClientPlayPacket::ClientBoundPlaySetChunkCenterPacket),
+            ClientBoundPlayChunkDataPacket::ID => Some(EClientPlayPacket::ClientBoundPlayChunkDataPacket),
+            ClientBoundPlayGameEventPacket::ID => Some(EClientPlayPacket::ClientBoundPlayGameEventPacket),
+            ClientBoundPlayPlayerPosition::ID => Some(EClientPlayPacket::ClientBoundPlayPlayerPosition),
+            ClientBoundPlayKeepAlivePacket::ID => Some(EClientPlayPacket::ClientBoundPlayKeepAlivePacket),
+            ClientBoundPlayPongResponsePacket::ID => Some(EClientPlayPacket::ClientBoundPlayPongResponsePacket),
+            ClientBoundPlayDisconnectPacket::ID => Some(EClientPlayPacket::ClientBoundPlayDisconnectPacket),
+            ClientBoundPlaySystemChatPacket::ID => Some(EClientPlayPacket::ClientBoundPlaySystemChatPacket),
+            ClientBoundPlaySetTitleTextPacket::ID => Some(EClientPlayPacket::ClientBoundPlaySetTitleTextPacket),
+            ClientBoundPlaySetSubtitleTextPacket::ID => Some(EClientPlayPacket::ClientBoundPlaySetSubtitleTextPacket),
+            ClientBoundPlaySetTimePacket::ID => Some(EClientPlayPacket::ClientBoundPlaySetTimePacket),
             _ => None,
         }
     }
@@ -36,22 +107,76 @@ impl PacketFactory<EServerPlayPacket> for ServerConfigurationPacketFactory {
 
 // SERVERBOUND //
 
-// Acknowledge Finish Configuration 0x03
+// Confirm Teleportation
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:accept_teleportation", state = Play, bound = Server)]
+pub struct ServerBoundPlayConfirmTeleportationPacket {
+    #[packet(varint)]
+    pub teleport_id: i32,
+}
+
+// Serverbound Keep Alive
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:keep_alive", state = Play, bound = Server)]
+pub struct ServerBoundPlayKeepAlivePacket {
+    pub id: i64,
+}
+
+// Set Player Position
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:move_player_pos", state = Play, bound = Server)]
+pub struct ServerBoundPlayMovePlayerPosPacket {
+    pub x: f64,
+    pub feet_y: f64,
+    pub z: f64,
+    /// 0x01 on ground, 0x02 against a wall (1.21.2+), a single bool before
+    pub flags: u8,
+}
+
+// Client Tick End, sent after every client tick since 1.21.2
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:client_tick_end", state = Play, bound = Server)]
+pub struct ServerBoundPlayClientTickEndPacket {}
+
+// Ping Request, answered right away unlike keep-alives
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:ping_request", state = Play, bound = Server)]
+pub struct ServerBoundPlayPingRequestPacket {
+    pub id: i64,
+}
 
 // CLIENTBOUND //
 
 // Clientbound Login
 
+#[derive(PacketField, Debug)]
+pub struct DeathLocation {
+    pub dimension_name: String,
+    pub location: Position,
+}
+
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:login", state = Play, bound = Client)]
 pub struct ClientBoundPlayLoginPacket {
     pub entity_id: i32,
     pub hardcore: bool,
+    #[packet(length_prefixed)]
     pub dimension_names: Vec<String>,
+    #[packet(varint)]
     pub max_players: i32,
+    #[packet(varint)]
     pub view_distance: i32,
+    #[packet(varint)]
     pub simulation_distance: i32,
     pub reduced_debug_info: bool,
     pub respawn_screen: bool,
     pub limited_crafing: bool,
+    #[packet(varint)]
     pub dimension_type: i32,
     pub dimension_name: String,
     pub hashed_seed: i64,
@@ -59,10 +184,10 @@ pub struct ClientBoundPlayLoginPacket {
     pub previous_game_mode: i8,
     pub is_debug: bool,
     pub is_flat: bool,
-    pub has_death_location: bool,
-    pub death_dimension_name: Option<bool>,
-    pub death_location: Option<bool>,
+    pub death_location: Option<DeathLocation>,
+    #[packet(varint)]
     pub portal_cooldown: i32,
+    #[packet(varint, since = V1_21_3)]
     pub sea_level: i32,
     pub enforces_secure_chat: bool,
 }
@@ -86,8 +211,6 @@ impl Default for ClientBoundPlayLoginPacket {
             previous_game_mode: 0,
             is_debug: false,
             is_flat: false,
-            has_death_location: false,
-            death_dimension_name: None,
             death_location: None,
             portal_cooldown: 10,
             sea_level: 63,
@@ -96,47 +219,14 @@ impl Default for ClientBoundPlayLoginPacket {
     }
 }
 
-impl Packet for ClientBoundPlayLoginPacket {
-    fn id(&self) -> PacketType {
-        0x2C
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_i32(self.entity_id);
-        buf.write_bool(self.hardcore);
-        buf.write_varint(0);
-        /*for name in &self.dimension_names {
-            buf.write_string(name);
-        }*/
-        buf.write_varint(self.max_players);
-        buf.write_varint(self.view_distance);
-        buf.write_varint(self.simulation_distance);
-        buf.write_bool(self.reduced_debug_info);
-        buf.write_bool(self.respawn_screen);
-        buf.write_bool(self.limited_crafing);
-
-        buf.write_varint(self.dimension_type);
-        buf.write_string(&self.dimension_name);
-        buf.write_i64(self.hashed_seed);
-        buf.write_i8(0);
-        buf.write_i8(self.previous_game_mode);
-        buf.write_bool(self.is_debug);
-        buf.write_bool(self.is_flat);
-        buf.write_i8(0);
-        buf.write_varint(self.portal_cooldown);
-        buf.write_varint(self.sea_level);
-        buf.write_bool(self.enforces_secure_chat);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
-    }
-}
-
 // Set Chunk Center 0x58
 
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:set_chunk_cache_center", state = Play, bound = Client)]
 pub struct ClientBoundPlaySetChunkCenterPacket {
+    #[packet(varint)]
     pub chunk_x: i32,
+    #[packet(varint)]
     pub chunk_z: i32,
 }
 
@@ -149,21 +239,6 @@ impl Default for ClientBoundPlaySetChunkCenterPacket {
     }
 }
 
-impl Packet for ClientBoundPlaySetChunkCenterPacket {
-    fn id(&self) -> PacketType {
-        0x58
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_varint(self.chunk_x);
-        buf.write_varint(self.chunk_z);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
-    }
-}
-
 // Chunk Data
 
 pub struct ChunkData {
@@ -178,36 +253,36 @@ pub struct LightData {
     pub empty_block_light_mask: Vec<i8>,
 }
 
+#[derive(Debug, Default)]
 pub struct ClientBoundPlayChunkDataPacket {
     pub chunk_x: i32,
     pub chunk_z: i32,
 }
 
-fn encode_empty_bitset(buf: &mut ByteBuf) {
-    let mut bs = BitSet::new();
-    let mut longs = Vec::new();
-    for x in bs.iter() {
-        longs.push(x);
-    }
-    buf.write_varint(longs.len() as i32);
-    for long in longs {
-        buf.write_i64(long as i64);
-    }
+impl ClientBoundPlayChunkDataPacket {
+    pub const ID: PacketType = ids::play::clientbound::LEVEL_CHUNK_WITH_LIGHT;
 }
 
 impl Packet for ClientBoundPlayChunkDataPacket {
     fn id(&self) -> PacketType {
-        0x28
+        Self::ID
     }
 
-    fn encode(&self, buf: &mut ByteBuf) {
-        println!("Dump before buffer encode");
-        buf.dump();
+    fn name(&self) -> &'static str {
+        "minecraft:level_chunk_with_light"
+    }
+
+    fn state(&self) -> PacketStatus {
+        PacketStatus::Play
+    }
+
+    fn bound(&self) -> PacketBound {
+        PacketBound::Client
+    }
 
+    fn encode(&self, buf: &mut ByteBuf) {
         buf.write_i32(self.chunk_x);
         buf.write_i32(self.chunk_z);
-        println!("Dump after chunk x and z");
-        buf.dump();
         // Chunk data
         /*let nbt = nbt!("", {});
         {
@@ -222,52 +297,99 @@ impl Packet for ClientBoundPlayChunkDataPacket {
         //buf.write_u8('a' as u8);
         //buf.write_u16(0);
         buf.write_u8(0);
-        println!("Dump after nbt");
-        buf.dump();
         buf.write_varint(0);
         buf.write_varint(0);
-        //
-        println!("Dump after data");
-        buf.dump();
-        //
         // Light data
-        encode_empty_bitset(buf);
-        encode_empty_bitset(buf);
-        encode_empty_bitset(buf);
-        encode_empty_bitset(buf);
+        buf.write_bitset(&BitSet::new());
+        buf.write_bitset(&BitSet::new());
+        buf.write_bitset(&BitSet::new());
+        buf.write_bitset(&BitSet::new());
         buf.write_varint(0);
         buf.write_varint(0);
     }
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
+    fn decode(&mut self, buf: &mut ByteBuf) -> Result<(), DecodeError> {
+        // Only the chunk coordinates are kept
+        self.chunk_x = buf.read_i32()?;
+        self.chunk_z = buf.read_i32()?;
+        buf.read_buf();
+        Ok(())
     }
 }
 
 // Game Event 0x23
 
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:game_event", state = Play, bound = Client)]
 pub struct ClientBoundPlayGameEventPacket {
     pub event_id: i8,
     pub value: f32,
 }
 
-impl Packet for ClientBoundPlayGameEventPacket {
-    fn id(&self) -> PacketType {
-        0x23
-    }
+// Pong Response
 
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_i8(self.event_id);
-        buf.write_f32(self.value);
-    }
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:pong_response", state = Play, bound = Client)]
+pub struct ClientBoundPlayPongResponsePacket {
+    pub id: i64,
+}
+
+// Disconnect (play)
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:disconnect", state = Play, bound = Client)]
+pub struct ClientBoundPlayDisconnectPacket {
+    pub reason: TextComponent,
+}
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
+impl ClientBoundPlayDisconnectPacket {
+    pub fn new(reason: impl Into<TextComponent>) -> ClientBoundPlayDisconnectPacket {
+        ClientBoundPlayDisconnectPacket { reason: reason.into() }
     }
 }
 
+// System Chat Message
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:system_chat", state = Play, bound = Client)]
+pub struct ClientBoundPlaySystemChatPacket {
+    pub content: TextComponent,
+    /// Shown above the hotbar instead of in the chat
+    pub overlay: bool,
+}
+
+// Set Title Text
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:set_title_text", state = Play, bound = Client)]
+pub struct ClientBoundPlaySetTitleTextPacket {
+    pub text: TextComponent,
+}
+
+// Set Subtitle Text
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:set_subtitle_text", state = Play, bound = Client)]
+pub struct ClientBoundPlaySetSubtitleTextPacket {
+    pub text: TextComponent,
+}
+
+// Update Time
+
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:set_time", state = Play, bound = Client)]
+pub struct ClientBoundPlaySetTimePacket {
+    /// Ticks since the world was created
+    pub world_age: i64,
+    /// Negative to stop the daylight cycle, until 1.21.1
+    pub time_of_day: i64,
+    #[packet(since = V1_21_3)]
+    pub time_of_day_increasing: bool,
+}
+
 // Player Position 0x42
 
+#[derive(Debug)]
 pub struct ClientBoundPlayPlayerPosition {
     pub teleport_id: i32,
     pub x: f64,
@@ -278,7 +400,7 @@ pub struct ClientBoundPlayPlayerPosition {
     pub vel_z: f64,
     pub yaw: f32,
     pub pitch: f32,
-    pub flags: i16,
+    pub flags: i32,
 }
 
 impl Default for ClientBoundPlayPlayerPosition {
@@ -298,12 +420,51 @@ impl Default for ClientBoundPlayPlayerPosition {
     }
 }
 
+impl ClientBoundPlayPlayerPosition {
+    pub const ID: PacketType = ids::play::clientbound::PLAYER_POSITION;
+
+    /// Relative flags known before 1.21.2, for the position and rotation.
+    const LEGACY_FLAGS: i32 = 0x1F;
+}
+
 impl Packet for ClientBoundPlayPlayerPosition {
     fn id(&self) -> PacketType {
-        0x42
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "minecraft:player_position"
+    }
+
+    fn state(&self) -> PacketStatus {
+        PacketStatus::Play
+    }
+
+    fn bound(&self) -> PacketBound {
+        PacketBound::Client
     }
 
     fn encode(&self, buf: &mut ByteBuf) {
+        self.encode_for(buf, ProtocolVersion::LATEST);
+    }
+
+    fn decode(&mut self, buf: &mut ByteBuf) -> Result<(), DecodeError> {
+        self.decode_for(buf, ProtocolVersion::LATEST)
+    }
+
+    fn encode_for(&self, buf: &mut ByteBuf, version: ProtocolVersion) {
+        // 1.21.2 moved the teleport id first, added the velocity and widened the flags
+        if version <= ProtocolVersion::V1_21_1 {
+            buf.write_f64(self.x);
+            buf.write_f64(self.y);
+            buf.write_f64(self.z);
+            buf.write_f32(self.yaw);
+            buf.write_f32(self.pitch);
+            buf.write_i8((self.flags & Self::LEGACY_FLAGS) as i8);
+            buf.write_varint(self.teleport_id);
+            return;
+        }
+
         buf.write_varint(self.teleport_id);
         buf.write_f64(self.x);
         buf.write_f64(self.y);
@@ -313,10 +474,146 @@ impl Packet for ClientBoundPlayPlayerPosition {
         buf.write_f64(self.vel_z);
         buf.write_f32(self.yaw);
         buf.write_f32(self.pitch);
-        buf.write_i16(self.flags);
+        buf.write_i32(self.flags);
+    }
+
+    fn decode_for(&mut self, buf: &mut ByteBuf, version: ProtocolVersion) -> Result<(), DecodeError> {
+        if version <= ProtocolVersion::V1_21_1 {
+            self.x = buf.read_f64()?;
+            self.y = buf.read_f64()?;
+            self.z = buf.read_f64()?;
+            self.yaw = buf.read_f32()?;
+            self.pitch = buf.read_f32()?;
+            self.flags = buf.read_i8()? as i32 & Self::LEGACY_FLAGS;
+            self.teleport_id = buf.read_varint()?;
+            self.vel_x = 0.0;
+            self.vel_y = 0.0;
+            self.vel_z = 0.0;
+            return Ok(());
+        }
+
+        self.teleport_id = buf.read_varint()?;
+        self.x = buf.read_f64()?;
+        self.y = buf.read_f64()?;
+        self.z = buf.read_f64()?;
+        self.vel_x = buf.read_f64()?;
+        self.vel_y = buf.read_f64()?;
+        self.vel_z = buf.read_f64()?;
+        self.yaw = buf.read_f32()?;
+        self.pitch = buf.read_f32()?;
+        self.flags = buf.read_i32()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ByteBuf;
+    use crate::packet::Packet;
+    use crate::play::{ClientBoundPlayLoginPacket, ClientBoundPlayPlayerPosition, ClientBoundPlaySetTimePacket, DeathLocation};
+    use crate::protocol::ProtocolVersion;
+    use crate::types::Position;
+
+    #[test]
+    pub fn test_play_login_round_trip() {
+        let packet = ClientBoundPlayLoginPacket {
+            dimension_names: vec!["minecraft:overworld".to_string(), "minecraft:the_end".to_string()],
+            death_location: Some(DeathLocation {
+                dimension_name: "minecraft:the_end".to_string(),
+                location: Position::new(1, 2, 3),
+            }),
+            ..ClientBoundPlayLoginPacket::default()
+        };
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+
+        // Entity id and hardcore, then the dimension names
+        buf.skip(5).unwrap();
+        assert_eq!(buf.read_varint().unwrap(), 2);
+        assert_eq!(buf.read_string().unwrap(), "minecraft:overworld");
+        assert_eq!(buf.read_string().unwrap(), "minecraft:the_end");
+
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+        let mut decoded = ClientBoundPlayLoginPacket::default();
+        decoded.decode(&mut buf).unwrap();
+        assert_eq!(decoded.dimension_names, packet.dimension_names);
+        assert_eq!(decoded.death_location.unwrap().location, Position::new(1, 2, 3));
+        assert_eq!(decoded.sea_level, 63);
+        assert_eq!(buf.remaining_len(), 0);
     }
 
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        todo!()
+    #[test]
+    pub fn test_play_login_without_sea_level() {
+        let packet = ClientBoundPlayLoginPacket::default();
+        let mut latest = ByteBuf::new();
+        packet.encode(&mut latest);
+        let mut legacy = ByteBuf::new();
+        packet.encode_for(&mut legacy, ProtocolVersion::V1_21_1);
+
+        // Sea level is a single byte VarInt before the last boolean
+        assert_eq!(legacy.len(), latest.len() - 1);
+
+        let mut decoded = ClientBoundPlayLoginPacket {
+            sea_level: 0,
+            ..ClientBoundPlayLoginPacket::default()
+        };
+        decoded.decode_for(&mut legacy, ProtocolVersion::V1_21_1).unwrap();
+        assert_eq!(decoded.sea_level, 0);
+        assert_eq!(legacy.remaining_len(), 0);
+    }
+
+    #[test]
+    pub fn test_play_player_position_layouts() {
+        let packet = ClientBoundPlayPlayerPosition {
+            teleport_id: 7,
+            vel_y: 1.0,
+            flags: 0x21,
+            ..ClientBoundPlayPlayerPosition::default()
+        };
+
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+        assert_eq!(buf.read_varint().unwrap(), 7);
+        let mut decoded = ClientBoundPlayPlayerPosition::default();
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+        decoded.decode(&mut buf).unwrap();
+        assert_eq!((decoded.teleport_id, decoded.vel_y, decoded.flags), (7, 1.0, 0x21));
+
+        // Before 1.21.2: position, rotation, byte flags then the teleport id, without velocity
+        let mut buf = ByteBuf::new();
+        packet.encode_for(&mut buf, ProtocolVersion::V1_21_1);
+        assert_eq!(buf.len(), 3 * 8 + 2 * 4 + 1 + 1);
+        assert_eq!(buf.read_f64().unwrap(), 8.0);
+        buf.skip(2 * 8 + 2 * 4).unwrap();
+        assert_eq!(buf.read_i8().unwrap(), 0x01);
+        assert_eq!(buf.read_varint().unwrap(), 7);
+
+        let mut buf = ByteBuf::new();
+        packet.encode_for(&mut buf, ProtocolVersion::V1_21_1);
+        let mut decoded = ClientBoundPlayPlayerPosition::default();
+        decoded.decode_for(&mut buf, ProtocolVersion::V1_21_1).unwrap();
+        assert_eq!((decoded.teleport_id, decoded.vel_y, decoded.flags), (7, 0.0, 0x01));
+    }
+
+    #[test]
+    pub fn test_play_set_time_layouts() {
+        let packet = ClientBoundPlaySetTimePacket {
+            world_age: 1200,
+            time_of_day: 6000,
+            time_of_day_increasing: true,
+        };
+        let mut buf = ByteBuf::new();
+        packet.encode(&mut buf);
+        assert_eq!(buf.len(), 8 + 8 + 1);
+
+        // No daylight cycle flag before 1.21.2
+        let mut buf = ByteBuf::new();
+        packet.encode_for(&mut buf, ProtocolVersion::V1_21_1);
+        assert_eq!(buf.len(), 8 + 8);
+        let mut decoded = ClientBoundPlaySetTimePacket::default();
+        decoded.decode_for(&mut buf, ProtocolVersion::V1_21_1).unwrap();
+        assert_eq!((decoded.world_age, decoded.time_of_day, decoded.time_of_day_increasing), (1200, 6000, false));
     }
 }
@@ -0,0 +1,205 @@
+use crate::connection::PlayerHandle;
+use crate::phase::ClientIdentity;
+use crate::status::{ServerStatusResponsePlayers, ServerStatusResponsePlayersSample};
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// PLAYERS //
+
+// Players listed in the server list at most, picked at random like vanilla
+pub const STATUS_SAMPLE_LEN: usize = 12;
+
+// Shown instead of players who opted out of server listings
+const ANONYMOUS_PLAYER: &str = "Anonymous Player";
+
+/// What the server list shows of the online players.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusSample {
+    /// Names of online players
+    #[default]
+    Players,
+    /// Only the player count
+    Hidden,
+    /// These lines, whoever is online
+    Custom(Vec<String>),
+}
+
+/// Player that reached Play.
+#[derive(Clone)]
+pub struct OnlinePlayer {
+    pub uuid: Uuid,
+    pub username: String,
+    /// Opted in to server listings in its client settings
+    pub listed: bool,
+    /// Virtual host the player connected with, `None` for the default host
+    pub host: Option<String>,
+    pub handle: PlayerHandle,
+}
+
+/// Virtual host already has its maximum number of players.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ServerFull;
+
+/// Server-wide list of the players in Play, shared by every connection.
+#[derive(Clone, Default)]
+pub struct PlayerRegistry {
+    players: Arc<Mutex<HashMap<Uuid, OnlinePlayer>>>,
+}
+
+impl PlayerRegistry {
+    pub fn new() -> PlayerRegistry {
+        PlayerRegistry::default()
+    }
+
+    /// Adds a player unless its host has `max_players` online, returning the
+    /// one it replaces if that player was already online from another connection.
+    pub fn add(
+        &self,
+        identity: &ClientIdentity,
+        listed: bool,
+        host: Option<String>,
+        max_players: i32,
+        handle: PlayerHandle,
+    ) -> Result<Option<OnlinePlayer>, ServerFull> {
+        let mut players = self.players.lock().unwrap();
+        // A player replacing itself takes no extra slot
+        let online = players
+            .values()
+            .filter(|player| player.host == host && player.uuid != identity.uuid)
+            .count();
+        if online as i64 >= max_players as i64 {
+            return Err(ServerFull);
+        }
+        let player = OnlinePlayer {
+            uuid: identity.uuid,
+            username: identity.username.clone(),
+            listed,
+            host,
+            handle,
+        };
+        Ok(players.insert(identity.uuid, player))
+    }
+
+    /// Removes the player of connection `id`, unless it was replaced since,
+    /// returning whether it was online.
+    pub fn remove(&self, uuid: Uuid, id: u64) -> bool {
+        let mut players = self.players.lock().unwrap();
+        if players.get(&uuid).is_some_and(|player| player.handle.id() == id) {
+            players.remove(&uuid);
+            return true;
+        }
+        false
+    }
+
+    pub fn get(&self, uuid: Uuid) -> Option<OnlinePlayer> {
+        self.players.lock().unwrap().get(&uuid).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Players online through virtual host `host`.
+    pub fn count(&self, host: Option<&str>) -> usize {
+        self.players.lock().unwrap().values().filter(|player| player.host.as_deref() == host).count()
+    }
+
+    /// Players part of the status response of virtual host `host`.
+    pub fn status(&self, host: Option<&str>, max: i32, sample: &StatusSample) -> ServerStatusResponsePlayers {
+        let players = self.players.lock().unwrap();
+        let host_players = || players.values().filter(|player| player.host.as_deref() == host);
+        let sample = match sample {
+            StatusSample::Players => host_players()
+                .choose_multiple(&mut rand::thread_rng(), STATUS_SAMPLE_LEN)
+                .into_iter()
+                .map(|player| match player.listed {
+                    true => sample_line(&player.username, player.uuid),
+                    false => sample_line(ANONYMOUS_PLAYER, Uuid::nil()),
+                })
+                .collect(),
+            StatusSample::Hidden => Vec::new(),
+            StatusSample::Custom(lines) => lines.iter().map(|line| sample_line(line, Uuid::nil())).collect(),
+        };
+        ServerStatusResponsePlayers {
+            max,
+            online: host_players().count() as i32,
+            sample,
+        }
+    }
+}
+
+fn sample_line(name: &str, uuid: Uuid) -> ServerStatusResponsePlayersSample {
+    ServerStatusResponsePlayersSample {
+        name: name.to_string(),
+        id: uuid.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth;
+    use crate::connection::PlayerHandle;
+    use crate::phase::ClientIdentity;
+    use crate::players::{PlayerRegistry, STATUS_SAMPLE_LEN, ServerFull, StatusSample};
+
+    fn identity(username: &str) -> ClientIdentity {
+        ClientIdentity {
+            uuid: auth::offline_uuid(username),
+            username: username.to_string(),
+            properties: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_players_registry() {
+        let players = PlayerRegistry::new();
+        let steve = identity("Steve");
+        assert!(players.add(&steve, true, None, 1, PlayerHandle::spawn(0, tokio::io::sink(), None)).unwrap().is_none());
+        players.add(&identity("Alex"), true, Some("lobby".to_string()), 1, PlayerHandle::spawn(1, tokio::io::sink(), None)).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players.count(Some("lobby")), 1);
+
+        // Logging in again replaces the first connection, which then leaves
+        let full = players.add(&identity("Herobrine"), true, None, 1, PlayerHandle::spawn(3, tokio::io::sink(), None));
+        assert_eq!(full.err(), Some(ServerFull));
+        let replaced = players.add(&steve, true, None, 1, PlayerHandle::spawn(2, tokio::io::sink(), None)).unwrap().unwrap();
+        assert_eq!(replaced.handle.id(), 0);
+        players.remove(steve.uuid, 0);
+        assert_eq!(players.get(steve.uuid).unwrap().handle.id(), 2);
+        players.remove(steve.uuid, 2);
+        assert!(players.get(steve.uuid).is_none());
+        assert_eq!(players.len(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_players_status_sample() {
+        let players = PlayerRegistry::new();
+        for i in 0..20 {
+            players.add(&identity(&format!("bot{}", i)), i != 0, None, 100, PlayerHandle::spawn(i, tokio::io::sink(), None)).unwrap();
+        }
+
+        let status = players.status(None, 100, &StatusSample::Players);
+        assert_eq!((status.online, status.max), (20, 100));
+        assert_eq!(status.sample.len(), STATUS_SAMPLE_LEN);
+        assert!(status.sample.iter().all(|line| line.name != "bot0"));
+        assert_eq!(players.status(Some("lobby"), 100, &StatusSample::Players).online, 0);
+
+        assert!(players.status(None, 100, &StatusSample::Hidden).sample.is_empty());
+        let custom = players.status(None, 100, &StatusSample::Custom(vec!["Welcome!".to_string()]));
+        assert_eq!(custom.online, 20);
+        assert_eq!(custom.sample[0].name, "Welcome!");
+        assert_eq!(custom.sample[0].id, "00000000-0000-0000-0000-000000000000");
+
+        let sample: StatusSample = serde_json::from_str(r#"{"custom": ["a", "b"]}"#).unwrap();
+        assert_eq!(sample, StatusSample::Custom(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(serde_json::from_str::<StatusSample>(r#""hidden""#).unwrap(), StatusSample::Hidden);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::packet::{PacketBound, PacketStatus};
+use crate::{PacketType, ids};
+use std::fmt;
+
+// PROTOCOL VERSIONS //
+
+/// Protocol version a client can connect with, ordered from oldest to newest.
+///
+/// Packets are written against the latest version; ids and layouts are
+/// translated for the older ones.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Default)]
+pub enum ProtocolVersion {
+    /// 1.21 and 1.21.1
+    V1_21_1,
+    /// 1.21.2 and 1.21.3
+    V1_21_3,
+    /// 1.21.4
+    #[default]
+    V1_21_4,
+}
+
+/// Packets renamed after a version: the version, then the name up to that
+/// version and the latest name.
+const RENAMES: [(ProtocolVersion, PacketStatus, PacketBound, &str, &str); 2] = [
+    (ProtocolVersion::V1_21_1, PacketStatus::Login, PacketBound::Client, "minecraft:game_profile", "minecraft:login_finished"),
+    (ProtocolVersion::V1_21_1, PacketStatus::Play, PacketBound::Client, "minecraft:set_carried_item", "minecraft:set_held_slot"),
+];
+
+impl ProtocolVersion {
+    pub const ALL: [ProtocolVersion; 3] = [ProtocolVersion::V1_21_1, ProtocolVersion::V1_21_3, ProtocolVersion::V1_21_4];
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_21_4;
+
+    /// Version of a handshake `protocol_version`, `None` when unsupported.
+    pub fn from_protocol(protocol: i32) -> Option<ProtocolVersion> {
+        ProtocolVersion::ALL.into_iter().find(|version| version.protocol() == protocol)
+    }
+
+    pub fn protocol(&self) -> i32 {
+        match self {
+            ProtocolVersion::V1_21_1 => 767,
+            ProtocolVersion::V1_21_3 => 768,
+            ProtocolVersion::V1_21_4 => 769,
+        }
+    }
+
+    /// Game version shown to players, also the version of the `minecraft:core` pack.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_21_1 => "1.21.1",
+            ProtocolVersion::V1_21_3 => "1.21.3",
+            ProtocolVersion::V1_21_4 => "1.21.4",
+        }
+    }
+
+    /// Id in this version of a packet, from its latest resource name.
+    pub fn packet_id(&self, state: PacketStatus, bound: PacketBound, name: &str) -> Option<PacketType> {
+        let name = RENAMES
+            .iter()
+            .find(|rename| *self <= rename.0 && rename.1 == state && rename.2 == bound && rename.4 == name)
+            .map_or(name, |rename| rename.3);
+        ids::id(*self, state, bound, name)
+    }
+
+    /// Latest id of a packet sent with its id in this version, `None` when the
+    /// packet no longer exists.
+    pub fn latest_id(&self, state: PacketStatus, bound: PacketBound, id: PacketType) -> Option<PacketType> {
+        let name = ids::name(*self, state, bound, id)?;
+        let name = RENAMES
+            .iter()
+            .find(|rename| *self <= rename.0 && rename.1 == state && rename.2 == bound && rename.3 == name)
+            .map_or(name, |rename| rename.4);
+        ids::id(ProtocolVersion::LATEST, state, bound, name)
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.protocol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ids;
+    use crate::packet::{PacketBound, PacketStatus};
+    use crate::protocol::ProtocolVersion;
+
+    #[test]
+    pub fn test_protocol_from_protocol() {
+        assert_eq!(ProtocolVersion::from_protocol(767), Some(ProtocolVersion::V1_21_1));
+        assert_eq!(ProtocolVersion::from_protocol(768), Some(ProtocolVersion::V1_21_3));
+        assert_eq!(ProtocolVersion::from_protocol(769), Some(ProtocolVersion::V1_21_4));
+        assert_eq!(ProtocolVersion::from_protocol(766), None);
+        assert!(ProtocolVersion::V1_21_1 < ProtocolVersion::LATEST);
+    }
+
+    #[test]
+    pub fn test_protocol_packet_id() {
+        let version = ProtocolVersion::V1_21_1;
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Client, "minecraft:login"), Some(0x2B));
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Client, "minecraft:player_rotation"), None);
+        // Renamed packets
+        assert_eq!(version.packet_id(PacketStatus::Login, PacketBound::Client, "minecraft:login_finished"), Some(0x02));
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Client, "minecraft:set_held_slot"), Some(0x53));
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Server, "minecraft:set_carried_item"), Some(0x2F));
+
+        let version = ProtocolVersion::V1_21_3;
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Client, "minecraft:set_held_slot"), Some(0x63));
+        assert_eq!(version.packet_id(PacketStatus::Play, PacketBound::Server, "minecraft:player_loaded"), None);
+    }
+
+    #[test]
+    pub fn test_protocol_latest_id() {
+        let version = ProtocolVersion::V1_21_1;
+        assert_eq!(version.latest_id(PacketStatus::Play, PacketBound::Server, 0x18), Some(ids::play::serverbound::KEEP_ALIVE));
+        assert_eq!(version.latest_id(PacketStatus::Play, PacketBound::Client, 0x53), Some(ids::play::clientbound::SET_HELD_SLOT));
+        // Pick Item was split in 1.21.4
+        assert_eq!(version.latest_id(PacketStatus::Play, PacketBound::Server, 0x20), None);
+        assert_eq!(version.latest_id(PacketStatus::Play, PacketBound::Server, 0x7F), None);
+
+        for version in ProtocolVersion::ALL {
+            let id = version.packet_id(PacketStatus::Login, PacketBound::Client, "minecraft:login_finished").unwrap();
+            assert_eq!(version.latest_id(PacketStatus::Login, PacketBound::Client, id), Some(ids::login::clientbound::LOGIN_FINISHED));
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use crate::frame::FrameDecoder;
+use crate::handshake::ServerBoundHandshakePacket;
+use crate::packet::{Packet, PacketBound, PacketManager, PacketStatus};
+use crate::protocol::ProtocolVersion;
+use crate::{ByteBuf, DecodeError, PacketType, ids};
+use std::fmt;
+
+// PROXY //
+
+/// Outcome of decoding then re-encoding a relayed packet.
+#[derive(Debug)]
+pub enum Verdict {
+    /// Re-encoded to the same bytes
+    Match,
+    /// No voidpoc type for this packet
+    NotImplemented,
+    Undecodable(DecodeError),
+    /// Decoded without reading the whole packet
+    Unread(usize),
+    /// Re-encoded to different bytes, from `offset` on
+    Mismatch {
+        offset: usize,
+        original: Vec<u8>,
+        encoded: Vec<u8>,
+    },
+}
+
+impl Verdict {
+    /// Whether the packet exposes a bug in a voidpoc codec.
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Verdict::Match | Verdict::NotImplemented)
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Match => write!(f, "ok"),
+            Verdict::NotImplemented => write!(f, "not implemented"),
+            Verdict::Undecodable(err) => write!(f, "failed to decode: {:?}", err),
+            Verdict::Unread(len) => write!(f, "{} bytes left after decoding", len),
+            Verdict::Mismatch {
+                offset,
+                original,
+                encoded,
+            } => write!(
+                f,
+                "re-encoded differently from byte {} ({} bytes, re-encoded {} bytes)",
+                offset,
+                original.len(),
+                encoded.len()
+            ),
+        }
+    }
+}
+
+/// Packet relayed by the proxy, with what the checker made of it.
+#[derive(Debug)]
+pub struct CheckedPacket {
+    pub version: ProtocolVersion,
+    pub state: PacketStatus,
+    pub bound: PacketBound,
+    /// Id in `version`
+    pub id: PacketType,
+    pub name: Option<&'static str>,
+    pub verdict: Verdict,
+}
+
+/// Decodes a packet content with voidpoc's types, then re-encodes it and
+/// compares with the original bytes.
+pub fn check(packets: PacketManager, state: PacketStatus, bound: PacketBound, id: PacketType, content: &[u8]) -> Verdict {
+    let Some(latest_id) = packets.latest_id(state, bound, id) else {
+        return Verdict::NotImplemented;
+    };
+    let mut buf = ByteBuf::with_capacity(content.len());
+    buf.write_buf(content);
+    let packet = match packets.decode_dyn(state, bound, latest_id, &mut buf) {
+        Ok(packet) => packet,
+        Err(DecodeError::UnknownPacketId(_)) => return Verdict::NotImplemented,
+        Err(err) => return Verdict::Undecodable(err),
+    };
+    if !buf.is_empty() {
+        return Verdict::Unread(buf.len());
+    }
+
+    let mut encoded = ByteBuf::with_capacity(content.len());
+    packet.encode_for(&mut encoded, packets.version);
+    let encoded = encoded.as_slice();
+    let offset = content.iter().zip(encoded).position(|(original, encoded)| original != encoded);
+    match offset.or((content.len() != encoded.len()).then(|| content.len().min(encoded.len()))) {
+        Some(offset) => Verdict::Mismatch {
+            offset,
+            original: content.to_vec(),
+            encoded: encoded.to_vec(),
+        },
+        None => Verdict::Match,
+    }
+}
+
+/// Follows both directions of a proxied connection, checking every packet.
+///
+/// Encrypted connections cannot be followed, so the upstream server has to
+/// run in offline mode.
+pub struct ConformanceChecker {
+    version: ProtocolVersion,
+    // Each direction switches state after its own packet
+    serverbound_state: PacketStatus,
+    clientbound_state: PacketStatus,
+    serverbound: FrameDecoder,
+    clientbound: FrameDecoder,
+    stopped: Option<String>,
+}
+
+impl ConformanceChecker {
+    pub fn new() -> ConformanceChecker {
+        ConformanceChecker {
+            version: ProtocolVersion::LATEST,
+            serverbound_state: PacketStatus::Handshake,
+            clientbound_state: PacketStatus::Handshake,
+            serverbound: FrameDecoder::new(),
+            clientbound: FrameDecoder::new(),
+            stopped: None,
+        }
+    }
+
+    /// Why the checker stopped following the connection, if it did.
+    pub fn stopped(&self) -> Option<&str> {
+        self.stopped.as_deref()
+    }
+
+    /// Checks the packets completed by bytes relayed towards `bound`.
+    pub fn feed(&mut self, bound: PacketBound, data: &[u8]) -> Vec<CheckedPacket> {
+        let mut checked = Vec::new();
+        if self.stopped.is_some() {
+            return checked;
+        }
+        let decoder = match bound {
+            PacketBound::Server => &mut self.serverbound,
+            PacketBound::Client => &mut self.clientbound,
+        };
+        decoder.feed(data);
+
+        while self.stopped.is_none() {
+            let decoder = match bound {
+                PacketBound::Server => &mut self.serverbound,
+                PacketBound::Client => &mut self.clientbound,
+            };
+            match decoder.next_frame() {
+                Ok(Some(frame)) => match self.check_frame(bound, frame) {
+                    Ok(packet) => checked.push(packet),
+                    Err(err) => self.stopped = Some(format!("invalid packet: {:?}", err)),
+                },
+                Ok(None) => break,
+                Err(err) => self.stopped = Some(format!("invalid frame: {}", err)),
+            }
+        }
+        checked
+    }
+
+    fn check_frame(&mut self, bound: PacketBound, mut frame: ByteBuf) -> Result<CheckedPacket, DecodeError> {
+        let state = match bound {
+            PacketBound::Server => self.serverbound_state,
+            PacketBound::Client => self.clientbound_state,
+        };
+        let id = frame.read_varint()?;
+        let content = frame.as_slice();
+        let next_state = match state {
+            PacketStatus::Handshake => Some(self.handshake(content)?),
+            _ => None,
+        };
+
+        let name = ids::name(self.version, state, bound, id);
+        let packet = CheckedPacket {
+            version: self.version,
+            state,
+            bound,
+            id,
+            name,
+            verdict: check(PacketManager::new(self.version), state, bound, id, content),
+        };
+        match next_state {
+            Some(next_state) => {
+                self.serverbound_state = next_state;
+                self.clientbound_state = next_state;
+            }
+            None => self.follow(state, bound, name, content)?,
+        }
+        Ok(packet)
+    }
+
+    /// Reads the client's version, returning the state both directions switch to.
+    fn handshake(&mut self, content: &[u8]) -> Result<PacketStatus, DecodeError> {
+        let mut buf = ByteBuf::with_capacity(content.len());
+        buf.write_buf(content);
+        let mut handshake = ServerBoundHandshakePacket::default();
+        handshake.decode(&mut buf)?;
+        match ProtocolVersion::from_protocol(handshake.protocol_version) {
+            Some(version) => self.version = version,
+            None => self.stopped = Some(format!("unsupported protocol version {}", handshake.protocol_version)),
+        }
+        // Transfers log in again
+        match handshake.next_state {
+            1 => Ok(PacketStatus::Status),
+            _ => Ok(PacketStatus::Login),
+        }
+    }
+
+    /// Switches state and compression after packets that do so.
+    fn follow(&mut self, state: PacketStatus, bound: PacketBound, name: Option<&str>, content: &[u8]) -> Result<(), DecodeError> {
+        let name = name.unwrap_or_default();
+        match (bound, state, name) {
+            (PacketBound::Server, PacketStatus::Login, "minecraft:login_acknowledged")
+            | (PacketBound::Server, PacketStatus::Play, "minecraft:configuration_acknowledged") => {
+                self.serverbound_state = PacketStatus::Configuration;
+            }
+            (PacketBound::Server, PacketStatus::Configuration, "minecraft:finish_configuration") => {
+                self.serverbound_state = PacketStatus::Play;
+            }
+            (PacketBound::Client, PacketStatus::Login, "minecraft:hello") => {
+                self.stopped = Some("server requested encryption, it must run in offline mode".to_string());
+            }
+            (PacketBound::Client, PacketStatus::Login, "minecraft:login_compression") => {
+                let mut buf = ByteBuf::with_capacity(content.len());
+                buf.write_buf(content);
+                let threshold = buf.read_varint()?;
+                let threshold = (threshold >= 0).then_some(threshold);
+                // The client compresses everything it sends after receiving it
+                self.clientbound.set_compression(threshold);
+                self.serverbound.set_compression(threshold);
+            }
+            (PacketBound::Client, PacketStatus::Login, "minecraft:login_finished" | "minecraft:game_profile")
+            | (PacketBound::Client, PacketStatus::Play, "minecraft:start_configuration") => {
+                self.clientbound_state = PacketStatus::Configuration;
+            }
+            (PacketBound::Client, PacketStatus::Configuration, "minecraft:finish_configuration") => {
+                self.clientbound_state = PacketStatus::Play;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Default for ConformanceChecker {
+    fn default() -> Self {
+        ConformanceChecker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::{ClientBoundConfigurationFinishConfigurationPacket, ServerBoundConfigurationAcknowledgeFinishConfiguration};
+    use crate::frame::FrameEncoder;
+    use crate::handshake::ServerBoundHandshakePacket;
+    use crate::login::{
+        ClientBoundLoginEncryptionRequestPacket, ClientBoundLoginLoginSuccessPacket, ClientBoundLoginSetCompressionPacket,
+        ServerBoundLoginLoginAcknowledgedPacket, ServerBoundLoginStartPacket,
+    };
+    use crate::packet::{Packet, PacketBound, PacketManager, PacketStatus};
+    use crate::play::ClientBoundPlayGameEventPacket;
+    use crate::protocol::ProtocolVersion;
+    use crate::proxy::{ConformanceChecker, Verdict, check};
+    use crate::{ByteBuf, ids};
+
+    fn frame(encoder: &FrameEncoder, packet: &dyn Packet) -> Vec<u8> {
+        let mut buf = encoder.begin();
+        assert!(PacketManager::new(ProtocolVersion::V1_21_1).encode(packet, &mut buf));
+        encoder.finish(buf).to_vec()
+    }
+
+    fn handshake() -> ServerBoundHandshakePacket {
+        ServerBoundHandshakePacket {
+            protocol_version: ProtocolVersion::V1_21_1.protocol(),
+            server_address: "localhost".to_string(),
+            server_port: 25565,
+            next_state: 2,
+        }
+    }
+
+    #[test]
+    pub fn test_proxy_check_verdicts() {
+        let packets = PacketManager::default();
+        let (state, bound) = (PacketStatus::Play, PacketBound::Client);
+        let mut content = ByteBuf::new();
+        ClientBoundPlayGameEventPacket { event_id: 13, value: 0.0 }.encode(&mut content);
+        let id = ids::play::clientbound::GAME_EVENT;
+        assert!(matches!(check(packets, state, bound, id, content.as_slice()), Verdict::Match));
+
+        content.write_u16(0);
+        assert!(matches!(check(packets, state, bound, id, content.as_slice()), Verdict::Unread(2)));
+        assert!(matches!(check(packets, state, bound, id, &[13]), Verdict::Undecodable(_)));
+        assert!(matches!(check(packets, state, bound, ids::play::clientbound::BUNDLE_DELIMITER, &[]), Verdict::NotImplemented));
+
+        // An overlong varint decodes fine but is not re-encoded the same way
+        let verdict = check(packets, PacketStatus::Login, bound, ids::login::clientbound::LOGIN_COMPRESSION, &[0x81, 0x00]);
+        assert!(matches!(verdict, Verdict::Mismatch { offset: 0, .. }));
+        assert!(verdict.is_failure());
+    }
+
+    #[test]
+    pub fn test_proxy_follows_states() {
+        let mut checker = ConformanceChecker::new();
+        let mut encoder = FrameEncoder::new();
+
+        let mut serverbound = frame(&encoder, &handshake());
+        serverbound.extend(frame(
+            &encoder,
+            &ServerBoundLoginStartPacket {
+                name: "bot".to_string(),
+                ..Default::default()
+            },
+        ));
+        let checked = checker.feed(PacketBound::Server, &serverbound);
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[1].state, PacketStatus::Login);
+        assert_eq!(checked[1].version, ProtocolVersion::V1_21_1);
+
+        let mut clientbound = frame(&encoder, &ClientBoundLoginSetCompressionPacket { threshold: 16 });
+        encoder.set_compression(Some(16));
+        clientbound.extend(frame(
+            &encoder,
+            &ClientBoundLoginLoginSuccessPacket {
+                username: "a name long enough to be compressed".to_string(),
+                ..Default::default()
+            },
+        ));
+        clientbound.extend(frame(&encoder, &ClientBoundConfigurationFinishConfigurationPacket::default()));
+        clientbound.extend(frame(&encoder, &ClientBoundPlayGameEventPacket { event_id: 13, value: 0.0 }));
+        let checked = checker.feed(PacketBound::Client, &clientbound);
+        let states: Vec<_> = checked.iter().map(|packet| (packet.state, packet.name.unwrap())).collect();
+        assert_eq!(
+            states,
+            [
+                (PacketStatus::Login, "minecraft:login_compression"),
+                (PacketStatus::Login, "minecraft:game_profile"),
+                (PacketStatus::Configuration, "minecraft:finish_configuration"),
+                (PacketStatus::Play, "minecraft:game_event"),
+            ]
+        );
+
+        let mut serverbound = frame(&encoder, &ServerBoundLoginLoginAcknowledgedPacket::default());
+        serverbound.extend(frame(&encoder, &ServerBoundConfigurationAcknowledgeFinishConfiguration::default()));
+        let checked = checker.feed(PacketBound::Server, &serverbound);
+        assert_eq!(checked[1].state, PacketStatus::Configuration);
+        assert!(checked.iter().all(|packet| matches!(packet.verdict, Verdict::Match)));
+        assert!(checker.stopped().is_none());
+    }
+
+    #[test]
+    pub fn test_proxy_stops_on_encryption() {
+        let mut checker = ConformanceChecker::new();
+        let encoder = FrameEncoder::new();
+        checker.feed(PacketBound::Server, &frame(&encoder, &handshake()));
+
+        let request = frame(&encoder, &ClientBoundLoginEncryptionRequestPacket::default());
+        let mut clientbound = request.clone();
+        clientbound.extend(request);
+        assert_eq!(checker.feed(PacketBound::Client, &clientbound).len(), 1);
+        assert!(checker.stopped().is_some());
+    }
+}
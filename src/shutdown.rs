@@ -0,0 +1,140 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+// SHUTDOWN //
+
+// How long players' queued packets, then save hooks, may hold up the exit
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+pub const SAVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Server-wide shutdown signal, with the reason shown to players.
+#[derive(Clone)]
+pub struct Shutdown {
+    reason: Arc<watch::Sender<Option<String>>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        Shutdown {
+            reason: Arc::new(watch::channel(None).0),
+        }
+    }
+
+    /// Starts the shutdown. Only the first reason is kept.
+    pub fn trigger(&self, reason: &str) {
+        self.reason.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(reason.to_string());
+            true
+        });
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.reason.borrow().is_some()
+    }
+
+    /// Waits for the shutdown, returning its reason.
+    pub async fn triggered(&self) -> String {
+        let mut reason = self.reason.subscribe();
+        // The sender lives as long as `self`
+        let reason = reason.wait_for(Option::is_some).await.expect("shutdown sender dropped");
+        reason.clone().unwrap_or_default()
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown::new()
+    }
+}
+
+type SaveHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> + Send>;
+
+/// State to write out before the server exits, such as the world or player data.
+#[derive(Default)]
+pub struct SaveHooks {
+    hooks: Vec<(&'static str, SaveHook)>,
+}
+
+impl SaveHooks {
+    pub fn new() -> SaveHooks {
+        SaveHooks { hooks: Vec::new() }
+    }
+
+    pub fn register<F, Fut>(&mut self, name: &'static str, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.hooks.push((name, Box::new(move || Box::pin(hook()))));
+    }
+
+    /// Runs every hook concurrently, each for at most `timeout`. Returns the
+    /// outcome of every hook, in registration order.
+    pub async fn run(self, timeout: Duration) -> Vec<(&'static str, Result<(), String>)> {
+        let mut running = JoinSet::new();
+        for (i, (name, hook)) in self.hooks.into_iter().enumerate() {
+            running.spawn(async move {
+                let result = match tokio::time::timeout(timeout, hook()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("timed out after {}s", timeout.as_secs_f32())),
+                };
+                (i, name, result)
+            });
+        }
+
+        let mut results = Vec::new();
+        while let Some(joined) = running.join_next().await {
+            match joined {
+                Ok(result) => results.push(result),
+                Err(err) => eprintln!("Save hook panicked: {}", err),
+            }
+        }
+        results.sort_by_key(|(i, _, _)| *i);
+        results.into_iter().map(|(_, name, result)| (name, result)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shutdown::{SaveHooks, Shutdown};
+    use std::time::Duration;
+
+    #[tokio::test]
+    pub async fn test_shutdown_reason() {
+        let shutdown = Shutdown::new();
+        let waiting = shutdown.clone();
+        let waiter = tokio::spawn(async move { waiting.triggered().await });
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger("Server restarting");
+        shutdown.trigger("Server closed");
+        assert!(shutdown.is_triggered());
+        assert_eq!(waiter.await.unwrap(), "Server restarting");
+        assert_eq!(shutdown.triggered().await, "Server restarting");
+    }
+
+    #[tokio::test]
+    pub async fn test_shutdown_save_hooks() {
+        let mut hooks = SaveHooks::new();
+        hooks.register("world", || async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        hooks.register("players", || async { Ok(()) });
+        hooks.register("capture", || async { Err("disk full".to_string()) });
+
+        let results = hooks.run(Duration::from_millis(50)).await;
+        let names: Vec<&str> = results.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["world", "players", "capture"]);
+        assert!(results[0].1.is_err());
+        assert_eq!(results[1].1, Ok(()));
+        assert_eq!(results[2].1, Err("disk full".to_string()));
+    }
+}
@@ -1,35 +1,57 @@
 use crate::packet::{Packet, PacketFactory};
-use crate::{ByteBuf, PacketType};
+use crate::protocol::ProtocolVersion;
+use crate::text::TextComponent;
 use serde::{Deserialize, Serialize};
-use std::any::Any;
 
 // STATUS //
 
 #[derive(Eq, PartialEq)]
 pub enum EServerStatusPacket {
-    ServerBoundStatusRequestPacket = 0x00,
-    ServerBoundStatusPingPacket = 0x01,
+    ServerBoundStatusRequestPacket,
+    ServerBoundStatusPingPacket,
 }
 
 pub struct ServerStatusPacketFactory {}
 
 impl PacketFactory<EServerStatusPacket> for ServerStatusPacketFactory {
-    fn decode<T: Packet + 'static>(&self, id: i32, buf: &mut ByteBuf) -> Box<T> {
-        let packet: Box<dyn Any> = match id {
-            0x00 => Box::new(ServerBoundStatusRequestPacket::default()) as Box<dyn Any>,
-            0x01 => Box::new(ServerBoundStatusPingPacket::default()) as Box<dyn Any>,
-            _ => panic!("Unknown packet id"),
-        };
-        let mut packet: Box<T> = packet.downcast::<T>().expect("Failed to downcast Packet");
-
-        packet.decode(buf);
-        packet
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ServerBoundStatusRequestPacket::ID => Some(Box::new(ServerBoundStatusRequestPacket::default())),
+            ServerBoundStatusPingPacket::ID => Some(Box::new(ServerBoundStatusPingPacket::default())),
+            _ => None,
+        }
     }
 
     fn from_id(&self, id: i32) -> Option<EServerStatusPacket> {
         match id {
-            0x00 => Some(EServerStatusPacket::ServerBoundStatusRequestPacket),
-            0x01 => Some(EServerStatusPacket::ServerBoundStatusPingPacket),
+            ServerBoundStatusRequestPacket::ID => Some(EServerStatusPacket::ServerBoundStatusRequestPacket),
+            ServerBoundStatusPingPacket::ID => Some(EServerStatusPacket::ServerBoundStatusPingPacket),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq)]
+pub enum EClientStatusPacket {
+    ClientBoundStatusResponsePacket,
+    ClientBoundStatusPingResponsePacket,
+}
+
+pub struct ClientStatusPacketFactory {}
+
+impl PacketFactory<EClientStatusPacket> for ClientStatusPacketFactory {
+    fn create(&self, id: i32) -> Option<Box<dyn Packet>> {
+        match id {
+            ClientBoundStatusResponsePacket::ID => Some(Box::new(ClientBoundStatusResponsePacket::default())),
+            ClientBoundStatusPingResponsePacket::ID => Some(Box::new(ClientBoundStatusPingResponsePacket::default())),
+            _ => None,
+        }
+    }
+
+    fn from_id(&self, id: i32) -> Option<EClientStatusPacket> {
+        match id {
+            ClientBoundStatusResponsePacket::ID => Some(EClientStatusPacket::ClientBoundStatusResponsePacket),
+            ClientBoundStatusPingResponsePacket::ID => Some(EClientStatusPacket::ClientBoundStatusPingResponsePacket),
             _ => None,
         }
     }
@@ -41,39 +63,18 @@ impl PacketFactory<EServerStatusPacket> for ServerStatusPacketFactory {
 
 // Status Request
 
-#[derive(Debug, Default)]
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:status_request", state = Status, bound = Server)]
 pub struct ServerBoundStatusRequestPacket {}
-impl Packet for ServerBoundStatusRequestPacket {
-    fn id(&self) -> PacketType {
-        0x00
-    }
-
-    fn encode(&self, _buf: &mut ByteBuf) {}
-
-    fn decode(&mut self, _buf: &mut ByteBuf) {}
-}
 
 // Ping Request
 
-#[derive(Debug, Default)]
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:ping_request", state = Status, bound = Server)]
 pub struct ServerBoundStatusPingPacket {
     pub timestamp: i64,
 }
 
-impl Packet for ServerBoundStatusPingPacket {
-    fn id(&self) -> PacketType {
-        0x01
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_i64(self.timestamp);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        self.timestamp = buf.read_i64();
-    }
-}
-
 // CLIENTBOUND //
 
 // Status Response 0x00
@@ -97,22 +98,22 @@ pub struct ServerStatusResponsePlayers {
     pub sample: Vec<ServerStatusResponsePlayersSample>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ServerStatusResponseDescription {
-    pub text: String,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServerStatusResponse {
     pub version: ServerStatusResponseVersion,
     pub players: ServerStatusResponsePlayers,
-    pub description: ServerStatusResponseDescription,
-    //pub favicon: String,
+    /// MOTD
+    pub description: TextComponent,
+    /// 64x64 PNG as a `data:image/png;base64,` URI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
     pub enforces_secure_chat: bool,
 }
 
-#[derive(Debug)]
+#[derive(Packet, Debug)]
+#[packet(name = "minecraft:status_response", state = Status, bound = Client)]
 pub struct ClientBoundStatusResponsePacket {
+    #[packet(json)]
     pub response: ServerStatusResponse,
 }
 
@@ -121,57 +122,26 @@ impl Default for ClientBoundStatusResponsePacket {
         ClientBoundStatusResponsePacket {
             response: ServerStatusResponse {
                 version: ServerStatusResponseVersion {
-                    name: "1.21.4".to_string(),
-                    protocol: 769,
+                    name: ProtocolVersion::LATEST.name().to_string(),
+                    protocol: ProtocolVersion::LATEST.protocol(),
                 },
                 players: ServerStatusResponsePlayers {
                     max: 100,
                     online: 0,
                     sample: vec![],
                 },
-                description: ServerStatusResponseDescription {
-                    text: "HANNNNNNNNNN".to_string(),
-                },
-                //favicon: "".to_string(),
+                description: TextComponent::text("HANNNNNNNNNN"),
+                favicon: None,
                 enforces_secure_chat: false,
             }
         }
     }
 }
 
-impl Packet for ClientBoundStatusResponsePacket {
-    fn id(&self) -> PacketType {
-        0x00
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        let json = serde_json::to_string(&self.response).unwrap();
-        buf.write_string(&json);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        let json = buf.read_string();
-        self.response = serde_json::from_str(&json).unwrap();
-    }
-}
-
 // Ping Response 0x01
 
-#[derive(Debug, Default)]
+#[derive(Packet, Debug, Default)]
+#[packet(name = "minecraft:pong_response", state = Status, bound = Client)]
 pub struct ClientBoundStatusPingResponsePacket {
     pub timestamp: i64,
 }
-
-impl Packet for ClientBoundStatusPingResponsePacket {
-    fn id(&self) -> PacketType {
-        0x01
-    }
-
-    fn encode(&self, buf: &mut ByteBuf) {
-        buf.write_i64(self.timestamp);
-    }
-
-    fn decode(&mut self, buf: &mut ByteBuf) {
-        self.timestamp = buf.read_i64();
-    }
-}
\ No newline at end of file
@@ -0,0 +1,952 @@
+use crate::DecodeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+use simdnbt::owned::{NbtCompound, NbtList, NbtTag};
+use std::fmt;
+use uuid::Uuid;
+
+// TEXT COMPONENTS //
+
+// Introduces a legacy formatting code, such as `§c` for red
+pub const LEGACY_PREFIX: char = '§';
+
+fn invalid(message: impl Into<String>) -> DecodeError {
+    DecodeError::InvalidData(message.into())
+}
+
+/// The 16 colors of legacy formatting codes, in code order.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor {
+    pub const ALL: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::DarkBlue,
+        NamedColor::DarkGreen,
+        NamedColor::DarkAqua,
+        NamedColor::DarkRed,
+        NamedColor::DarkPurple,
+        NamedColor::Gold,
+        NamedColor::Gray,
+        NamedColor::DarkGray,
+        NamedColor::Blue,
+        NamedColor::Green,
+        NamedColor::Aqua,
+        NamedColor::Red,
+        NamedColor::LightPurple,
+        NamedColor::Yellow,
+        NamedColor::White,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NamedColor::Black => "black",
+            NamedColor::DarkBlue => "dark_blue",
+            NamedColor::DarkGreen => "dark_green",
+            NamedColor::DarkAqua => "dark_aqua",
+            NamedColor::DarkRed => "dark_red",
+            NamedColor::DarkPurple => "dark_purple",
+            NamedColor::Gold => "gold",
+            NamedColor::Gray => "gray",
+            NamedColor::DarkGray => "dark_gray",
+            NamedColor::Blue => "blue",
+            NamedColor::Green => "green",
+            NamedColor::Aqua => "aqua",
+            NamedColor::Red => "red",
+            NamedColor::LightPurple => "light_purple",
+            NamedColor::Yellow => "yellow",
+            NamedColor::White => "white",
+        }
+    }
+
+    pub fn rgb(self) -> u32 {
+        match self {
+            NamedColor::Black => 0x000000,
+            NamedColor::DarkBlue => 0x0000AA,
+            NamedColor::DarkGreen => 0x00AA00,
+            NamedColor::DarkAqua => 0x00AAAA,
+            NamedColor::DarkRed => 0xAA0000,
+            NamedColor::DarkPurple => 0xAA00AA,
+            NamedColor::Gold => 0xFFAA00,
+            NamedColor::Gray => 0xAAAAAA,
+            NamedColor::DarkGray => 0x555555,
+            NamedColor::Blue => 0x5555FF,
+            NamedColor::Green => 0x55FF55,
+            NamedColor::Aqua => 0x55FFFF,
+            NamedColor::Red => 0xFF5555,
+            NamedColor::LightPurple => 0xFF55FF,
+            NamedColor::Yellow => 0xFFFF55,
+            NamedColor::White => 0xFFFFFF,
+        }
+    }
+
+    /// Legacy formatting code, the character after `§`.
+    pub fn code(self) -> char {
+        char::from_digit(self as u32, 16).unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<NamedColor> {
+        NamedColor::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    pub fn from_code(code: char) -> Option<NamedColor> {
+        code.to_digit(16).map(|i| NamedColor::ALL[i as usize])
+    }
+
+    /// Closest named color, for places that only know legacy codes.
+    pub fn nearest(rgb: u32) -> NamedColor {
+        let channels = |rgb: u32| [(rgb >> 16) as i32 & 0xFF, (rgb >> 8) as i32 & 0xFF, rgb as i32 & 0xFF];
+        let target = channels(rgb);
+        NamedColor::ALL
+            .into_iter()
+            .min_by_key(|color| {
+                let channels = channels(color.rgb());
+                (0..3).map(|i| (channels[i] - target[i]).pow(2)).sum::<i32>()
+            })
+            .unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TextColor {
+    Named(NamedColor),
+    /// `#RRGGBB` in JSON and NBT
+    Rgb(u32),
+}
+
+impl TextColor {
+    pub fn parse(color: &str) -> Option<TextColor> {
+        match color.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(TextColor::Rgb),
+            Some(_) => None,
+            None => NamedColor::from_name(color).map(TextColor::Named),
+        }
+    }
+
+    pub fn named(self) -> NamedColor {
+        match self {
+            TextColor::Named(color) => color,
+            TextColor::Rgb(rgb) => NamedColor::nearest(rgb),
+        }
+    }
+}
+
+impl fmt::Display for TextColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextColor::Named(color) => write!(f, "{}", color.name()),
+            TextColor::Rgb(rgb) => write!(f, "#{:06X}", rgb),
+        }
+    }
+}
+
+impl From<NamedColor> for TextColor {
+    fn from(color: NamedColor) -> Self {
+        TextColor::Named(color)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ClickAction {
+    OpenUrl,
+    RunCommand,
+    SuggestCommand,
+    ChangePage,
+    CopyToClipboard,
+}
+
+impl ClickAction {
+    const ALL: [ClickAction; 5] = [
+        ClickAction::OpenUrl,
+        ClickAction::RunCommand,
+        ClickAction::SuggestCommand,
+        ClickAction::ChangePage,
+        ClickAction::CopyToClipboard,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClickAction::OpenUrl => "open_url",
+            ClickAction::RunCommand => "run_command",
+            ClickAction::SuggestCommand => "suggest_command",
+            ClickAction::ChangePage => "change_page",
+            ClickAction::CopyToClipboard => "copy_to_clipboard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ClickAction> {
+        ClickAction::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClickEvent {
+    pub action: ClickAction,
+    pub value: String,
+}
+
+impl ClickEvent {
+    pub fn new(action: ClickAction, value: impl Into<String>) -> ClickEvent {
+        ClickEvent {
+            action,
+            value: value.into(),
+        }
+    }
+
+    pub fn open_url(url: impl Into<String>) -> ClickEvent {
+        ClickEvent::new(ClickAction::OpenUrl, url)
+    }
+
+    pub fn run_command(command: impl Into<String>) -> ClickEvent {
+        ClickEvent::new(ClickAction::RunCommand, command)
+    }
+
+    pub fn suggest_command(command: impl Into<String>) -> ClickEvent {
+        ClickEvent::new(ClickAction::SuggestCommand, command)
+    }
+
+    pub fn copy_to_clipboard(text: impl Into<String>) -> ClickEvent {
+        ClickEvent::new(ClickAction::CopyToClipboard, text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+    ShowItem {
+        id: String,
+        count: i32,
+    },
+    ShowEntity {
+        entity_type: String,
+        id: Uuid,
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+impl HoverEvent {
+    pub fn show_text(text: impl Into<TextComponent>) -> HoverEvent {
+        HoverEvent::ShowText(Box::new(text.into()))
+    }
+}
+
+/// Formatting of a component. Unset fields are inherited from the parent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<TextColor>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub font: Option<String>,
+    /// Inserted in the chat input on shift-click
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    pub fn is_empty(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// This style, with the fields it leaves unset taken from `parent`.
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+
+    /// Legacy codes of the color and decorations, the color first since it
+    /// resets the decorations.
+    fn legacy_codes(&self) -> String {
+        let mut codes = String::new();
+        if let Some(color) = self.color {
+            codes.push(LEGACY_PREFIX);
+            codes.push(color.named().code());
+        }
+        let decorations = [
+            (self.obfuscated, 'k'),
+            (self.bold, 'l'),
+            (self.strikethrough, 'm'),
+            (self.underlined, 'n'),
+            (self.italic, 'o'),
+        ];
+        for (enabled, code) in decorations {
+            if enabled == Some(true) {
+                codes.push(LEGACY_PREFIX);
+                codes.push(code);
+            }
+        }
+        codes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextContent {
+    Text(String),
+    /// Translation key of the client's language, with its arguments
+    Translate { key: String, with: Vec<TextComponent> },
+    /// Key bound to an action, such as `key.jump`
+    Keybind(String),
+}
+
+/// Text shown to players, serialized as JSON in the status and login states
+/// and as network NBT from Configuration on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextComponent {
+    pub content: TextContent,
+    pub style: Style,
+    /// Children, inheriting this component's style
+    pub extra: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    pub fn text(text: impl Into<String>) -> TextComponent {
+        TextComponent::from_content(TextContent::Text(text.into()))
+    }
+
+    pub fn translate(key: impl Into<String>, with: Vec<TextComponent>) -> TextComponent {
+        TextComponent::from_content(TextContent::Translate { key: key.into(), with })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> TextComponent {
+        TextComponent::from_content(TextContent::Keybind(key.into()))
+    }
+
+    fn from_content(content: TextContent) -> TextComponent {
+        TextComponent {
+            content,
+            style: Style::default(),
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn color(mut self, color: impl Into<TextColor>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<String>) -> Self {
+        self.style.font = Some(font.into());
+        self
+    }
+
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn click(mut self, event: ClickEvent) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub fn hover(mut self, event: HoverEvent) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    pub fn append(mut self, child: impl Into<TextComponent>) -> Self {
+        self.extra.push(child.into());
+        self
+    }
+
+    /// Plain text without style nor children, sent as a bare string in NBT.
+    pub fn is_plain(&self) -> bool {
+        matches!(self.content, TextContent::Text(_)) && self.style.is_empty() && self.extra.is_empty()
+    }
+
+    /// Text without any formatting. Translations and keybinds show their key.
+    pub fn to_plain(&self) -> String {
+        let mut plain = String::new();
+        self.visit(&Style::default(), &mut |text, _| plain.push_str(text));
+        plain
+    }
+
+    /// Calls `f` with the text of every component in order, with its style
+    /// once inherited.
+    fn visit(&self, parent: &Style, f: &mut impl FnMut(&str, &Style)) {
+        let style = self.style.inherit(parent);
+        match &self.content {
+            TextContent::Text(text) => f(text, &style),
+            TextContent::Translate { key, .. } | TextContent::Keybind(key) => f(key, &style),
+        }
+        for child in &self.extra {
+            child.visit(&style, f);
+        }
+    }
+
+    /// Parses text with legacy `§` formatting codes, as used in configs.
+    /// Unknown codes are dropped.
+    pub fn from_legacy(legacy: &str) -> TextComponent {
+        let mut parts = Vec::new();
+        let mut style = Style::default();
+        let mut text = String::new();
+        let mut chars = legacy.chars().peekable();
+        while let Some(c) = chars.next() {
+            let Some(code) = chars.peek().filter(|_| c == LEGACY_PREFIX).map(|code| code.to_ascii_lowercase()) else {
+                text.push(c);
+                continue;
+            };
+            chars.next();
+            if !text.is_empty() {
+                parts.push(TextComponent {
+                    content: TextContent::Text(std::mem::take(&mut text)),
+                    style: style.clone(),
+                    extra: Vec::new(),
+                });
+            }
+            match code {
+                'k' => style.obfuscated = Some(true),
+                'l' => style.bold = Some(true),
+                'm' => style.strikethrough = Some(true),
+                'n' => style.underlined = Some(true),
+                'o' => style.italic = Some(true),
+                'r' => style = Style::default(),
+                code => {
+                    if let Some(color) = NamedColor::from_code(code) {
+                        // Like in legacy clients, a color resets the decorations
+                        style = Style {
+                            color: Some(TextColor::Named(color)),
+                            ..Style::default()
+                        };
+                    }
+                }
+            }
+        }
+        if !text.is_empty() {
+            parts.push(TextComponent {
+                content: TextContent::Text(text),
+                style,
+                extra: Vec::new(),
+            });
+        }
+
+        match parts.len() {
+            0 => TextComponent::text(""),
+            1 => parts.remove(0),
+            _ => TextComponent {
+                extra: parts,
+                ..TextComponent::text("")
+            },
+        }
+    }
+
+    /// Text with legacy `§` formatting codes, for clients older than 1.7.
+    /// RGB colors become the closest named color, events are lost.
+    pub fn to_legacy(&self) -> String {
+        let mut legacy = String::new();
+        let mut current = String::new();
+        self.visit(&Style::default(), &mut |text, style| {
+            if text.is_empty() {
+                return;
+            }
+            let codes = style.legacy_codes();
+            if codes != current {
+                // Only a color or a reset clears the previous decorations
+                if style.color.is_none() && !current.is_empty() {
+                    legacy.push(LEGACY_PREFIX);
+                    legacy.push('r');
+                }
+                legacy.push_str(&codes);
+                current = codes;
+            }
+            legacy.push_str(text);
+        });
+        legacy
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut json = Map::new();
+        match &self.content {
+            TextContent::Text(text) => {
+                json.insert("text".to_string(), Value::String(text.clone()));
+            }
+            TextContent::Translate { key, with } => {
+                json.insert("translate".to_string(), Value::String(key.clone()));
+                if !with.is_empty() {
+                    json.insert("with".to_string(), Value::Array(with.iter().map(TextComponent::to_json).collect()));
+                }
+            }
+            TextContent::Keybind(key) => {
+                json.insert("keybind".to_string(), Value::String(key.clone()));
+            }
+        }
+
+        let style = &self.style;
+        if let Some(color) = style.color {
+            json.insert("color".to_string(), Value::String(color.to_string()));
+        }
+        let decorations = [
+            ("bold", style.bold),
+            ("italic", style.italic),
+            ("underlined", style.underlined),
+            ("strikethrough", style.strikethrough),
+            ("obfuscated", style.obfuscated),
+        ];
+        for (name, value) in decorations {
+            if let Some(value) = value {
+                json.insert(name.to_string(), Value::Bool(value));
+            }
+        }
+        if let Some(font) = &style.font {
+            json.insert("font".to_string(), Value::String(font.clone()));
+        }
+        if let Some(insertion) = &style.insertion {
+            json.insert("insertion".to_string(), Value::String(insertion.clone()));
+        }
+        if let Some(event) = &style.click_event {
+            json.insert("clickEvent".to_string(), serde_json::json!({
+                "action": event.action.name(),
+                "value": event.value,
+            }));
+        }
+        if let Some(event) = &style.hover_event {
+            let (action, contents) = match event {
+                HoverEvent::ShowText(text) => ("show_text", text.to_json()),
+                HoverEvent::ShowItem { id, count } => ("show_item", serde_json::json!({ "id": id, "count": count })),
+                HoverEvent::ShowEntity { entity_type, id, name } => {
+                    let mut contents = serde_json::json!({ "type": entity_type, "id": id.to_string() });
+                    if let Some(name) = name {
+                        contents["name"] = name.to_json();
+                    }
+                    ("show_entity", contents)
+                }
+            };
+            json.insert("hoverEvent".to_string(), serde_json::json!({ "action": action, "contents": contents }));
+        }
+
+        if !self.extra.is_empty() {
+            json.insert("extra".to_string(), Value::Array(self.extra.iter().map(TextComponent::to_json).collect()));
+        }
+        Value::Object(json)
+    }
+
+    /// Parses a JSON component, also accepting the string and array shorthands.
+    pub fn from_json(json: &Value) -> Result<TextComponent, DecodeError> {
+        let json = match json {
+            Value::String(text) => return Ok(TextComponent::text(text.clone())),
+            // The first component, with the others as children
+            Value::Array(components) => {
+                let (first, rest) = components.split_first().ok_or_else(|| invalid("empty text component list"))?;
+                let mut component = TextComponent::from_json(first)?;
+                for child in rest {
+                    component.extra.push(TextComponent::from_json(child)?);
+                }
+                return Ok(component);
+            }
+            Value::Object(json) => json,
+            _ => return Err(invalid("text component is not a string, a list or an object")),
+        };
+
+        let content = if let Some(text) = json.get("text") {
+            TextContent::Text(primitive_text(text)?)
+        } else if let Some(key) = json.get("translate") {
+            let with = match json.get("with") {
+                Some(Value::Array(with)) => with.iter().map(translate_argument).collect::<Result<_, _>>()?,
+                Some(_) => return Err(invalid("translation arguments are not a list")),
+                None => Vec::new(),
+            };
+            TextContent::Translate { key: string(key, "translate")?, with }
+        } else if let Some(key) = json.get("keybind") {
+            TextContent::Keybind(string(key, "keybind")?)
+        } else if json.contains_key("extra") {
+            TextContent::Text(String::new())
+        } else {
+            return Err(invalid("text component has no text, translate or keybind"));
+        };
+
+        let color = match json.get("color") {
+            Some(color) => {
+                let color = string(color, "color")?;
+                Some(TextColor::parse(&color).ok_or_else(|| invalid(format!("unknown text color {}", color)))?)
+            }
+            None => None,
+        };
+        let style = Style {
+            color,
+            bold: flag(json, "bold")?,
+            italic: flag(json, "italic")?,
+            underlined: flag(json, "underlined")?,
+            strikethrough: flag(json, "strikethrough")?,
+            obfuscated: flag(json, "obfuscated")?,
+            font: json.get("font").map(|font| string(font, "font")).transpose()?,
+            insertion: json.get("insertion").map(|insertion| string(insertion, "insertion")).transpose()?,
+            click_event: json.get("clickEvent").map(click_event).transpose()?,
+            hover_event: json.get("hoverEvent").map(hover_event).transpose()?,
+        };
+
+        let extra = match json.get("extra") {
+            Some(Value::Array(extra)) => extra.iter().map(TextComponent::from_json).collect::<Result<_, _>>()?,
+            Some(_) => return Err(invalid("text component extra is not a list")),
+            None => Vec::new(),
+        };
+        Ok(TextComponent { content, style, extra })
+    }
+
+    /// Network NBT, a bare string tag for plain text.
+    pub fn to_nbt(&self) -> NbtTag {
+        match &self.content {
+            TextContent::Text(text) if self.is_plain() => NbtTag::String(text.as_str().into()),
+            _ => json_to_nbt(&self.to_json()),
+        }
+    }
+
+    pub fn from_nbt(nbt: &NbtTag) -> Result<TextComponent, DecodeError> {
+        TextComponent::from_json(&nbt_to_json(nbt))
+    }
+}
+
+impl Default for TextComponent {
+    fn default() -> Self {
+        TextComponent::text("")
+    }
+}
+
+impl fmt::Display for TextComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_plain())
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = Value::deserialize(deserializer)?;
+        TextComponent::from_json(&json).map_err(serde::de::Error::custom)
+    }
+}
+
+fn string(json: &Value, name: &str) -> Result<String, DecodeError> {
+    json.as_str().map(str::to_string).ok_or_else(|| invalid(format!("text component {} is not a string", name)))
+}
+
+/// Text of a `text` field, which vanilla also accepts as a number or boolean.
+fn primitive_text(json: &Value) -> Result<String, DecodeError> {
+    match json {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(invalid("text component text is not a string")),
+    }
+}
+
+fn translate_argument(json: &Value) -> Result<TextComponent, DecodeError> {
+    match json {
+        Value::Number(_) | Value::Bool(_) => Ok(TextComponent::text(primitive_text(json)?)),
+        json => TextComponent::from_json(json),
+    }
+}
+
+/// Decoration flag, a boolean in JSON and a byte in NBT.
+fn flag(json: &Map<String, Value>, name: &str) -> Result<Option<bool>, DecodeError> {
+    match json.get(name) {
+        None => Ok(None),
+        Some(Value::Bool(value)) => Ok(Some(*value)),
+        Some(Value::Number(value)) => Ok(Some(value.as_i64() != Some(0))),
+        Some(_) => Err(invalid(format!("text component {} is not a boolean", name))),
+    }
+}
+
+fn click_event(json: &Value) -> Result<ClickEvent, DecodeError> {
+    let action = string(&json["action"], "click action")?;
+    let action = ClickAction::from_name(&action).ok_or_else(|| invalid(format!("unknown click action {}", action)))?;
+    Ok(ClickEvent {
+        action,
+        // Page numbers can be sent as numbers
+        value: primitive_text(&json["value"])?,
+    })
+}
+
+fn hover_event(json: &Value) -> Result<HoverEvent, DecodeError> {
+    let action = string(&json["action"], "hover action")?;
+    // `value` is the pre-1.16 name of `contents`
+    let contents = json.get("contents").or_else(|| json.get("value")).unwrap_or(&Value::Null);
+    match action.as_str() {
+        "show_text" => Ok(HoverEvent::ShowText(Box::new(TextComponent::from_json(contents)?))),
+        "show_item" => match contents {
+            Value::String(id) => Ok(HoverEvent::ShowItem { id: id.clone(), count: 1 }),
+            _ => Ok(HoverEvent::ShowItem {
+                id: string(&contents["id"], "item id")?,
+                count: contents["count"].as_i64().unwrap_or(1) as i32,
+            }),
+        },
+        "show_entity" => Ok(HoverEvent::ShowEntity {
+            entity_type: string(&contents["type"], "entity type")?,
+            id: entity_uuid(&contents["id"])?,
+            name: match contents.get("name") {
+                Some(name) => Some(Box::new(TextComponent::from_json(name)?)),
+                None => None,
+            },
+        }),
+        action => Err(invalid(format!("unknown hover action {}", action))),
+    }
+}
+
+/// Entity UUID, a string in JSON and usually 4 ints in NBT.
+fn entity_uuid(json: &Value) -> Result<Uuid, DecodeError> {
+    if let Some(uuid) = json.as_str() {
+        return Uuid::parse_str(uuid).map_err(|err| invalid(err.to_string()));
+    }
+    let ints: Option<Vec<i64>> = json.as_array().map(|ints| ints.iter().filter_map(Value::as_i64).collect());
+    match ints.as_deref() {
+        Some([a, b, c, d]) => {
+            let uuid = [a, b, c, d].iter().fold(0u128, |uuid, int| (uuid << 32) | (**int as u32 as u128));
+            Ok(Uuid::from_u128(uuid))
+        }
+        _ => Err(invalid("entity id is not a UUID")),
+    }
+}
+
+fn json_to_nbt(json: &Value) -> NbtTag {
+    match json {
+        Value::Bool(value) => NbtTag::Byte(*value as i8),
+        Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(int), _) if i32::try_from(int).is_ok() => NbtTag::Int(int as i32),
+            (Some(long), _) => NbtTag::Long(long),
+            (_, double) => NbtTag::Double(double.unwrap_or_default()),
+        },
+        Value::String(text) => NbtTag::String(text.as_str().into()),
+        Value::Array(values) => NbtTag::List(json_list_to_nbt(values)),
+        Value::Object(json) => NbtTag::Compound(NbtCompound::from_values(
+            json.iter().map(|(name, value)| (name.as_str().into(), json_to_nbt(value))).collect(),
+        )),
+        Value::Null => NbtTag::Compound(NbtCompound::new()),
+    }
+}
+
+/// NBT lists hold a single tag type, only components and strings are expected.
+fn json_list_to_nbt(values: &[Value]) -> NbtList {
+    if values.is_empty() {
+        return NbtList::Empty;
+    }
+    if values.iter().all(Value::is_string) {
+        return NbtList::String(values.iter().filter_map(Value::as_str).map(Into::into).collect());
+    }
+    NbtList::Compound(
+        values
+            .iter()
+            .map(|value| match json_to_nbt(value) {
+                NbtTag::Compound(compound) => compound,
+                tag => NbtCompound::from_values(vec![("".into(), tag)]),
+            })
+            .collect(),
+    )
+}
+
+fn nbt_to_json(nbt: &NbtTag) -> Value {
+    match nbt {
+        NbtTag::Byte(value) => Value::from(*value),
+        NbtTag::Short(value) => Value::from(*value),
+        NbtTag::Int(value) => Value::from(*value),
+        NbtTag::Long(value) => Value::from(*value),
+        NbtTag::Float(value) => Number::from_f64(*value as f64).map_or(Value::Null, Value::Number),
+        NbtTag::Double(value) => Number::from_f64(*value).map_or(Value::Null, Value::Number),
+        NbtTag::ByteArray(values) => Value::from(values.clone()),
+        NbtTag::String(text) => Value::String(text.to_str().into_owned()),
+        NbtTag::List(list) => Value::Array(nbt_list_to_json(list)),
+        NbtTag::Compound(compound) => compound_to_json(compound),
+        NbtTag::IntArray(values) => Value::from(values.clone()),
+        NbtTag::LongArray(values) => Value::from(values.clone()),
+    }
+}
+
+fn compound_to_json(compound: &NbtCompound) -> Value {
+    // Lists of mixed types wrap their elements in a compound with an empty key
+    if compound.len() == 1
+        && let Some(value) = compound.get("")
+    {
+        return nbt_to_json(value);
+    }
+    Value::Object(compound.iter().map(|(name, value)| (name.to_str().into_owned(), nbt_to_json(value))).collect())
+}
+
+fn nbt_list_to_json(list: &NbtList) -> Vec<Value> {
+    match list {
+        NbtList::Empty => Vec::new(),
+        NbtList::Byte(values) => values.iter().map(|value| Value::from(*value)).collect(),
+        NbtList::Short(values) => values.iter().map(|value| Value::from(*value)).collect(),
+        NbtList::Int(values) => values.iter().map(|value| Value::from(*value)).collect(),
+        NbtList::Long(values) => values.iter().map(|value| Value::from(*value)).collect(),
+        NbtList::Float(values) => values.iter().map(|value| nbt_to_json(&NbtTag::Float(*value))).collect(),
+        NbtList::Double(values) => values.iter().map(|value| nbt_to_json(&NbtTag::Double(*value))).collect(),
+        NbtList::ByteArray(values) => values.iter().map(|value| Value::from(value.clone())).collect(),
+        NbtList::String(values) => values.iter().map(|text| Value::String(text.to_str().into_owned())).collect(),
+        NbtList::List(values) => values.iter().map(|list| Value::Array(nbt_list_to_json(list))).collect(),
+        NbtList::Compound(values) => values.iter().map(compound_to_json).collect(),
+        NbtList::IntArray(values) => values.iter().map(|value| Value::from(value.clone())).collect(),
+        NbtList::LongArray(values) => values.iter().map(|value| Value::from(value.clone())).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::{ClickEvent, HoverEvent, NamedColor, TextColor, TextComponent, TextContent};
+    use simdnbt::owned::NbtTag;
+    use uuid::Uuid;
+
+    fn rich() -> TextComponent {
+        TextComponent::text("Welcome to ")
+            .color(NamedColor::Gray)
+            .append(
+                TextComponent::text("void")
+                    .color(TextColor::Rgb(0x7F3FBF))
+                    .bold(true)
+                    .click(ClickEvent::open_url("https://example.com"))
+                    .hover(HoverEvent::show_text("Open the website")),
+            )
+            .append(TextComponent::translate("chat.type.text", vec!["Steve".into(), TextComponent::keybind("key.jump")]))
+            .append(TextComponent::text("!").hover(HoverEvent::ShowEntity {
+                entity_type: "minecraft:player".to_string(),
+                id: Uuid::from_u128(0x0123456789ABCDEF0123456789ABCDEF),
+                name: Some(Box::new("Steve".into())),
+            }))
+    }
+
+    #[test]
+    pub fn test_text_json() {
+        let json = TextComponent::text("Hello").color(NamedColor::Red).bold(true).to_json();
+        assert_eq!(json, serde_json::json!({ "text": "Hello", "color": "red", "bold": true }));
+
+        let component = rich();
+        let json = serde_json::to_string(&component).unwrap();
+        assert!(json.contains(r##""color":"#7F3FBF""##));
+        assert!(json.contains(r#""clickEvent":{"action":"open_url","value":"https://example.com"}"#));
+        assert_eq!(serde_json::from_str::<TextComponent>(&json).unwrap(), component);
+
+        // Shorthands
+        let component: TextComponent = serde_json::from_str(r#"["a", {"text": "b", "italic": true}]"#).unwrap();
+        assert_eq!(component, TextComponent::text("a").append(TextComponent::text("b").italic(true)));
+        assert_eq!(serde_json::from_str::<TextComponent>(r#""plain""#).unwrap(), TextComponent::text("plain"));
+        assert!(serde_json::from_str::<TextComponent>(r#"{"text": "a", "color": "pink"}"#).is_err());
+        assert!(serde_json::from_str::<TextComponent>("42").is_err());
+    }
+
+    #[test]
+    pub fn test_text_nbt() {
+        assert_eq!(TextComponent::text("Hi").to_nbt(), NbtTag::String("Hi".into()));
+
+        let component = rich();
+        let NbtTag::Compound(nbt) = component.to_nbt() else {
+            panic!("rich component is not a compound");
+        };
+        assert_eq!(nbt.string("color").unwrap().to_str(), "gray");
+        assert_eq!(TextComponent::from_nbt(&NbtTag::Compound(nbt)).unwrap(), component);
+
+        // Flags are bytes, and entity UUIDs usually int arrays
+        let nbt = NbtTag::Compound(simdnbt::owned::NbtCompound::from_values(vec![
+            ("text".into(), NbtTag::String("x".into())),
+            ("bold".into(), NbtTag::Byte(1)),
+            (
+                "hoverEvent".into(),
+                NbtTag::Compound(simdnbt::owned::NbtCompound::from_values(vec![
+                    ("action".into(), NbtTag::String("show_entity".into())),
+                    (
+                        "contents".into(),
+                        NbtTag::Compound(simdnbt::owned::NbtCompound::from_values(vec![
+                            ("type".into(), NbtTag::String("minecraft:pig".into())),
+                            ("id".into(), NbtTag::IntArray(vec![1, 2, 3, -1])),
+                        ])),
+                    ),
+                ])),
+            ),
+        ]));
+        let component = TextComponent::from_nbt(&nbt).unwrap();
+        assert_eq!(component.style.bold, Some(true));
+        match component.style.hover_event {
+            Some(HoverEvent::ShowEntity { id, .. }) => {
+                assert_eq!(id, Uuid::from_u128(0x00000001_00000002_00000003_FFFFFFFF))
+            }
+            event => panic!("unexpected hover event {:?}", event),
+        }
+    }
+
+    #[test]
+    pub fn test_text_legacy() {
+        let component = TextComponent::from_legacy("§6Void §lPOC§r, §x§cnow §nopen");
+        assert_eq!(component.to_plain(), "Void POC, now open");
+        assert_eq!(component.extra.len(), 5);
+        assert_eq!(component.extra[0].style.color, Some(TextColor::Named(NamedColor::Gold)));
+        // Decorations add up until a color or a reset
+        assert_eq!(component.extra[1].style.color, Some(TextColor::Named(NamedColor::Gold)));
+        assert_eq!(component.extra[1].style.bold, Some(true));
+        assert!(component.extra[2].style.is_empty());
+        assert_eq!(component.extra[4].style.underlined, Some(true));
+        assert_eq!(component.to_legacy(), "§6Void §6§lPOC§r, §cnow §c§nopen");
+
+        assert!(TextComponent::from_legacy("plain").is_plain());
+        assert_eq!(TextComponent::from_legacy("100§").content, TextContent::Text("100§".to_string()));
+        assert_eq!(TextComponent::text("x").color(TextColor::Rgb(0xFE5050)).to_legacy(), "§cx");
+        assert_eq!(rich().to_legacy(), "§7Welcome to §5§lvoid§7chat.type.text!");
+    }
+}
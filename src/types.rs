@@ -0,0 +1,190 @@
+use crate::DecodeError;
+use crate::item::SlotComponent;
+use std::fmt;
+
+// DATA TYPES //
+
+/// Block position, packed on the wire into a single long as
+/// x (26 bits), z (26 bits) then y (12 bits).
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32, z: i32) -> Position {
+        Position { x, y, z }
+    }
+
+    pub fn pack(&self) -> i64 {
+        ((self.x as i64 & 0x3FFFFFF) << 38) | ((self.z as i64 & 0x3FFFFFF) << 12) | (self.y as i64 & 0xFFF)
+    }
+
+    pub fn unpack(val: i64) -> Position {
+        // Arithmetic shifts sign extend each field
+        Position {
+            x: (val >> 38) as i32,
+            y: (val << 52 >> 52) as i32,
+            z: (val << 26 >> 38) as i32,
+        }
+    }
+}
+
+/// Rotation angle, in steps of 1/256 of a full turn.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct Angle(pub u8);
+
+impl Angle {
+    pub fn from_degrees(degrees: f32) -> Angle {
+        Angle((degrees.rem_euclid(360.0) * 256.0 / 360.0).round() as i32 as u8)
+    }
+
+    pub fn to_degrees(&self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+}
+
+/// Namespaced identifier such as `minecraft:overworld`.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Identifier {
+    pub namespace: String,
+    pub path: String,
+}
+
+impl Identifier {
+    pub const DEFAULT_NAMESPACE: &'static str = "minecraft";
+    pub const MAX_LEN: usize = 32767;
+
+    pub fn new(namespace: &str, path: &str) -> Result<Identifier, DecodeError> {
+        if !namespace.chars().all(Identifier::is_namespace_char) {
+            return Err(DecodeError::InvalidData(format!("invalid identifier namespace '{}'", namespace)));
+        }
+        if !path.chars().all(Identifier::is_path_char) {
+            return Err(DecodeError::InvalidData(format!("invalid identifier path '{}'", path)));
+        }
+        Ok(Identifier {
+            namespace: namespace.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Identifier in the `minecraft` namespace.
+    pub fn minecraft(path: &str) -> Result<Identifier, DecodeError> {
+        Identifier::new(Identifier::DEFAULT_NAMESPACE, path)
+    }
+
+    /// Parses `namespace:path`, or `path` alone in the `minecraft` namespace.
+    pub fn parse(val: &str) -> Result<Identifier, DecodeError> {
+        match val.split_once(':') {
+            Some((namespace, path)) => Identifier::new(namespace, path),
+            None => Identifier::minecraft(val),
+        }
+    }
+
+    fn is_namespace_char(c: char) -> bool {
+        matches!(c, 'a'..='z' | '0'..='9' | '.' | '-' | '_')
+    }
+
+    fn is_path_char(c: char) -> bool {
+        Identifier::is_namespace_char(c) || c == '/'
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+/// Three floats, as used by entity metadata.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Rotation quaternion, as used by display entity metadata.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        // Identity rotation
+        Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+}
+
+/// Item stack in an inventory slot. A count of zero is an empty slot.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Slot {
+    pub item_count: i32,
+    pub item_id: i32,
+    pub components_to_add: Vec<SlotComponent>,
+    pub components_to_remove: Vec<i32>,
+}
+
+impl Slot {
+    pub fn empty() -> Slot {
+        Slot::default()
+    }
+
+    pub fn new(item_id: i32, item_count: i32) -> Slot {
+        Slot {
+            item_count,
+            item_id,
+            components_to_add: Vec::new(),
+            components_to_remove: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item_count <= 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Angle, Identifier, Position};
+
+    #[test]
+    pub fn test_types_position_pack() {
+        // Example from the protocol documentation
+        let pos = Position::new(18357644, 831, -20882616);
+        assert_eq!(pos.pack(), 0x4607_632C_15B4_833F);
+        assert_eq!(Position::unpack(pos.pack()), pos);
+    }
+
+    #[test]
+    pub fn test_types_angle_degrees() {
+        assert_eq!(Angle::from_degrees(90.0), Angle(64));
+        assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+        assert_eq!(Angle::from_degrees(360.0), Angle(0));
+        assert_eq!(Angle(128).to_degrees(), 180.0);
+    }
+
+    #[test]
+    pub fn test_types_identifier_parse() {
+        let id = Identifier::parse("minecraft:overworld").unwrap();
+        assert_eq!(id.namespace, "minecraft");
+        assert_eq!(id.path, "overworld");
+        assert_eq!(Identifier::parse("stone").unwrap().to_string(), "minecraft:stone");
+        assert_eq!(Identifier::parse("voidpoc:worlds/void").unwrap().path, "worlds/void");
+
+        assert!(Identifier::parse("Minecraft:stone").is_err());
+        assert!(Identifier::parse("minecraft:stone block").is_err());
+        assert!(Identifier::parse("mine/craft:stone").is_err());
+    }
+}
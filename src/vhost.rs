@@ -0,0 +1,157 @@
+use crate::config::ServerConfig;
+use crate::favicon::Favicon;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// VIRTUAL HOSTS //
+
+/// Hostname matched against the virtual hosts, from the handshake
+/// `server_address`. Forge appends `\0FML\0` (`FML2`, `FML3` in later
+/// versions) and fully qualified names end with a dot, both are dropped.
+pub fn normalize_host(server_address: &str) -> String {
+    let host = server_address.split('\0').next().unwrap_or_default();
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Server as seen by players connecting with one of its hostnames.
+pub struct VirtualHost {
+    /// Normalized hostname, `None` for the default host
+    pub name: Option<String>,
+    pub motd: String,
+    pub favicon: Favicon,
+    pub max_players: i32,
+    pub whitelist: Option<Vec<String>>,
+    pub world: String,
+}
+
+impl VirtualHost {
+    /// Whether the whitelist, if any, lets `username` in.
+    pub fn allows(&self, username: &str) -> bool {
+        match &self.whitelist {
+            Some(names) => names.iter().any(|name| name.eq_ignore_ascii_case(username)),
+            None => true,
+        }
+    }
+}
+
+/// Virtual hosts of the config, by hostname.
+pub struct VirtualHosts {
+    default: Arc<VirtualHost>,
+    hosts: HashMap<String, Arc<VirtualHost>>,
+    // Hosts using the same icon file share its favicon
+    favicons: HashMap<String, Favicon>,
+}
+
+impl VirtualHosts {
+    /// Icons are empty until loaded, see `favicons`.
+    pub fn new(config: &ServerConfig) -> VirtualHosts {
+        let mut favicons = HashMap::new();
+        let mut favicon = |path: &String| favicons.entry(path.clone()).or_insert_with(|| Favicon::new(path)).clone();
+
+        let default = VirtualHost {
+            name: None,
+            motd: config.motd.clone(),
+            favicon: favicon(&config.icon),
+            max_players: config.max_players,
+            whitelist: config.whitelist.clone(),
+            world: config.world.clone(),
+        };
+        let hosts = config
+            .virtual_hosts
+            .iter()
+            .map(|(name, host)| {
+                let name = normalize_host(name);
+                let host = VirtualHost {
+                    name: Some(name.clone()),
+                    motd: host.motd.clone().unwrap_or_else(|| default.motd.clone()),
+                    favicon: favicon(host.icon.as_ref().unwrap_or(&config.icon)),
+                    max_players: host.max_players.unwrap_or(default.max_players),
+                    whitelist: host.whitelist.clone().or_else(|| default.whitelist.clone()),
+                    world: host.world.clone().unwrap_or_else(|| default.world.clone()),
+                };
+                (name, Arc::new(host))
+            })
+            .collect();
+
+        VirtualHosts {
+            default: Arc::new(default),
+            hosts,
+            favicons,
+        }
+    }
+
+    /// Host used before the handshake, and for hostnames without a virtual host.
+    pub fn default_host(&self) -> Arc<VirtualHost> {
+        self.default.clone()
+    }
+
+    /// Virtual host of the `server_address` sent in the handshake.
+    pub fn resolve(&self, server_address: &str) -> Arc<VirtualHost> {
+        self.hosts.get(&normalize_host(server_address)).unwrap_or(&self.default).clone()
+    }
+
+    /// Icon of every host, each file once.
+    pub fn favicons(&self) -> impl Iterator<Item = &Favicon> {
+        self.favicons.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{ServerConfig, VirtualHostConfig};
+    use crate::vhost::{self, VirtualHosts};
+
+    #[test]
+    pub fn test_vhost_normalize() {
+        assert_eq!(vhost::normalize_host("play.example.com"), "play.example.com");
+        assert_eq!(vhost::normalize_host("Play.Example.com."), "play.example.com");
+        assert_eq!(vhost::normalize_host("play.example.com\0FML\0"), "play.example.com");
+        assert_eq!(vhost::normalize_host("play.example.com.\0FML3\0"), "play.example.com");
+        assert_eq!(vhost::normalize_host("127.0.0.1"), "127.0.0.1");
+    }
+
+    #[test]
+    pub fn test_vhost_resolve() {
+        let mut config = ServerConfig {
+            motd: "Main".to_string(),
+            whitelist: Some(vec!["Steve".to_string()]),
+            ..ServerConfig::default()
+        };
+        config.virtual_hosts.insert(
+            "Lobby.example.com.".to_string(),
+            VirtualHostConfig {
+                motd: Some("Lobby".to_string()),
+                max_players: Some(10),
+                whitelist: Some(vec!["Alex".to_string()]),
+                ..VirtualHostConfig::default()
+            },
+        );
+        config.virtual_hosts.insert(
+            "build.example.com".to_string(),
+            VirtualHostConfig {
+                icon: Some("build.png".to_string()),
+                world: Some("voidpoc:build".to_string()),
+                ..VirtualHostConfig::default()
+            },
+        );
+        let hosts = VirtualHosts::new(&config);
+
+        let lobby = hosts.resolve("lobby.example.com\0FML2\0");
+        assert_eq!(lobby.name.as_deref(), Some("lobby.example.com"));
+        assert_eq!((lobby.motd.as_str(), lobby.max_players), ("Lobby", 10));
+        assert!(lobby.allows("alex") && !lobby.allows("Steve"));
+
+        // Unset settings are those of the server
+        let build = hosts.resolve("build.example.com");
+        assert_eq!((build.motd.as_str(), build.max_players), ("Main", 100));
+        assert_eq!(build.world, "voidpoc:build");
+        assert!(build.allows("Steve") && !build.allows("Alex"));
+        assert_eq!(build.favicon.path().to_str(), Some("build.png"));
+        assert_eq!(hosts.favicons().count(), 2);
+
+        let default = hosts.resolve("mc.example.com");
+        assert!(default.name.is_none());
+        assert_eq!(default.world, "minecraft:overworld");
+        assert_eq!(default.favicon.path(), lobby.favicon.path());
+    }
+}
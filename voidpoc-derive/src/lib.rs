@@ -0,0 +1,381 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Field, Fields, GenericArgument, Ident, LitStr, PathArguments, Type, parse_macro_input};
+
+// DERIVE //
+
+const STATES: [&str; 5] = ["Handshake", "Status", "Login", "Configuration", "Play"];
+const BOUNDS: [&str; 2] = ["Client", "Server"];
+
+/// Implements `voidpoc::packet::Packet`, encoding and decoding the fields in
+/// declaration order. The id is taken from `voidpoc::ids` by resource name.
+///
+/// ```ignore
+/// #[derive(Packet)]
+/// #[packet(name = "minecraft:login", state = Play, bound = Client)]
+/// pub struct ClientBoundPlayLoginPacket {
+///     pub entity_id: i32,
+///     #[packet(length_prefixed)]
+///     pub dimension_names: Vec<String>,
+///     #[packet(varint)]
+///     pub max_players: i32,
+/// }
+/// ```
+///
+/// Fields use their `PacketField` implementation unless one of these is given:
+/// - `varint` / `varlong`: an `i32` / `i64` sent as a VarInt / VarLong
+/// - `length_prefixed`: a `Vec` sent as a VarInt length then its elements
+/// - `rest`: a `Vec<u8>` taking everything until the end of the packet
+/// - `max_len = N`: a `String` of at most `N` bytes
+/// - `json`: a serde type sent as a JSON string
+/// - `since = V1_21_3` / `until = V1_21_1`: only sent from / up to a
+///   `ProtocolVersion`, left to its default value otherwise
+///
+/// `Option`s are sent as a boolean then the value. Attributes apply to the
+/// elements of `Vec`s and `Option`s.
+#[proc_macro_derive(Packet, attributes(packet))]
+pub fn derive_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_packet(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// Implements `voidpoc::field::PacketField` for a struct, with the same field
+/// attributes as `#[derive(Packet)]` except `since` and `until`.
+#[proc_macro_derive(PacketField, attributes(packet))]
+pub fn derive_packet_field(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_packet_field(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+struct PacketAttrs {
+    name: LitStr,
+    state: Ident,
+    bound: Ident,
+}
+
+fn parse_packet_attrs(input: &DeriveInput) -> syn::Result<PacketAttrs> {
+    let mut name = None;
+    let mut state = None;
+    let mut bound = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("packet")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("state") {
+                let ident = meta.value()?.parse::<Ident>()?;
+                if !STATES.contains(&ident.to_string().as_str()) {
+                    return Err(syn::Error::new(ident.span(), format!("state must be one of {}", STATES.join(", "))));
+                }
+                state = Some(ident);
+            } else if meta.path.is_ident("bound") {
+                let ident = meta.value()?.parse::<Ident>()?;
+                if !BOUNDS.contains(&ident.to_string().as_str()) {
+                    return Err(syn::Error::new(ident.span(), format!("bound must be one of {}", BOUNDS.join(", "))));
+                }
+                bound = Some(ident);
+            } else {
+                return Err(meta.error("expected `name`, `state` or `bound`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let missing = |name| syn::Error::new(input.ident.span(), format!("missing `#[packet({} = ...)]`", name));
+    Ok(PacketAttrs {
+        name: name.ok_or_else(|| missing("name"))?,
+        state: state.ok_or_else(|| missing("state"))?,
+        bound: bound.ok_or_else(|| missing("bound"))?,
+    })
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    varint: bool,
+    varlong: bool,
+    length_prefixed: bool,
+    rest: bool,
+    json: bool,
+    max_len: Option<Expr>,
+    since: Option<Ident>,
+    until: Option<Ident>,
+}
+
+fn parse_field_attrs(field: &Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("packet")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("varint") {
+                attrs.varint = true;
+            } else if meta.path.is_ident("varlong") {
+                attrs.varlong = true;
+            } else if meta.path.is_ident("length_prefixed") {
+                attrs.length_prefixed = true;
+            } else if meta.path.is_ident("rest") {
+                attrs.rest = true;
+            } else if meta.path.is_ident("json") {
+                attrs.json = true;
+            } else if meta.path.is_ident("max_len") {
+                attrs.max_len = Some(meta.value()?.parse::<Expr>()?);
+            } else if meta.path.is_ident("since") {
+                attrs.since = Some(meta.value()?.parse::<Ident>()?);
+            } else if meta.path.is_ident("until") {
+                attrs.until = Some(meta.value()?.parse::<Ident>()?);
+            } else {
+                return Err(meta.error("expected `varint`, `varlong`, `length_prefixed`, `rest`, `json`, `max_len`, `since` or `until`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<Vec<&Field>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.named.iter().collect()),
+            Fields::Unit => Ok(Vec::new()),
+            Fields::Unnamed(_) => Err(syn::Error::new(input.ident.span(), "tuple structs are not supported")),
+        },
+        _ => Err(syn::Error::new(input.ident.span(), "only structs are supported")),
+    }
+}
+
+/// Returns the `T` of `Wrapper<T>` when `ty` is a `Wrapper`.
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
+
+/// Statement writing `value`, a reference to a `ty`, into `buf`.
+fn write_field(ty: &Type, attrs: &FieldAttrs, value: TokenStream2) -> syn::Result<TokenStream2> {
+    if attrs.rest {
+        if !inner_type(ty, "Vec").is_some_and(is_u8) {
+            return Err(syn::Error::new(ty.span(), "`rest` is only supported on `Vec<u8>`"));
+        }
+        return Ok(quote! { buf.write_buf(#value); });
+    }
+    if let Some(inner) = inner_type(ty, "Vec") {
+        if !attrs.length_prefixed {
+            return Err(syn::Error::new(ty.span(), "`Vec` fields need `#[packet(length_prefixed)]` or `#[packet(rest)]`"));
+        }
+        if is_u8(inner) {
+            return Ok(quote! { buf.write_byte_array(#value); });
+        }
+        let write = write_field(inner, attrs, quote! { val })?;
+        return Ok(quote! { buf.write_prefixed_array(#value, |buf, val| { #write }); });
+    }
+    if let Some(inner) = inner_type(ty, "Option") {
+        let write = write_field(inner, attrs, quote! { val })?;
+        return Ok(quote! { buf.write_prefixed_option((#value).as_ref(), |buf, val| { #write }); });
+    }
+
+    Ok(if attrs.varint {
+        quote! { buf.write_varint(*#value); }
+    } else if attrs.varlong {
+        quote! { buf.write_varlong(*#value); }
+    } else if attrs.json {
+        quote! { buf.write_json(#value); }
+    } else {
+        quote! { ::voidpoc::field::PacketField::write_to(#value, buf); }
+    })
+}
+
+/// Expression reading a `ty` from `buf`, evaluating to a `Result`.
+fn read_field(ty: &Type, attrs: &FieldAttrs) -> syn::Result<TokenStream2> {
+    if attrs.rest {
+        return Ok(quote! { Ok(buf.read_buf()) });
+    }
+    if let Some(inner) = inner_type(ty, "Vec") {
+        if is_u8(inner) {
+            return Ok(quote! { buf.read_byte_array() });
+        }
+        let read = read_field(inner, attrs)?;
+        return Ok(quote! { buf.read_prefixed_array(|buf| #read) });
+    }
+    if let Some(inner) = inner_type(ty, "Option") {
+        let read = read_field(inner, attrs)?;
+        return Ok(quote! { buf.read_prefixed_option(|buf| #read) });
+    }
+
+    Ok(if attrs.varint {
+        quote! { buf.read_varint() }
+    } else if attrs.varlong {
+        quote! { buf.read_varlong() }
+    } else if attrs.json {
+        quote! { buf.read_json() }
+    } else if let Some(max_len) = &attrs.max_len {
+        quote! { buf.read_string_max(#max_len) }
+    } else {
+        quote! { <#ty as ::voidpoc::field::PacketField>::read_from(buf) }
+    })
+}
+
+/// Condition on `version` for a field sent by some protocol versions only.
+fn version_condition(attrs: &FieldAttrs) -> Option<TokenStream2> {
+    let since = attrs.since.as_ref().map(|since| quote! { version >= ::voidpoc::protocol::ProtocolVersion::#since });
+    let until = attrs.until.as_ref().map(|until| quote! { version <= ::voidpoc::protocol::ProtocolVersion::#until });
+    match (since, until) {
+        (Some(since), Some(until)) => Some(quote! { #since && #until }),
+        (since, until) => since.or(until),
+    }
+}
+
+struct FieldCode {
+    names: Vec<Ident>,
+    writes: Vec<TokenStream2>,
+    reads: Vec<TokenStream2>,
+    conditions: Vec<Option<TokenStream2>>,
+}
+
+fn field_code(input: &DeriveInput) -> syn::Result<FieldCode> {
+    let mut code = FieldCode {
+        names: Vec::new(),
+        writes: Vec::new(),
+        reads: Vec::new(),
+        conditions: Vec::new(),
+    };
+    for field in named_fields(input)? {
+        let name = field.ident.clone().expect("named fields have an ident");
+        let attrs = parse_field_attrs(field)?;
+        code.writes.push(write_field(&field.ty, &attrs, quote! { &self.#name })?);
+        code.reads.push(read_field(&field.ty, &attrs)?);
+        code.conditions.push(version_condition(&attrs));
+        code.names.push(name);
+    }
+    Ok(code)
+}
+
+/// Path of the generated `voidpoc::ids` constant of a packet, as
+/// `::voidpoc::ids::play::clientbound::KEEP_ALIVE` for `minecraft:keep_alive`.
+fn id_path(name: &LitStr, state: &Ident, bound: &Ident) -> syn::Result<TokenStream2> {
+    let value = name.value();
+    let path = value.strip_prefix("minecraft:").unwrap_or(&value);
+    if path.is_empty() || !path.chars().all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_' | '/' | '.' | '-' | ':')) {
+        return Err(syn::Error::new(name.span(), "expected a resource name such as `minecraft:keep_alive`"));
+    }
+
+    let module = Ident::new(&state.to_string().to_lowercase(), state.span());
+    let direction = Ident::new(&format!("{}bound", bound.to_string().to_lowercase()), bound.span());
+    let constant = Ident::new(&path.replace(['/', '.', '-', ':'], "_").to_uppercase(), name.span());
+    Ok(quote! { ::voidpoc::ids::#module::#direction::#constant })
+}
+
+fn expand_packet(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let PacketAttrs { name, state, bound } = parse_packet_attrs(input)?;
+    let id = id_path(&name, &state, &bound)?;
+    let FieldCode {
+        names,
+        writes,
+        reads,
+        conditions,
+    } = field_code(input)?;
+    let writes = writes.iter().zip(&conditions).map(|(write, condition)| match condition {
+        Some(condition) => quote! { if #condition { #write } },
+        None => write.clone(),
+    });
+    let reads = names.iter().zip(reads.iter().zip(&conditions)).map(|(name, (read, condition))| match condition {
+        Some(condition) => quote! { if #condition { self.#name = #read?; } },
+        None => quote! { self.#name = #read?; },
+    });
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub const ID: ::voidpoc::PacketType = #id;
+            pub const NAME: &'static str = #name;
+            pub const STATE: ::voidpoc::packet::PacketStatus = ::voidpoc::packet::PacketStatus::#state;
+            pub const BOUND: ::voidpoc::packet::PacketBound = ::voidpoc::packet::PacketBound::#bound;
+        }
+
+        impl #impl_generics ::voidpoc::packet::Packet for #ident #ty_generics #where_clause {
+            fn id(&self) -> ::voidpoc::PacketType {
+                Self::ID
+            }
+
+            fn name(&self) -> &'static str {
+                Self::NAME
+            }
+
+            fn state(&self) -> ::voidpoc::packet::PacketStatus {
+                Self::STATE
+            }
+
+            fn bound(&self) -> ::voidpoc::packet::PacketBound {
+                Self::BOUND
+            }
+
+            fn encode(&self, buf: &mut ::voidpoc::ByteBuf) {
+                self.encode_for(buf, ::voidpoc::protocol::ProtocolVersion::LATEST);
+            }
+
+            fn decode(&mut self, buf: &mut ::voidpoc::ByteBuf) -> ::std::result::Result<(), ::voidpoc::DecodeError> {
+                self.decode_for(buf, ::voidpoc::protocol::ProtocolVersion::LATEST)
+            }
+
+            #[allow(unused_variables)]
+            fn encode_for(&self, buf: &mut ::voidpoc::ByteBuf, version: ::voidpoc::protocol::ProtocolVersion) {
+                #(#writes)*
+            }
+
+            #[allow(unused_variables)]
+            fn decode_for(
+                &mut self,
+                buf: &mut ::voidpoc::ByteBuf,
+                version: ::voidpoc::protocol::ProtocolVersion,
+            ) -> ::std::result::Result<(), ::voidpoc::DecodeError> {
+                #(#reads)*
+                Ok(())
+            }
+        }
+    })
+}
+
+fn expand_packet_field(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let FieldCode {
+        names,
+        writes,
+        reads,
+        conditions,
+    } = field_code(input)?;
+    if conditions.iter().any(Option::is_some) {
+        return Err(syn::Error::new(input.ident.span(), "`since` and `until` are only supported by `#[derive(Packet)]`"));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::voidpoc::field::PacketField for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn write_to(&self, buf: &mut ::voidpoc::ByteBuf) {
+                #(#writes)*
+            }
+
+            #[allow(unused_variables)]
+            fn read_from(buf: &mut ::voidpoc::ByteBuf) -> ::std::result::Result<Self, ::voidpoc::DecodeError> {
+                Ok(Self {
+                    #(#names: #reads?,)*
+                })
+            }
+        }
+    })
+}